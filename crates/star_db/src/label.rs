@@ -0,0 +1,196 @@
+use std::fmt;
+
+/// A Bazel label, such as `@repo//pkg:target`, `//pkg:file.bzl` or `:local`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Label {
+    /// The name of the repository, without the leading `@`. This is `None` if the label
+    /// doesn't name a repository, in which case it refers to the repository containing
+    /// the file it appears in, and `Some("")` for the main repository, as in `@//pkg`.
+    pub repo: Option<String>,
+    /// The package, relative to the root of the repository. This is `None` for labels
+    /// relative to the package they appear in, such as `:local`.
+    pub package: Option<String>,
+    /// The name of the target, which may contain slashes.
+    pub name: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LabelError {
+    InvalidRepository,
+    InvalidPackage,
+    InvalidName,
+}
+
+impl fmt::Display for LabelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LabelError::InvalidRepository => "invalid repository name",
+            LabelError::InvalidPackage => "invalid package name",
+            LabelError::InvalidName => "invalid target name",
+        })
+    }
+}
+
+impl std::error::Error for LabelError {}
+
+impl Label {
+    pub fn parse(text: &str) -> Result<Label, LabelError> {
+        let (repo, rest) = match text.strip_prefix('@') {
+            Some(rest) => {
+                // Canonical repository names are written with two `@`s.
+                let rest = rest.strip_prefix('@').unwrap_or(rest);
+                match rest.find("//") {
+                    Some(index) => (Some(&rest[..index]), &rest[index..]),
+                    // `@repo` is short for `@repo//:repo`.
+                    None => {
+                        check_repo(rest)?;
+                        if rest.is_empty() {
+                            return Err(LabelError::InvalidRepository);
+                        }
+                        return Ok(Label {
+                            repo: Some(rest.to_string()),
+                            package: Some(String::new()),
+                            name: rest.to_string(),
+                        });
+                    }
+                }
+            }
+            None => (None, text),
+        };
+        if let Some(repo) = repo {
+            check_repo(repo)?;
+        }
+
+        let (package, name) = match rest.strip_prefix("//") {
+            Some(rest) => match rest.split_once(':') {
+                Some((package, name)) => (Some(package), name),
+                // `//pkg/sub` is short for `//pkg/sub:sub`.
+                None => (Some(rest), rest.rsplit('/').next().unwrap()),
+            },
+            None => (None, rest.strip_prefix(':').unwrap_or(rest)),
+        };
+        if let Some(package) = package {
+            check_package(package)?;
+        }
+        check_name(name)?;
+
+        Ok(Label {
+            repo: repo.map(str::to_string),
+            package: package.map(str::to_string),
+            name: name.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(repo) = &self.repo {
+            write!(f, "@{}", repo)?;
+        }
+        if let Some(package) = &self.package {
+            write!(f, "//{}", package)?;
+        }
+        write!(f, ":{}", self.name)
+    }
+}
+
+fn check_repo(repo: &str) -> Result<(), LabelError> {
+    let valid = repo
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '~' | '+'));
+    if valid {
+        Ok(())
+    } else {
+        Err(LabelError::InvalidRepository)
+    }
+}
+
+fn check_package(package: &str) -> Result<(), LabelError> {
+    if package.is_empty() {
+        return Ok(());
+    }
+    if package.contains(':') || !valid_segments(package) {
+        return Err(LabelError::InvalidPackage);
+    }
+    Ok(())
+}
+
+fn check_name(name: &str) -> Result<(), LabelError> {
+    if name.is_empty() || name.contains(':') || !valid_segments(name) {
+        return Err(LabelError::InvalidName);
+    }
+    Ok(())
+}
+
+/// Checks that a path has no empty, `.` or `..` segments, which also rules out leading and
+/// trailing slashes.
+fn valid_segments(path: &str) -> bool {
+    path.split('/')
+        .all(|segment| !matches!(segment, "" | "." | ".."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(repo: Option<&str>, package: Option<&str>, name: &str) -> Label {
+        Label {
+            repo: repo.map(str::to_string),
+            package: package.map(str::to_string),
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let cases = [
+            ("//pkg:file.bzl", label(None, Some("pkg"), "file.bzl")),
+            (
+                "//pkg/sub:dir/file.bzl",
+                label(None, Some("pkg/sub"), "dir/file.bzl"),
+            ),
+            ("//:defs.bzl", label(None, Some(""), "defs.bzl")),
+            ("//pkg/sub", label(None, Some("pkg/sub"), "sub")),
+            (":local", label(None, None, "local")),
+            ("local.bzl", label(None, None, "local.bzl")),
+            (
+                "@repo//pkg:target",
+                label(Some("repo"), Some("pkg"), "target"),
+            ),
+            (
+                "@@canonical~1.0//:target",
+                label(Some("canonical~1.0"), Some(""), "target"),
+            ),
+            ("@//pkg:target", label(Some(""), Some("pkg"), "target")),
+            ("@repo", label(Some("repo"), Some(""), "repo")),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(Label::parse(text), Ok(expected), "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        let cases = [
+            ("@", LabelError::InvalidRepository),
+            ("@re po//:x", LabelError::InvalidRepository),
+            ("///pkg:x", LabelError::InvalidPackage),
+            ("//pkg/:x", LabelError::InvalidPackage),
+            ("//pkg/../other:x", LabelError::InvalidPackage),
+            ("//pkg:", LabelError::InvalidName),
+            ("//pkg:a:b", LabelError::InvalidName),
+            ("", LabelError::InvalidName),
+            (":../x", LabelError::InvalidName),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(Label::parse(text), Err(expected), "{}", text);
+        }
+    }
+
+    #[test]
+    fn display() {
+        for text in ["@repo//pkg:target", "//pkg:file.bzl", ":local", "@//:x"] {
+            assert_eq!(Label::parse(text).unwrap().to_string(), text);
+        }
+    }
+}
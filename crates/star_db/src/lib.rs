@@ -1,81 +1,401 @@
+mod label;
+mod load;
+mod vfs;
+mod workspace;
+
+pub use crate::{
+    label::{Label, LabelError},
+    load::{resolve_load, resolve_loads, Load, LoadError},
+    vfs::Vfs,
+    workspace::{
+        dialect, is_bazel_file, local_repositories, repository_roots, Package, Workspace,
+        BUILD_FILES, WORKSPACE_FILES,
+    },
+};
+
 use salsa::{Database, Durability, ParallelDatabase};
-use star_syntax::{lines::Lines, parse_file, Parse};
+use star_syntax::{lines::Lines, parse_file, Parse, TextEdit, TextRange, TextSize};
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::{BTreeMap, BTreeSet, HashSet},
     hash::Hash,
-    sync::{Arc, Mutex},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
 };
 
 #[salsa::jar(db = Db)]
-pub struct Jar(lines, parse, File);
+pub struct Jar(
+    lines,
+    parse,
+    File,
+    Workspace,
+    repository_roots,
+    resolve_loads,
+);
+
+pub trait Db: salsa::DbWithJar<Jar> {
+    fn inputs(&self) -> &Inputs;
+}
 
-pub trait Db: salsa::DbWithJar<Jar> {}
+#[salsa::input]
+pub struct File {
+    #[return_ref]
+    path: PathBuf,
+    /// Whether the file exists, either on disk or in the editor. Files that don't exist
+    /// have empty text.
+    exists: bool,
+    #[return_ref]
+    text: String,
+    /// The parse of `text`, if it was reparsed incrementally after edits in the editor
+    /// instead of by [`parse`].
+    #[return_ref]
+    reparsed: Option<Parse>,
+}
 
-#[derive(Default)]
-#[salsa::db(Jar)]
-pub struct RootDatabase {
-    storage: salsa::Storage<Self>,
+/// The files known to a database and the workspace they belong to. Databases share
+/// these with their snapshots.
+///
+/// Which paths are interned isn't tracked by salsa, only their contents are, so queries
+/// never intern files: a path that hasn't been interned is treated as missing until it is
+/// read with [`SourceDatabase::read_files`].
+#[derive(Clone, Default)]
+pub struct Inputs {
+    vfs: Arc<Mutex<Vfs>>,
+    workspace: Arc<Mutex<Option<Workspace>>>,
 }
 
-impl Db for RootDatabase {}
+impl Inputs {
+    pub fn vfs(&self) -> MutexGuard<'_, Vfs> {
+        self.vfs.lock().unwrap()
+    }
 
-impl salsa::Database for RootDatabase {}
+    /// Returns the file at `path`, if it has been interned.
+    pub fn file(&self, path: &Path) -> Option<File> {
+        self.vfs().file(path)
+    }
 
-impl salsa::ParallelDatabase for RootDatabase {
-    fn snapshot(&self) -> salsa::Snapshot<Self> {
-        salsa::Snapshot::new(RootDatabase {
-            storage: self.storage.snapshot(),
-        })
+    /// Returns the workspace input, which is created along with the database.
+    pub fn workspace(&self) -> Workspace {
+        self.workspace
+            .lock()
+            .unwrap()
+            .expect("databases are created by `SourceDatabase::default`")
     }
 }
 
-#[salsa::input]
-pub struct File {
-    #[return_ref]
-    text: String,
+/// Tracks the files known to the database. `DB` is the database combining the jars of
+/// all crates, which lives in the highest-level crate that defines a jar.
+///
+/// Setting an input waits for snapshots to be dropped, so none of the methods hold the
+/// lock on the VFS, which snapshots take to intern files, while setting one.
+pub struct SourceDatabase<DB> {
+    pub db: DB,
 }
 
-#[derive(Default)]
-pub struct SourceDatabase {
-    pub db: RootDatabase,
-    pub files: Arc<Mutex<HashMap<String, File>>>,
+impl<DB: Db + Default> Default for SourceDatabase<DB> {
+    fn default() -> Self {
+        let db = DB::default();
+        let workspace = Workspace::new(
+            &db,
+            None,
+            BTreeMap::new(),
+            BTreeSet::new(),
+            Vec::new(),
+            None,
+        );
+        *db.inputs().workspace.lock().unwrap() = Some(workspace);
+        SourceDatabase { db }
+    }
 }
 
-impl SourceDatabase {
-    pub fn set_file_text(&mut self, path: String, text: String) {
-        match self.files.lock().unwrap().entry(path) {
-            Entry::Occupied(entry) => {
-                entry.get().set_text(&mut self.db).to(text);
+impl<DB: Db + ParallelDatabase> SourceDatabase<DB> {
+    /// Sets the contents of the file at `path` as the editor sees them. They shadow the
+    /// contents on disk until [`SourceDatabase::remove_overlay`] is called.
+    pub fn set_overlay(&mut self, path: &Path, text: String) {
+        let file = {
+            let mut vfs = self.db.inputs().vfs();
+            let file = vfs.intern(&self.db, path);
+            vfs.add_overlay(file);
+            file
+        };
+        self.set_contents(file, Some(text));
+    }
+
+    /// Sets the contents of the file at `path` as the editor sees them, after `edits`
+    /// were made to the editor's previous contents, in order. The file is reparsed
+    /// incrementally from its previous parse; otherwise this is the same as
+    /// [`SourceDatabase::set_overlay`].
+    pub fn edit_overlay(&mut self, path: &Path, text: String, edits: &[TextEdit]) {
+        let (file, overlaid) = {
+            let mut vfs = self.db.inputs().vfs();
+            let file = vfs.intern(&self.db, path);
+            let overlaid = vfs.is_overlaid(file);
+            vfs.add_overlay(file);
+            (file, overlaid)
+        };
+        // The edits were made to the editor's contents, which are only known if the file
+        // was already open.
+        let old_len = TextSize::of(file.text(&self.db).as_str());
+        let reparsed = merge_edits(edits, &text)
+            .filter(|edit| {
+                overlaid
+                    && edit.delete.end() <= old_len
+                    && old_len - edit.delete.len() + TextSize::of(edit.insert.as_str())
+                        == TextSize::of(text.as_str())
+            })
+            .map(|edit| parse(&self.db, file).reparse(&edit, &text));
+        self.set_text(file, Some(text));
+        self.set_reparsed(file, reparsed);
+    }
+
+    /// Drops the editor's contents of the file at `path`, going back to the contents
+    /// on disk.
+    pub fn remove_overlay(&mut self, path: &Path) {
+        let file = {
+            let mut vfs = self.db.inputs().vfs();
+            match vfs.file(path) {
+                Some(file) if vfs.remove_overlay(file) => file,
+                _ => return,
+            }
+        };
+        self.set_contents(file, vfs::read(path));
+    }
+
+    /// Rereads the file at `path` after it changed on disk. Files open in the editor
+    /// keep the editor's contents.
+    pub fn reload_file(&mut self, path: &Path) {
+        let file = {
+            let vfs = self.db.inputs().vfs();
+            match vfs.file(path) {
+                Some(file) if !vfs.is_overlaid(file) => file,
+                _ => return,
+            }
+        };
+        self.set_contents(file, vfs::read(path));
+    }
+
+    fn set_contents(&mut self, file: File, text: Option<String>) {
+        self.set_text(file, text);
+        self.set_reparsed(file, None);
+    }
+
+    fn set_text(&mut self, file: File, text: Option<String>) {
+        if file.exists(&self.db) != text.is_some() {
+            file.set_exists(&mut self.db).to(text.is_some());
+        }
+        file.set_text(&mut self.db).to(text.unwrap_or_default());
+    }
+
+    fn set_reparsed(&mut self, file: File, reparsed: Option<Parse>) {
+        if reparsed.is_some() || file.reparsed(&self.db).is_some() {
+            file.set_reparsed(&mut self.db).to(reparsed);
+        }
+    }
+
+    /// Returns the file at `path`, if it has been interned.
+    pub fn file(&self, path: &Path) -> Option<File> {
+        self.db.inputs().file(path)
+    }
+
+    /// Returns the current text of the file at `path`, if it exists.
+    pub fn file_text(&self, path: &Path) -> Option<String> {
+        let file = self.file(path)?;
+        file.exists(&self.db).then(|| file.text(&self.db).clone())
+    }
+
+    /// Returns the workspace, if one has been loaded.
+    pub fn workspace(&self) -> Option<Workspace> {
+        let workspace = self.db.inputs().workspace();
+        workspace.root(&self.db).is_some().then_some(workspace)
+    }
+
+    /// Sets the root and the repositories of the workspace. Its packages and files are
+    /// added as they are found.
+    pub fn set_workspace(&mut self, root: PathBuf, repositories: BTreeMap<String, PathBuf>) {
+        let workspace = self.db.inputs().workspace();
+        workspace.set_root(&mut self.db).to(Some(root));
+        workspace.set_repositories(&mut self.db).to(repositories);
+    }
+
+    /// Sets the root of the Buck project the editor opened, if it is in one.
+    pub fn set_buck_root(&mut self, root: Option<PathBuf>) {
+        let workspace = self.db.inputs().workspace();
+        if *workspace.buck_root(&self.db) != root {
+            workspace.set_buck_root(&mut self.db).to(root);
+        }
+    }
+
+    pub fn set_packages(&mut self, packages: BTreeSet<PathBuf>) {
+        let workspace = self.db.inputs().workspace();
+        workspace.set_packages(&mut self.db).to(packages);
+    }
+
+    /// Adds files read from disk to the workspace. Files open in the editor keep the
+    /// editor's contents.
+    ///
+    /// Like all file contents, these are inputs of low durability. Files that haven't been
+    /// interned before are created without starting a new revision, so only the update to
+    /// the workspace's list of files cancels running queries.
+    pub fn add_files(&mut self, files: Vec<(PathBuf, String)>) {
+        let mut added = Vec::new();
+        for (path, text) in files {
+            let (file, text) = {
+                let mut vfs = self.db.inputs().vfs();
+                match vfs.file(&path) {
+                    Some(file) if !vfs.is_overlaid(file) => (file, Some(text)),
+                    Some(file) => (file, None),
+                    None => (vfs.insert(&self.db, path, text), None),
+                }
+            };
+            // Files interned before may have been read before they last changed.
+            if let Some(text) = text {
+                if !file.exists(&self.db) || *file.text(&self.db) != text {
+                    self.set_contents(file, Some(text));
+                }
             }
-            Entry::Vacant(entry) => {
-                entry.insert(File::new(&self.db, text));
+            added.push(file);
+        }
+        self.add_to_workspace(added);
+    }
+
+    /// Reads the files at `paths`, which queries looked for before they were interned,
+    /// such as the targets of `load` statements outside the indexed workspace. The ones
+    /// that exist are added to the workspace's files, which is what those queries depend
+    /// on to notice them.
+    pub fn read_files(&mut self, paths: Vec<PathBuf>) {
+        let mut added = Vec::new();
+        for path in paths {
+            let file = {
+                let mut vfs = self.db.inputs().vfs();
+                if vfs.file(&path).is_some() {
+                    continue;
+                }
+                vfs.intern(&self.db, &path)
+            };
+            if file.exists(&self.db) {
+                added.push(file);
             }
         }
+        self.add_to_workspace(added);
+    }
+
+    fn add_to_workspace(&mut self, added: Vec<File>) {
+        if added.is_empty() {
+            return;
+        }
+        let workspace = self.db.inputs().workspace();
+        let mut files = workspace.files(&self.db).clone();
+        let known: HashSet<File> = files.iter().copied().collect();
+        files.extend(added.into_iter().filter(|file| !known.contains(file)));
+        workspace.set_files(&mut self.db).to(files);
     }
 
     pub fn cancel(&mut self) {
         self.db.synthetic_write(Durability::LOW);
     }
 
-    pub fn snapshot(&self) -> SourceDatabaseSnapshot {
+    pub fn snapshot(&self) -> SourceDatabaseSnapshot<DB> {
         SourceDatabaseSnapshot {
             db: self.db.snapshot(),
-            files: Arc::clone(&self.files),
         }
     }
 }
 
-pub struct SourceDatabaseSnapshot {
-    pub db: salsa::Snapshot<RootDatabase>,
-    pub files: Arc<Mutex<HashMap<String, File>>>,
+pub struct SourceDatabaseSnapshot<DB: ParallelDatabase> {
+    pub db: salsa::Snapshot<DB>,
 }
 
+impl<DB: Db + ParallelDatabase> SourceDatabaseSnapshot<DB> {
+    /// Returns the file at `path`, if it is known.
+    pub fn file(&self, path: &Path) -> Option<File> {
+        self.db.inputs().file(path)
+    }
+
+    /// Returns the workspace, if one has been loaded.
+    pub fn workspace(&self) -> Option<Workspace> {
+        let workspace = self.db.inputs().workspace();
+        workspace.root(&*self.db).is_some().then_some(workspace)
+    }
+}
+
+/// Parses `file`, unless it was reparsed incrementally after edits in the editor.
 #[salsa::tracked]
 pub fn parse(db: &dyn Db, file: File) -> Parse {
-    parse_file(file.text(db))
+    match file.reparsed(db) {
+        Some(parse) => parse.clone(),
+        None => {
+            let dialect = db.inputs().workspace().dialect(db, file.path(db));
+            parse_file(file.text(db), dialect)
+        }
+    }
+}
+
+/// Merges `edits`, made in order, into a single edit of the text before them, given
+/// `text`, the text after them. Returns `None` if there are none.
+fn merge_edits(edits: &[TextEdit], text: &str) -> Option<TextEdit> {
+    let (first, rest) = edits.split_first()?;
+    // The range of the original text replaced by the edits so far, and the length of
+    // the text that replaced it.
+    let mut delete = first.delete;
+    let mut inserted = TextSize::of(first.insert.as_str());
+    for edit in rest {
+        let edited = TextRange::at(delete.start(), inserted);
+        let start = edited.start().min(edit.delete.start());
+        let end = edited.end().max(edit.delete.end());
+        // The text after `edited` is the original text, shifted.
+        delete = TextRange::new(start, delete.end() + (end - edited.end()));
+        inserted = end - start - edit.delete.len() + TextSize::of(edit.insert.as_str());
+    }
+    let insert = text.get(std::ops::Range::<usize>::from(TextRange::at(
+        delete.start(),
+        inserted,
+    )))?;
+    Some(TextEdit::new(delete, insert.to_string()))
 }
 
 #[salsa::tracked]
 pub fn lines(db: &dyn Db, file: File) -> Lines {
     Lines::new(file.text(db))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use star_syntax::Dialect;
+
+    #[test]
+    fn reparse_merged_edits() {
+        let original = "def f(x):\n    return [x, 1]\n\ny = f(2)\n";
+        let parse = parse_file(original, Dialect::default());
+        let mut text = original.to_string();
+        let mut edits = Vec::new();
+        for (needle, insert) in [
+            ("1", "1, 2"),
+            ("y", "z"),
+            ("f(x)", "f(x, w)"),
+            ("[x, 1, 2]", "[]"),
+            ("\n\n", "\n"),
+        ] {
+            let start = TextSize::try_from(text.find(needle).unwrap()).unwrap();
+            let delete = TextRange::at(start, TextSize::of(needle));
+            let edit = TextEdit::new(delete, insert.to_string());
+            edit.apply(&mut text);
+            edits.push(edit);
+
+            let merged = merge_edits(&edits, &text).unwrap();
+            let mut edited = original.to_string();
+            merged.apply(&mut edited);
+            assert_eq!(edited, text);
+            assert_eq!(
+                parse.reparse(&merged, &text),
+                parse_file(&text, Dialect::default()),
+                "{:?}",
+                text
+            );
+        }
+        // From `f(x)` to `y`, which became `f(x, w):\n    return []\nz`.
+        let merged = merge_edits(&edits, &text).unwrap();
+        assert_eq!(merged.delete, TextRange::new(4.into(), 30.into()));
+        assert_eq!(merged.insert, "f(x, w):\n    return []\nz");
+        assert_eq!(merge_edits(&[], &text), None);
+    }
+}
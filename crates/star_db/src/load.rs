@@ -0,0 +1,93 @@
+use crate::{
+    label::{Label, LabelError},
+    parse, Db, File,
+};
+use star_syntax::{
+    ast::{self, AstNode, AstToken},
+    TextRange,
+};
+use std::{fmt, path::PathBuf};
+
+/// The module string of a `load` statement, resolved to the file it names.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Load {
+    /// The module, such as `//pkg:defs.bzl`.
+    pub module: String,
+    /// The range of the module's string literal.
+    pub range: TextRange,
+    pub resolution: Result<File, LoadError>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    InvalidLabel(LabelError),
+    /// The label refers to a repository or package that isn't part of the workspace.
+    Unresolved,
+    /// There is no file at the path the label refers to.
+    NotFound(PathBuf),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::InvalidLabel(err) => write!(f, "{}", err),
+            LoadError::Unresolved => write!(f, "unknown repository or package"),
+            LoadError::NotFound(path) => write!(f, "no file at {}", path.display()),
+        }
+    }
+}
+
+/// Resolves the modules of the `load` statements in `file`, relative to the package
+/// containing it. Files that haven't been interned yet are not found until they are read
+/// with [`SourceDatabase::read_files`](crate::SourceDatabase::read_files).
+#[salsa::tracked(return_ref)]
+pub fn resolve_loads(db: &dyn Db, file: File) -> Vec<Load> {
+    parse(db, file)
+        .syntax()
+        .descendants()
+        .filter_map(ast::LoadStmt::cast)
+        .filter_map(|stmt| {
+            let module = stmt.module()?;
+            let value = module.value()?;
+            Some(Load {
+                resolution: resolve_module(db, file, &value),
+                module: value,
+                range: module.syntax().text_range(),
+            })
+        })
+        .collect()
+}
+
+/// Returns the file loaded by `load(module, ...)` in `file`.
+pub fn resolve_load(db: &dyn Db, file: File, module: &str) -> Option<File> {
+    let load = resolve_loads(db, file)
+        .iter()
+        .find(|load| load.module == module)?;
+    load.resolution.clone().ok()
+}
+
+fn resolve_module(db: &dyn Db, file: File, module: &str) -> Result<File, LoadError> {
+    let label = Label::parse(module).map_err(LoadError::InvalidLabel)?;
+    let from = file.path(db);
+    let workspace = db.inputs().workspace();
+    let path = match workspace.root(db) {
+        Some(_) => workspace.resolve_label(db, from, &label),
+        // Without a workspace, only labels relative to the loading file can be resolved,
+        // treating its directory as the package.
+        None if label.repo.is_none() && label.package.is_none() => {
+            from.parent().map(|dir| dir.join(&label.name))
+        }
+        None => None,
+    };
+    let path = path.ok_or(LoadError::Unresolved)?;
+    match db.inputs().file(&path) {
+        Some(loaded) if loaded.exists(db) => Ok(loaded),
+        Some(_) => Err(LoadError::NotFound(path)),
+        None => {
+            // Reading the file adds it to the workspace's files, so this is resolved again
+            // once it has been read.
+            workspace.files(db);
+            Err(LoadError::NotFound(path))
+        }
+    }
+}
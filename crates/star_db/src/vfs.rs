@@ -0,0 +1,71 @@
+use crate::{Db, File};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Maps paths to [`File`] inputs. A path is interned the first time it is requested,
+/// whether or not a file exists there, so that queries notice when one is created or
+/// deleted.
+///
+/// Files open in the editor are overlaid: their contents come from the editor and
+/// shadow the contents on disk until they are closed.
+#[derive(Default)]
+pub struct Vfs {
+    files: HashMap<PathBuf, File>,
+    overlays: HashSet<File>,
+}
+
+impl Vfs {
+    /// Returns the file at `path`, if it has been interned.
+    pub fn file(&self, path: &Path) -> Option<File> {
+        self.files.get(path).copied()
+    }
+
+    /// Returns the file at `path`, reading it from disk if it hasn't been interned yet.
+    pub fn intern(&mut self, db: &dyn Db, path: &Path) -> File {
+        *self.files.entry(path.to_path_buf()).or_insert_with(|| {
+            let text = read(path);
+            File::new(
+                db,
+                path.to_path_buf(),
+                text.is_some(),
+                text.unwrap_or_default(),
+                None,
+            )
+        })
+    }
+
+    /// Interns `path` with contents that were already read from disk.
+    pub(crate) fn insert(&mut self, db: &dyn Db, path: PathBuf, text: String) -> File {
+        let file = File::new(db, path.clone(), true, text, None);
+        self.files.insert(path, file);
+        file
+    }
+
+    pub fn is_overlaid(&self, file: File) -> bool {
+        self.overlays.contains(&file)
+    }
+
+    pub(crate) fn add_overlay(&mut self, file: File) {
+        self.overlays.insert(file);
+    }
+
+    /// Removes the overlay of `file`, returning `false` if it had none.
+    pub(crate) fn remove_overlay(&mut self, file: File) -> bool {
+        self.overlays.remove(&file)
+    }
+
+    /// Iterates over the interned files and their paths.
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, File)> + '_ {
+        self.files
+            .iter()
+            .map(|(path, file)| (path.as_path(), *file))
+    }
+}
+
+/// Reads the file at `path`, returning `None` if it doesn't exist or isn't readable.
+pub(crate) fn read(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok()
+}
@@ -0,0 +1,288 @@
+use crate::{label::Label, Db, File};
+use star_syntax::{
+    ast::{self, AstNode, AstToken},
+    parse_file, Dialect,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+/// Files whose presence marks the root of a repository.
+pub const WORKSPACE_FILES: &[&str] =
+    &["MODULE.bazel", "WORKSPACE.bazel", "WORKSPACE", "REPO.bazel"];
+
+/// Files whose presence makes a directory a package.
+pub const BUILD_FILES: &[&str] = &["BUILD.bazel", "BUILD"];
+
+/// The build files of Buck2.
+const BUCK_FILES: &[&str] = &["BUCK", "TARGETS"];
+
+/// Checks whether the file at `path` is evaluated by Bazel, which predeclares more
+/// names than plain Starlark: a `.bzl`, BUILD or WORKSPACE file.
+pub fn is_bazel_file(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    name.ends_with(".bzl") || BUILD_FILES.contains(&name) || WORKSPACE_FILES.contains(&name)
+}
+
+/// The dialect the file at `path` is parsed in, going by its name: Bazel's for `.bzl`,
+/// BUILD and WORKSPACE files, Buck2's for BUCK and TARGETS files, and standard Starlark
+/// for anything else. [`Workspace::dialect`] also knows about `.bzl` files of Buck.
+pub fn dialect(path: &Path) -> Dialect {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    if BUILD_FILES.contains(&name) || WORKSPACE_FILES.contains(&name) {
+        Dialect::BAZEL_BUILD
+    } else if BUCK_FILES.contains(&name) {
+        Dialect::BUCK2
+    } else if is_bazel_file(path) {
+        Dialect::BAZEL
+    } else {
+        Dialect::STANDARD
+    }
+}
+
+/// The layout of a Bazel workspace: where its repositories live and which of their
+/// directories are packages.
+#[salsa::input]
+pub struct Workspace {
+    /// The root of the main repository, or `None` until a workspace is found.
+    #[return_ref]
+    root: Option<PathBuf>,
+    /// The roots of external repositories, keyed by name.
+    #[return_ref]
+    repositories: BTreeMap<String, PathBuf>,
+    /// The directories containing a BUILD file, in any of the repositories.
+    #[return_ref]
+    packages: BTreeSet<PathBuf>,
+    /// The Starlark files in any of the repositories, as far as they have been indexed.
+    #[return_ref]
+    files: Vec<File>,
+    /// The root of the Buck project the editor opened, which has a `.buckconfig` file.
+    #[return_ref]
+    buck_root: Option<PathBuf>,
+}
+
+/// A package, identified by its repository and its path within that repository.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Package {
+    /// The name of the repository, which is empty for the main repository.
+    pub repo: String,
+    /// The path of the package relative to the repository root, such as `foo/bar`.
+    pub name: String,
+    pub dir: PathBuf,
+}
+
+/// The names and roots of all repositories, with nested repositories first so that the
+/// first root containing a path is the innermost one.
+#[salsa::tracked(return_ref)]
+pub fn repository_roots(db: &dyn Db, workspace: Workspace) -> Vec<(String, PathBuf)> {
+    let mut roots: Vec<(String, PathBuf)> = workspace
+        .repositories(db)
+        .iter()
+        .map(|(name, root)| (name.clone(), root.clone()))
+        .collect();
+    if let Some(root) = workspace.root(db) {
+        roots.push((String::new(), root.clone()));
+    }
+    roots.sort_by_key(|(_, root)| std::cmp::Reverse(root.components().count()));
+    roots
+}
+
+impl Workspace {
+    /// Returns the root of the repository called `repo`. The empty name refers to the
+    /// main repository.
+    pub fn repository_root(self, db: &dyn Db, repo: &str) -> Option<PathBuf> {
+        if repo.is_empty() {
+            return self.root(db).clone();
+        }
+        self.repositories(db).get(repo).cloned()
+    }
+
+    /// Returns the name and root of the innermost repository containing `path`.
+    pub fn containing_repository(self, db: &dyn Db, path: &Path) -> Option<(String, PathBuf)> {
+        repository_roots(db, self)
+            .iter()
+            .find(|(_, root)| path.starts_with(root))
+            .cloned()
+    }
+
+    /// Returns the package containing the file at `path`: the innermost directory with a
+    /// BUILD file in the same repository.
+    pub fn containing_package(self, db: &dyn Db, path: &Path) -> Option<Package> {
+        let (repo, root) = self.containing_repository(db, path)?;
+        let packages = self.packages(db);
+        let dir = path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(&root))
+            .find(|dir| packages.contains(*dir))?;
+        Some(Package {
+            repo,
+            name: package_name(&root, dir),
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    /// Returns the packages in the repository called `repo`.
+    pub fn repository_packages(self, db: &dyn Db, repo: &str) -> Vec<Package> {
+        let root = match self.repository_root(db, repo) {
+            Some(root) => root,
+            None => return Vec::new(),
+        };
+        // Packages of repositories nested within this one don't belong to it.
+        self.packages(db)
+            .iter()
+            .filter(|dir| dir.starts_with(&root))
+            .filter(|dir| {
+                matches!(self.containing_repository(db, dir), Some((name, _)) if name == repo)
+            })
+            .map(|dir| Package {
+                repo: repo.to_string(),
+                name: package_name(&root, dir),
+                dir: dir.clone(),
+            })
+            .collect()
+    }
+
+    /// Returns the dialect the file at `path` is parsed in. This is the one [`dialect`]
+    /// picks, except that `.bzl` files in the Buck project are for Buck2.
+    pub fn dialect(self, db: &dyn Db, path: &Path) -> Dialect {
+        match self.buck_root(db) {
+            Some(root) if path.starts_with(root) && path.extension() == Some("bzl".as_ref()) => {
+                Dialect::BUCK2
+            }
+            _ => dialect(path),
+        }
+    }
+
+    /// Returns the path of the file that `label` refers to, when it appears in the file
+    /// at `from`. The path is returned even if no such file exists.
+    pub fn resolve_label(self, db: &dyn Db, from: &Path, label: &Label) -> Option<PathBuf> {
+        let root = match &label.repo {
+            Some(repo) => self.repository_root(db, repo)?,
+            None => self.containing_repository(db, from)?.1,
+        };
+        let package_dir = match &label.package {
+            Some(package) => root.join(package),
+            None => self.containing_package(db, from)?.dir,
+        };
+        Some(package_dir.join(&label.name))
+    }
+}
+
+fn package_name(root: &Path, dir: &Path) -> String {
+    let relative = dir.strip_prefix(root).unwrap_or(dir);
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Finds the repositories declared with `local_repository` or `new_local_repository` in
+/// a WORKSPACE file, or with `local_path_override` in a MODULE.bazel file. Relative paths
+/// are resolved against `root`.
+pub fn local_repositories(text: &str, root: &Path) -> BTreeMap<String, PathBuf> {
+    let parse = parse_file(text, Dialect::BAZEL_BUILD);
+    let file = match ast::File::cast(parse.syntax()) {
+        Some(file) => file,
+        None => return BTreeMap::new(),
+    };
+
+    let mut repositories = BTreeMap::new();
+    for call in file.syntax().descendants().filter_map(ast::CallExpr::cast) {
+        let callee = match call.expr() {
+            Some(ast::Expr::Literal(literal)) => literal.token(),
+            _ => continue,
+        };
+        let name_argument = match callee.text() {
+            "local_repository" | "new_local_repository" => "name",
+            "local_path_override" => "module_name",
+            _ => continue,
+        };
+
+        let mut name = None;
+        let mut path = None;
+        for argument in call.arguments().iter().flat_map(ast::Arguments::arguments) {
+            let keyword = match argument.name() {
+                Some(keyword) => keyword,
+                None => continue,
+            };
+            let value = match argument.value() {
+                Some(ast::Expr::Literal(literal)) => match literal.kind() {
+                    ast::LiteralKind::String(value) => value.value(),
+                    _ => None,
+                },
+                _ => None,
+            };
+            match keyword.syntax().text() {
+                keyword if keyword == name_argument => name = value,
+                "path" => path = value,
+                _ => {}
+            }
+        }
+
+        if let (Some(name), Some(path)) = (name, path) {
+            repositories.insert(name, root.join(path));
+        }
+    }
+    repositories
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dialects() {
+        assert_eq!(dialect(Path::new("/a/BUILD.bazel")), Dialect::BAZEL_BUILD);
+        assert_eq!(dialect(Path::new("/MODULE.bazel")), Dialect::BAZEL_BUILD);
+        assert_eq!(dialect(Path::new("/a/defs.bzl")), Dialect::BAZEL);
+        assert_eq!(dialect(Path::new("/a/script.star")), Dialect::STANDARD);
+        assert_eq!(dialect(Path::new("/a/BUCK")), Dialect::BUCK2);
+        assert_eq!(dialect(Path::new("/a/TARGETS")), Dialect::BUCK2);
+    }
+
+    #[test]
+    fn local_repositories_in_workspace() {
+        let text = r#"
+workspace(name = "main")
+
+local_repository(
+    name = "lib",
+    path = "third_party/lib",
+)
+new_local_repository(name = "abs", path = "/opt/abs", build_file = "abs.BUILD")
+http_archive(name = "remote", urls = ["https://example.com"])
+local_repository(name = "incomplete")
+"#;
+        let repositories = local_repositories(text, Path::new("/src"));
+        let expected: BTreeMap<String, PathBuf> = [
+            ("abs".to_string(), PathBuf::from("/opt/abs")),
+            ("lib".to_string(), PathBuf::from("/src/third_party/lib")),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(repositories, expected);
+    }
+
+    #[test]
+    fn local_path_overrides_in_module() {
+        let text = r#"
+module(name = "main")
+bazel_dep(name = "lib", version = "1.0")
+local_path_override(module_name = "lib", path = "../lib")
+"#;
+        let repositories = local_repositories(text, Path::new("/src/main"));
+        assert_eq!(
+            repositories.get("lib"),
+            Some(&PathBuf::from("/src/main/../lib"))
+        );
+    }
+}
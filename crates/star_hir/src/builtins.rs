@@ -0,0 +1,561 @@
+//! The predeclared names of the Starlark universe, which every file can use without
+//! loading them, the globals Bazel adds to them, and the members of the builtin types.
+
+/// A predeclared function or value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Builtin {
+    pub name: &'static str,
+    /// The parameters of a function, as they would appear in a `def`, or `None` for a
+    /// value.
+    pub params: Option<&'static str>,
+    pub doc: &'static str,
+}
+
+impl Builtin {
+    /// The signature of a function, such as `len(x)`, or the name of a value.
+    pub fn signature(&self) -> String {
+        match self.params {
+            Some(params) => format!("{}({})", self.name, params),
+            None => self.name.to_string(),
+        }
+    }
+}
+
+const fn function(name: &'static str, params: &'static str, doc: &'static str) -> Builtin {
+    Builtin {
+        name,
+        params: Some(params),
+        doc,
+    }
+}
+
+const fn value(name: &'static str, doc: &'static str) -> Builtin {
+    Builtin {
+        name,
+        params: None,
+        doc,
+    }
+}
+
+/// Sorted by name.
+pub static BUILTINS: &[Builtin] = &[
+    value("False", "The boolean false value."),
+    value(
+        "None",
+        "The value used to indicate the absence of any other value.",
+    ),
+    value("True", "The boolean true value."),
+    function("abs", "x", "Returns the absolute value of a number."),
+    function(
+        "all",
+        "x",
+        "Returns whether all elements of an iterable are true.",
+    ),
+    function(
+        "any",
+        "x",
+        "Returns whether at least one element of an iterable is true.",
+    ),
+    function("bool", "x=False", "Converts a value to a boolean."),
+    function(
+        "dict",
+        "pairs=[], **kwargs",
+        "Creates a dictionary from pairs or keyword arguments.",
+    ),
+    function(
+        "dir",
+        "x",
+        "Returns the names of the attributes of a value, sorted.",
+    ),
+    function(
+        "enumerate",
+        "x, start=0",
+        "Returns a list of (index, element) pairs of an iterable.",
+    ),
+    function(
+        "fail",
+        "*args, sep=\" \"",
+        "Stops execution with an error made of the arguments.",
+    ),
+    function("float", "x", "Converts a number or a string to a float."),
+    function(
+        "getattr",
+        "x, name, default=None",
+        "Returns the attribute of a value called `name`.",
+    ),
+    function(
+        "hasattr",
+        "x, name",
+        "Returns whether a value has an attribute called `name`.",
+    ),
+    function("hash", "x", "Returns a hash of a string."),
+    function("int", "x, base=10", "Converts a value to an integer."),
+    function(
+        "len",
+        "x",
+        "Returns the number of elements of a string or a collection.",
+    ),
+    function(
+        "list",
+        "x=[]",
+        "Creates a list with the elements of an iterable.",
+    ),
+    function(
+        "max",
+        "*args, key=None",
+        "Returns the greatest of the arguments, or of the elements of an iterable.",
+    ),
+    function(
+        "min",
+        "*args, key=None",
+        "Returns the least of the arguments, or of the elements of an iterable.",
+    ),
+    function(
+        "print",
+        "*args, sep=\" \"",
+        "Prints the arguments, separated by `sep`.",
+    ),
+    function(
+        "range",
+        "start_or_stop, stop=None, step=1",
+        "Returns a sequence of integers.",
+    ),
+    function("repr", "x", "Returns the string representation of a value."),
+    function(
+        "reversed",
+        "x",
+        "Returns a list with the elements of an iterable in reverse order.",
+    ),
+    function(
+        "sorted",
+        "x, key=None, reverse=False",
+        "Returns a sorted list with the elements of an iterable.",
+    ),
+    function("str", "x", "Converts a value to a string."),
+    function(
+        "struct",
+        "**kwargs",
+        "Creates an immutable value with the keyword arguments as fields.",
+    ),
+    function(
+        "tuple",
+        "x=()",
+        "Creates a tuple with the elements of an iterable.",
+    ),
+    function("type", "x", "Returns the name of the type of a value."),
+    function(
+        "zip",
+        "*args",
+        "Returns a list of tuples, combining the elements of the iterables at each index.",
+    ),
+];
+
+/// The globals that Bazel predeclares on top of the universe. Sorted by name.
+pub static BAZEL_BUILTINS: &[Builtin] = &[
+    function("Label", "label", "Creates a label referring to a target."),
+    function("aspect", "implementation, attr_aspects=[], attrs={}, **kwargs", "Creates a new aspect."),
+    value("attr", "The module for declaring the attributes of rules and aspects."),
+    function("depset", "direct=None, order=\"default\", *, transitive=None", "Creates a depset, a set optimized for accumulating data across dependencies."),
+    value("native", "The module of native rules and functions, for use in macros."),
+    function("provider", "doc=\"\", *, fields=None, init=None", "Creates a provider, which rules use to pass information to their dependents."),
+    function("repository_rule", "implementation, *, attrs=None, local=False, environ=[], configure=False, doc=\"\"", "Creates a repository rule."),
+    function("rule", "implementation, *, attrs={}, outputs=None, executable=False, test=False, toolchains=[], doc=\"\", provides=[], **kwargs", "Creates a new rule, which can be called from a BUILD file to declare a target."),
+    function("select", "x, no_match_error=\"\"", "Chooses a value depending on the configuration."),
+];
+
+/// Looks up a predeclared name. Bazel's globals are only predeclared in Bazel files.
+pub fn builtin(name: &str, bazel: bool) -> Option<&'static Builtin> {
+    let find = |builtins: &'static [Builtin]| {
+        builtins
+            .binary_search_by(|builtin| builtin.name.cmp(name))
+            .ok()
+            .map(|index| &builtins[index])
+    };
+    find(BUILTINS).or_else(|| if bazel { find(BAZEL_BUILTINS) } else { None })
+}
+
+/// The methods of strings.
+pub static STRING_METHODS: &[Builtin] = &[
+    function(
+        "capitalize",
+        "",
+        "Returns a copy of the string with its first character in upper case.",
+    ),
+    function(
+        "count",
+        "sub, start=0, end=None",
+        "Returns the number of non-overlapping occurrences of `sub`.",
+    ),
+    function(
+        "elems",
+        "",
+        "Returns an iterable of the one-character substrings of the string.",
+    ),
+    function(
+        "endswith",
+        "suffix, start=0, end=None",
+        "Returns whether the string ends with `suffix`.",
+    ),
+    function(
+        "find",
+        "sub, start=0, end=None",
+        "Returns the index of the first occurrence of `sub`, or -1.",
+    ),
+    function(
+        "format",
+        "*args, **kwargs",
+        "Replaces the `{}` fields of the string with the arguments.",
+    ),
+    function(
+        "index",
+        "sub, start=0, end=None",
+        "Returns the index of the first occurrence of `sub`, failing if there is none.",
+    ),
+    function(
+        "isalnum",
+        "",
+        "Returns whether all characters are letters or digits.",
+    ),
+    function("isalpha", "", "Returns whether all characters are letters."),
+    function("isdigit", "", "Returns whether all characters are digits."),
+    function(
+        "islower",
+        "",
+        "Returns whether all cased characters are in lower case.",
+    ),
+    function(
+        "isspace",
+        "",
+        "Returns whether all characters are white space.",
+    ),
+    function(
+        "istitle",
+        "",
+        "Returns whether the string is in title case.",
+    ),
+    function(
+        "isupper",
+        "",
+        "Returns whether all cased characters are in upper case.",
+    ),
+    function(
+        "join",
+        "elements",
+        "Joins the elements of an iterable of strings, separated by the string.",
+    ),
+    function("lower", "", "Returns a copy of the string in lower case."),
+    function(
+        "lstrip",
+        "chars=None",
+        "Returns a copy of the string with leading characters removed.",
+    ),
+    function(
+        "partition",
+        "sep",
+        "Splits the string at the first occurrence of `sep` into a tuple of three parts.",
+    ),
+    function(
+        "removeprefix",
+        "prefix",
+        "Returns a copy of the string without `prefix`.",
+    ),
+    function(
+        "removesuffix",
+        "suffix",
+        "Returns a copy of the string without `suffix`.",
+    ),
+    function(
+        "replace",
+        "old, new, count=-1",
+        "Returns a copy of the string with occurrences of `old` replaced by `new`.",
+    ),
+    function(
+        "rfind",
+        "sub, start=0, end=None",
+        "Returns the index of the last occurrence of `sub`, or -1.",
+    ),
+    function(
+        "rindex",
+        "sub, start=0, end=None",
+        "Returns the index of the last occurrence of `sub`, failing if there is none.",
+    ),
+    function(
+        "rpartition",
+        "sep",
+        "Splits the string at the last occurrence of `sep` into a tuple of three parts.",
+    ),
+    function(
+        "rsplit",
+        "sep=None, maxsplit=-1",
+        "Splits the string at occurrences of `sep`, starting from the end.",
+    ),
+    function(
+        "rstrip",
+        "chars=None",
+        "Returns a copy of the string with trailing characters removed.",
+    ),
+    function(
+        "split",
+        "sep=None, maxsplit=-1",
+        "Splits the string at occurrences of `sep`.",
+    ),
+    function(
+        "splitlines",
+        "keepends=False",
+        "Splits the string at line breaks.",
+    ),
+    function(
+        "startswith",
+        "prefix, start=0, end=None",
+        "Returns whether the string starts with `prefix`.",
+    ),
+    function(
+        "strip",
+        "chars=None",
+        "Returns a copy of the string with leading and trailing characters removed.",
+    ),
+    function("title", "", "Returns a copy of the string in title case."),
+    function("upper", "", "Returns a copy of the string in upper case."),
+];
+
+/// The methods of lists.
+pub static LIST_METHODS: &[Builtin] = &[
+    function("append", "x", "Adds an element to the end of the list."),
+    function("clear", "", "Removes all elements of the list."),
+    function(
+        "extend",
+        "x",
+        "Adds the elements of an iterable to the end of the list.",
+    ),
+    function(
+        "index",
+        "x, start=0, end=None",
+        "Returns the index of the first element equal to `x`.",
+    ),
+    function("insert", "i, x", "Inserts an element at index `i`."),
+    function(
+        "pop",
+        "i=-1",
+        "Removes and returns the element at index `i`.",
+    ),
+    function("remove", "x", "Removes the first element equal to `x`."),
+];
+
+/// The methods of dictionaries.
+pub static DICT_METHODS: &[Builtin] = &[
+    function("clear", "", "Removes all entries of the dictionary."),
+    function(
+        "get",
+        "key, default=None",
+        "Returns the value for `key`, or `default` if there is none.",
+    ),
+    function(
+        "items",
+        "",
+        "Returns the list of (key, value) pairs of the dictionary.",
+    ),
+    function("keys", "", "Returns the list of keys of the dictionary."),
+    function(
+        "pop",
+        "key, default=unbound",
+        "Removes the entry for `key` and returns its value.",
+    ),
+    function(
+        "popitem",
+        "",
+        "Removes and returns the first (key, value) pair of the dictionary.",
+    ),
+    function(
+        "setdefault",
+        "key, default=None",
+        "Returns the value for `key`, setting it to `default` first if there is none.",
+    ),
+    function(
+        "update",
+        "pairs=[], **kwargs",
+        "Adds the given entries to the dictionary.",
+    ),
+    function(
+        "values",
+        "",
+        "Returns the list of values of the dictionary.",
+    ),
+];
+
+/// The members of Bazel's `native` module, available in `.bzl` files.
+pub static NATIVE_MEMBERS: &[Builtin] = &[
+    function(
+        "alias",
+        "name, actual, **kwargs",
+        "Declares another name for a target.",
+    ),
+    function("cc_binary", "name, **kwargs", "Declares a C++ executable."),
+    function("cc_library", "name, **kwargs", "Declares a C++ library."),
+    function("cc_test", "name, **kwargs", "Declares a C++ test."),
+    function(
+        "config_setting",
+        "name, **kwargs",
+        "Declares a configuration state that `select` can match.",
+    ),
+    function(
+        "existing_rule",
+        "name",
+        "Returns the attributes of the target called `name` in this package, or `None`.",
+    ),
+    function(
+        "existing_rules",
+        "",
+        "Returns the attributes of all targets declared in this package so far.",
+    ),
+    function(
+        "exports_files",
+        "srcs, visibility=None, licenses=None",
+        "Makes files of this package available to other packages.",
+    ),
+    function(
+        "filegroup",
+        "name, srcs=[], **kwargs",
+        "Declares a collection of targets.",
+    ),
+    function(
+        "genrule",
+        "name, srcs=[], outs, cmd, **kwargs",
+        "Declares a target that generates files with a shell command.",
+    ),
+    function(
+        "glob",
+        "include=[], exclude=[], exclude_directories=1, allow_empty=True",
+        "Returns the files of this package matching the patterns.",
+    ),
+    function(
+        "java_binary",
+        "name, **kwargs",
+        "Declares a Java executable.",
+    ),
+    function("java_library", "name, **kwargs", "Declares a Java library."),
+    function("java_test", "name, **kwargs", "Declares a Java test."),
+    function(
+        "package_group",
+        "name, packages=[], includes=[]",
+        "Declares a set of packages, for use in visibility.",
+    ),
+    function(
+        "package_name",
+        "",
+        "Returns the name of the package being evaluated.",
+    ),
+    function(
+        "py_binary",
+        "name, **kwargs",
+        "Declares a Python executable.",
+    ),
+    function("py_library", "name, **kwargs", "Declares a Python library."),
+    function("py_test", "name, **kwargs", "Declares a Python test."),
+    function(
+        "repository_name",
+        "",
+        "Returns the name of the repository being evaluated.",
+    ),
+    function(
+        "sh_binary",
+        "name, **kwargs",
+        "Declares a shell script executable.",
+    ),
+    function("sh_test", "name, **kwargs", "Declares a shell script test."),
+    function(
+        "subpackages",
+        "include, exclude=[], allow_empty=False",
+        "Returns the direct subpackages of this package matching the patterns.",
+    ),
+    function(
+        "test_suite",
+        "name, tests=[], **kwargs",
+        "Declares a set of tests.",
+    ),
+];
+
+/// The members of the `ctx` object passed to the implementation of a rule.
+pub static CTX_MEMBERS: &[Builtin] = &[
+    value("actions", "The module of functions for declaring files and the actions that generate them."),
+    value("aspect_ids", "The ids of the aspects applied to the target."),
+    value("attr", "The values of the attributes of the target."),
+    value("bin_dir", "The root of the directory of binaries."),
+    value("build_file_path", "The path of the BUILD file of the target, relative to the source root."),
+    function("build_setting_value", "", "Returns the value of the build setting this rule declares."),
+    value("configuration", "The build configuration."),
+    function("coverage_instrumented", "target=None", "Returns whether coverage instrumentation is enabled for the target."),
+    value("disabled_features", "The features disabled by the user for this rule."),
+    value("exec_groups", "The execution groups available to the rule."),
+    value("executable", "The executable files of the attributes declared with `executable=True`."),
+    function("expand_location", "input, targets=[]", "Expands the `$(location ...)` templates in a string."),
+    function("expand_make_variables", "attribute_name, command, additional_substitutions", "Expands the make variables in a string."),
+    value("features", "The features enabled by the user for this rule."),
+    value("file", "The single files of the attributes declared with `allow_single_file=True`."),
+    value("files", "The files of the attributes that take labels."),
+    value("fragments", "The configuration fragments available to the rule."),
+    value("genfiles_dir", "The root of the directory of generated files."),
+    value("info_file", "The file with the stable keys of the workspace status."),
+    value("label", "The label of the target."),
+    value("outputs", "The predeclared output files of the target."),
+    function("resolve_command", "command=\"\", attribute=None, expand_locations=False, make_variables=None, tools=[], label_dict={}, execution_requirements={}", "Resolves a command for use in `run_shell`."),
+    function("resolve_tools", "tools=[]", "Resolves the tools needed by an action."),
+    value("rule", "The attributes of the rule an aspect is applied to."),
+    function("runfiles", "files=[], transitive_files=None, collect_data=False, collect_default=False, symlinks={}, root_symlinks={}", "Creates a runfiles object."),
+    value("split_attr", "The values of the attributes with split configurations."),
+    function("target_platform_has_constraint", "constraintValue", "Returns whether the target platform has a constraint value."),
+    value("toolchains", "The toolchains the rule requires."),
+    value("var", "The values of the make variables."),
+    value("version_file", "The file with the volatile keys of the workspace status."),
+    value("workspace_name", "The name of the workspace."),
+];
+
+/// The members of the `repository_ctx` object passed to the implementation of a
+/// repository rule.
+pub static REPOSITORY_CTX_MEMBERS: &[Builtin] = &[
+    value("attr", "The values of the attributes of the repository."),
+    function("delete", "path", "Deletes a file or a directory."),
+    function("download", "url, output=\"\", sha256=\"\", executable=False, allow_fail=False, canonical_id=\"\", auth={}, *, integrity=\"\"", "Downloads a file."),
+    function("download_and_extract", "url, output=\"\", sha256=\"\", type=\"\", stripPrefix=\"\", allow_fail=False, canonical_id=\"\", auth={}, *, integrity=\"\", rename_files={}", "Downloads an archive and extracts it."),
+    function("execute", "arguments, timeout=600, environment={}, quiet=True, working_directory=\"\"", "Runs a command."),
+    function("extract", "archive, output=\"\", stripPrefix=\"\", *, rename_files={}", "Extracts an archive."),
+    function("file", "path, content=\"\", executable=True, legacy_utf8=True", "Creates a file."),
+    function("getenv", "name, default=None", "Returns the value of an environment variable."),
+    value("name", "The name of the repository."),
+    value("os", "Information about the system."),
+    function("patch", "patch_file, strip=0", "Applies a patch file."),
+    function("path", "path", "Returns a path from a string, a label or a path."),
+    function("read", "path", "Reads a file."),
+    function("report_progress", "status=\"\"", "Updates the progress status of fetching the repository."),
+    function("symlink", "target, link_name", "Creates a symlink."),
+    function("template", "path, template, substitutions={}, executable=True", "Creates a file from a template."),
+    function("which", "program", "Returns the path of a program on the `PATH`, or `None`."),
+    value("workspace_root", "The path of the root of the workspace."),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorted() {
+        for builtins in [
+            BUILTINS,
+            BAZEL_BUILTINS,
+            STRING_METHODS,
+            LIST_METHODS,
+            DICT_METHODS,
+            NATIVE_MEMBERS,
+            CTX_MEMBERS,
+            REPOSITORY_CTX_MEMBERS,
+        ] {
+            assert!(builtins.windows(2).all(|pair| pair[0].name < pair[1].name));
+        }
+        assert_eq!(
+            builtin("len", false).map(Builtin::signature).as_deref(),
+            Some("len(x)")
+        );
+        assert_eq!(builtin("undefined", false), None);
+        assert_eq!(builtin("native", false), None);
+        assert!(builtin("native", true).is_some());
+    }
+}
@@ -0,0 +1,40 @@
+//! Errors found while inferring types.
+
+use crate::Ty;
+use star_syntax::TextRange;
+
+/// A type error in a function body, or in the top level of a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeError {
+    pub kind: TypeErrorKind,
+    pub range: TextRange,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypeErrorKind {
+    /// A binary operator applied to operands it doesn't support, as in `"a" + 1`.
+    UnsupportedOperands {
+        op: String,
+        lhs: Ty,
+        rhs: Ty,
+    },
+    /// An attribute that values of the type don't have, as in `None.foo`.
+    NoAttribute {
+        ty: Ty,
+        name: String,
+    },
+    NotCallable(Ty),
+    NotIndexable(Ty),
+    NotIterable(Ty),
+    /// More positional arguments than the function has positional parameters.
+    TooManyArguments {
+        expected: usize,
+        found: usize,
+    },
+    /// A parameter without a default value that no argument is passed to.
+    MissingArgument(String),
+    /// A keyword argument that doesn't name any parameter.
+    UnknownKeyword(String),
+    /// A keyword argument passed more than once in the same call.
+    DuplicateKeyword(String),
+}
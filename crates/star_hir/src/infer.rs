@@ -0,0 +1,1311 @@
+//! Type inference.
+//!
+//! The body of each function is inferred on its own by [`infer_function`], and the top
+//! level of a file by [`infer_file`]. Both read the syntax tree of the whole file, so
+//! any edit to a file infers it again; functions are identified by their names rather
+//! than their ranges, so that a [`Ty::Function`] stays the same across such edits.
+//! Inference is flow-insensitive: a name has the join of the types of all the values
+//! bound to it, wherever they are bound.
+//!
+//! The type of a call to a function defined with `def` is the type
+//! [`function_return_ty`] infers for the function. To keep the queries free of cycles,
+//! even for recursive functions, that query doesn't follow calls itself, and names loaded
+//! from other files are inferred from the loaded file without following its own loads.
+//!
+//! In dialects with type annotations, the declared types of parameters, return values
+//! and assigned names are taken as given, as long as they name types we know about.
+
+use crate::{
+    builtin, file_scopes, Binding, BindingKind, Db, FileScopes, Resolution, ScopeId, ScopeKind, Ty,
+    TypeError, TypeErrorKind,
+};
+use star_db::File;
+use star_syntax::{
+    ast::{self, AstNode, AstToken, BinaryOp, UnaryOp},
+    SyntaxKind::{self, *},
+    SyntaxNode, TextRange, T,
+};
+use std::collections::{HashMap, HashSet};
+
+/// A function defined by a `def` statement.
+#[salsa::interned]
+pub struct Function {
+    pub file: File,
+    /// The names of the functions the function is nested in, outermost first, followed
+    /// by its own. Each name comes with the number of functions of the same name defined
+    /// before it in the same body.
+    #[return_ref]
+    pub path: Vec<(String, usize)>,
+}
+
+impl Function {
+    /// The function defined by `def` in `file`.
+    pub fn from_def(db: &dyn Db, file: File, def: &ast::DefStmt) -> Function {
+        let mut path = Vec::new();
+        let mut node = def.syntax().clone();
+        while let Some(body) = node
+            .ancestors()
+            .skip(1)
+            .find(|ancestor| ancestor.kind() == DEF_STMT || ancestor.parent().is_none())
+        {
+            let name = ast::DefStmt::cast(node.clone())
+                .map(|def| def_name(&def))
+                .unwrap_or_default();
+            let index = body_defs(&body)
+                .into_iter()
+                .filter(|other| def_name(other) == name)
+                .position(|other| *other.syntax() == node)
+                .unwrap_or(0);
+            path.push((name, index));
+            if body.kind() != DEF_STMT {
+                break;
+            }
+            node = body;
+        }
+        path.reverse();
+        Function::new(db, file, path)
+    }
+
+    /// Finds the `def` statement of the function in `root`, the syntax tree of its file.
+    /// Missing if the function has been removed from the file.
+    pub fn def(self, db: &dyn Db, root: &SyntaxNode) -> Option<ast::DefStmt> {
+        let mut body = root.clone();
+        let mut def = None;
+        for (name, index) in self.path(db) {
+            let found = body_defs(&body)
+                .into_iter()
+                .filter(|other| def_name(other) == *name)
+                .nth(*index)?;
+            body = found.syntax().clone();
+            def = Some(found);
+        }
+        def
+    }
+}
+
+/// The types inferred for the expressions and names of a function body, or of the top
+/// level of a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InferenceResult {
+    exprs: HashMap<(TextRange, SyntaxKind), Ty>,
+    names: HashMap<(ScopeId, String), Ty>,
+    return_ty: Ty,
+    errors: Vec<TypeError>,
+}
+
+impl InferenceResult {
+    /// The type of `expr`, which must be in the body this result is for.
+    pub fn expr_ty(&self, expr: &ast::Expr) -> Ty {
+        let node = expr.syntax();
+        self.exprs
+            .get(&(node.text_range(), node.kind()))
+            .cloned()
+            .unwrap_or(Ty::Unknown)
+    }
+
+    /// The type of `name` in `scope`, which must be a scope of the body this result is
+    /// for, such as the scope of a comprehension in it.
+    pub fn name_ty(&self, scope: ScopeId, name: &str) -> Ty {
+        self.names
+            .get(&(scope, name.to_string()))
+            .cloned()
+            .unwrap_or(Ty::Unknown)
+    }
+
+    /// The type of the values the function returns. `None` for the top level of a file.
+    pub fn return_ty(&self) -> &Ty {
+        &self.return_ty
+    }
+
+    /// The type errors in the body, in order.
+    pub fn errors(&self) -> &[TypeError] {
+        &self.errors
+    }
+}
+
+/// Infers the types in the top level of `file`, outside of any function.
+#[salsa::tracked(return_ref)]
+pub fn infer_file(db: &dyn Db, file: File) -> InferenceResult {
+    let mut ctx = InferenceContext::new(db, file, None, Mode::Full);
+    let root = ctx.root.clone();
+    ctx.infer_body(&root);
+    ctx.finish(Ty::None)
+}
+
+/// Infers the types in the body of `function`.
+#[salsa::tracked(return_ref)]
+pub fn infer_function(db: &dyn Db, function: Function) -> InferenceResult {
+    let mut ctx = InferenceContext::for_function(db, function, Mode::Full);
+    let return_ty = match ctx.def() {
+        Some(def) => {
+            if let Some(suite) = def.suite() {
+                ctx.infer_body(suite.syntax());
+            }
+            ctx.return_ty(&def)
+        }
+        None => Ty::Unknown,
+    };
+    ctx.finish(return_ty)
+}
+
+/// Infers the type of the values `function` returns, without following calls in it.
+#[salsa::tracked(return_ref)]
+pub fn function_return_ty(db: &dyn Db, function: Function) -> Ty {
+    let mut ctx = InferenceContext::for_function(db, function, Mode::Local);
+    match ctx.def() {
+        Some(def) => ctx.return_ty(&def),
+        None => Ty::Unknown,
+    }
+}
+
+/// Infers the type of `expr` in `file`. By Bazel's convention, the parameters called
+/// `ctx` and `repository_ctx` in Bazel files are the contexts of rule implementations.
+pub fn infer_expr(db: &dyn Db, file: File, expr: &ast::Expr) -> Ty {
+    let node = expr.syntax();
+    let function = node
+        .ancestors()
+        .filter_map(ast::DefStmt::cast)
+        .find(|def| {
+            matches!(def.suite(), Some(suite) if suite.syntax().text_range().contains_range(node.text_range()))
+        });
+    let result = match function {
+        Some(def) => infer_function(db, Function::from_def(db, file, &def)),
+        None => infer_file(db, file),
+    };
+    result.expr_ty(expr)
+}
+
+/// Infers the type of `name`, which is bound in `scope` of `file`.
+pub fn infer_name(db: &dyn Db, file: File, scope: ScopeId, name: &str) -> Ty {
+    let root = star_db::parse(db.as_source_db(), file).syntax();
+    let def = owner(file_scopes(db, file), scope).and_then(|range| find_def(&root, range));
+    let result = match def {
+        Some(def) => infer_function(db, Function::from_def(db, file, &def)),
+        None => infer_file(db, file),
+    };
+    result.name_ty(scope, name)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Calls have the return types of the functions they call, and names bound in
+    /// other bodies have the types inferred for those bodies.
+    Full,
+    /// Calls have unknown types, and names bound anywhere in the file are inferred in
+    /// place.
+    Local,
+    /// Like `Local`, for a file that a name is loaded from. Names it loads itself have
+    /// unknown types, so that cycles of loads end.
+    Loaded,
+}
+
+struct InferenceContext<'a> {
+    db: &'a dyn Db,
+    file: File,
+    scopes: &'a FileScopes,
+    root: SyntaxNode,
+    bazel: bool,
+    mode: Mode,
+    /// The `def` statement whose body is inferred, or `None` for the top level.
+    body: Option<TextRange>,
+    exprs: HashMap<(TextRange, SyntaxKind), Ty>,
+    /// `None` while the type of the name is being inferred, so that a name whose value
+    /// depends on itself, as in `x = y` and `y = x`, gets an unknown type.
+    names: HashMap<(ScopeId, String), Option<Ty>>,
+    errors: Vec<TypeError>,
+}
+
+impl<'a> InferenceContext<'a> {
+    fn new(db: &'a dyn Db, file: File, body: Option<TextRange>, mode: Mode) -> Self {
+        let parse = star_db::parse(db.as_source_db(), file);
+        InferenceContext {
+            db,
+            file,
+            scopes: file_scopes(db, file),
+            root: parse.syntax(),
+            bazel: star_db::is_bazel_file(file.path(db.as_source_db())),
+            mode,
+            body,
+            exprs: HashMap::new(),
+            names: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// A context for the body of `function`, or for nothing if it no longer exists.
+    fn for_function(db: &'a dyn Db, function: Function, mode: Mode) -> Self {
+        let mut ctx = InferenceContext::new(db, function.file(db), None, mode);
+        ctx.body = function
+            .def(db, &ctx.root)
+            .map(|def| def.syntax().text_range());
+        ctx
+    }
+
+    fn finish(mut self, return_ty: Ty) -> InferenceResult {
+        self.errors.sort_by_key(|error| error.range.start());
+        InferenceResult {
+            exprs: self.exprs,
+            names: self
+                .names
+                .into_iter()
+                .filter_map(|(key, ty)| Some((key, ty?)))
+                .collect(),
+            return_ty,
+            errors: self.errors,
+        }
+    }
+
+    /// Reports an error, unless this context only infers types for another body.
+    fn error(&mut self, range: TextRange, kind: TypeErrorKind) {
+        if self.mode == Mode::Full {
+            self.errors.push(TypeError { kind, range });
+        }
+    }
+
+    /// The `def` statement whose body is inferred, or `None` for the top level or if the
+    /// function no longer exists.
+    fn def(&self) -> Option<ast::DefStmt> {
+        find_def(&self.root, self.body?)
+    }
+
+    /// Infers every expression in `node` and every name bound in the body, skipping
+    /// the bodies of nested functions.
+    fn infer_body(&mut self, node: &SyntaxNode) {
+        self.infer_children(node);
+        let names: Vec<(ScopeId, String)> = self
+            .scopes
+            .bindings()
+            .filter(|(_, binding)| owner(self.scopes, binding.scope) == self.body)
+            .map(|(_, binding)| (binding.scope, binding.name.clone()))
+            .collect();
+        for (scope, name) in names {
+            self.name_ty(scope, &name);
+        }
+    }
+
+    fn infer_children(&mut self, node: &SyntaxNode) {
+        for child in node.children() {
+            if child.kind() == TYPE_ANNOTATION {
+                continue;
+            }
+            if child.kind() == DEF_STMT {
+                // Default values are evaluated where the function is defined.
+                if let Some(params) = ast::DefStmt::cast(child).and_then(|def| def.parameters()) {
+                    self.infer_children(params.syntax());
+                }
+                continue;
+            }
+            if let Some(expr) = ast::Expr::cast(child.clone()) {
+                self.infer(&expr);
+            }
+            self.infer_children(&child);
+
+            let iterable = match child.kind() {
+                FOR_STMT => ast::ForStmt::cast(child).and_then(|stmt| stmt.expr()),
+                LIST_COMP_FOR => ast::CompFor::cast(child).and_then(|clause| clause.expr()),
+                _ => None,
+            };
+            if let Some(iterable) = iterable {
+                let ty = self.infer(&iterable);
+                if ty.variants().iter().all(|ty| !is_iterable(ty)) {
+                    self.error(
+                        iterable.syntax().text_range(),
+                        TypeErrorKind::NotIterable(ty),
+                    );
+                }
+            }
+        }
+    }
+
+    /// The type of the values returned by the function, including `None` if the end of
+    /// its body can be reached.
+    fn return_ty(&mut self, def: &ast::DefStmt) -> Ty {
+        if let Some(ty) = declared_ty(def.return_type()) {
+            return ty;
+        }
+        let suite = match def.suite() {
+            Some(suite) => suite,
+            None => return Ty::None,
+        };
+        let returns: Vec<ast::ReturnStmt> = suite
+            .syntax()
+            .descendants()
+            .filter(|node| {
+                node.ancestors()
+                    .take_while(|ancestor| ancestor != def.syntax())
+                    .all(|ancestor| ancestor.kind() != DEF_STMT)
+            })
+            .filter_map(ast::ReturnStmt::cast)
+            .collect();
+        let mut tys: Vec<Ty> = returns
+            .iter()
+            .map(|stmt| match stmt.expr() {
+                Some(expr) => self.infer(&expr),
+                None => Ty::None,
+            })
+            .collect();
+        if falls_through(&suite) {
+            tys.push(Ty::None);
+        }
+        Ty::join_all(tys).unwrap_or(Ty::None)
+    }
+
+    fn infer(&mut self, expr: &ast::Expr) -> Ty {
+        let key = (expr.syntax().text_range(), expr.syntax().kind());
+        if let Some(ty) = self.exprs.get(&key) {
+            return ty.clone();
+        }
+        let ty = self.infer_inner(expr);
+        self.exprs.insert(key, ty.clone());
+        ty
+    }
+
+    fn infer_opt(&mut self, expr: Option<ast::Expr>) -> Ty {
+        match expr {
+            Some(expr) => self.infer(&expr),
+            None => Ty::Unknown,
+        }
+    }
+
+    fn infer_inner(&mut self, expr: &ast::Expr) -> Ty {
+        match expr {
+            ast::Expr::Literal(literal) => match literal.kind() {
+                ast::LiteralKind::Int(_) => Ty::Int,
+                ast::LiteralKind::Float(_) => Ty::Float,
+                ast::LiteralKind::String(_) => Ty::String,
+                ast::LiteralKind::Ident(ident) => self.infer_name(&ident),
+            },
+            ast::Expr::ListExpr(list) => {
+                let elems: Vec<Ty> = list
+                    .elements()
+                    .into_iter()
+                    .map(|elem| self.infer(&elem))
+                    .collect();
+                Ty::list(Ty::join_all(elems).unwrap_or(Ty::Unknown))
+            }
+            ast::Expr::TupleExpr(tuple) => {
+                Ty::Tuple(tuple.exprs().map(|elem| self.infer(&elem)).collect())
+            }
+            ast::Expr::DictExpr(dict) => {
+                let (mut keys, mut values) = (Vec::new(), Vec::new());
+                for entry in dict.entries().iter().flat_map(|entries| entries.entries()) {
+                    keys.push(self.infer_opt(entry.key()));
+                    values.push(self.infer_opt(entry.value()));
+                }
+                Ty::dict(
+                    Ty::join_all(keys).unwrap_or(Ty::Unknown),
+                    Ty::join_all(values).unwrap_or(Ty::Unknown),
+                )
+            }
+            ast::Expr::SetExpr(set) => {
+                // Sets are rare enough that they don't have a type of their own.
+                for elem in set.elements() {
+                    self.infer(&elem);
+                }
+                Ty::Unknown
+            }
+            ast::Expr::ListComp(comp) => Ty::list(self.infer_opt(comp.expr())),
+            ast::Expr::DictComp(comp) => match comp.entry() {
+                Some(entry) => Ty::dict(self.infer_opt(entry.key()), self.infer_opt(entry.value())),
+                None => Ty::dict(Ty::Unknown, Ty::Unknown),
+            },
+            ast::Expr::IfExpr(if_expr) => {
+                self.infer_opt(if_expr.condition());
+                let then_ty = self.infer_opt(if_expr.then_expr());
+                then_ty.join(self.infer_opt(if_expr.else_expr()))
+            }
+            ast::Expr::UnaryExpr(unary) => {
+                let ty = self.infer_opt(unary.expr());
+                match (unary.op_kind(), ty) {
+                    (Some(UnaryOp::Not), _) => Ty::Bool,
+                    (Some(UnaryOp::Pos | UnaryOp::Neg), ty @ (Ty::Int | Ty::Float)) => ty,
+                    (Some(UnaryOp::BitNeg), Ty::Int) => Ty::Int,
+                    _ => Ty::Unknown,
+                }
+            }
+            ast::Expr::BinaryExpr(binary) => {
+                let lhs = self.infer_opt(binary.lhs());
+                let rhs = self.infer_opt(binary.rhs());
+                let (token, op) = match binary.op_details() {
+                    Some(details) => details,
+                    None => return Ty::Unknown,
+                };
+                match binary_ty(op, lhs.clone(), rhs.clone()) {
+                    Some(ty) => ty,
+                    None => {
+                        let op = token.text().to_string();
+                        let kind = TypeErrorKind::UnsupportedOperands { op, lhs, rhs };
+                        self.error(binary.syntax().text_range(), kind);
+                        Ty::Unknown
+                    }
+                }
+            }
+            ast::Expr::DotExpr(dot) => {
+                let receiver = self.infer_opt(dot.expr());
+                let ident = match dot.ident() {
+                    Some(ident) => ident,
+                    None => return Ty::Unknown,
+                };
+                let name = ident.syntax().text();
+                if receiver
+                    .variants()
+                    .iter()
+                    .all(|ty| !has_attribute(ty, name))
+                {
+                    let kind = TypeErrorKind::NoAttribute {
+                        ty: receiver.clone(),
+                        name: name.to_string(),
+                    };
+                    self.error(ident.syntax().text_range(), kind);
+                }
+                match receiver.members().iter().find(|member| member.name == name) {
+                    Some(member) if member.params.is_some() => {
+                        Ty::Method(Box::new(receiver), member)
+                    }
+                    _ => Ty::Unknown,
+                }
+            }
+            ast::Expr::CallExpr(call) => {
+                let callee = self.infer_opt(call.expr());
+                let arguments = call
+                    .arguments()
+                    .map(|args| args.arguments())
+                    .unwrap_or_default();
+                let mut args = Vec::new();
+                for arg in &arguments {
+                    let ty = self.infer_opt(arg.value());
+                    if matches!(arg.kind(), ast::ArgumentKind::Normal) && arg.name().is_none() {
+                        args.push(ty);
+                    }
+                }
+                if let Some(expr) = call.expr() {
+                    if callee.variants().iter().all(|ty| !is_callable(ty)) {
+                        self.error(
+                            expr.syntax().text_range(),
+                            TypeErrorKind::NotCallable(callee.clone()),
+                        );
+                    }
+                }
+                self.check_arguments(call, &callee, &arguments);
+                self.call_ty(callee, &args)
+            }
+            ast::Expr::IndexExpr(index) => {
+                let receiver = self.infer_opt(index.expr());
+                let index_expr = index.index();
+                self.infer_opt(index.index());
+                self.check_indexable(index.expr(), &receiver);
+                match receiver {
+                    Ty::List(elem) => *elem,
+                    Ty::Dict(_, value) => *value,
+                    Ty::String => Ty::String,
+                    Ty::Tuple(elems) => match index_expr.as_ref().and_then(int_literal) {
+                        Some(index) => elems.get(index).cloned().unwrap_or(Ty::Unknown),
+                        None => Ty::join_all(elems).unwrap_or(Ty::Unknown),
+                    },
+                    _ => Ty::Unknown,
+                }
+            }
+            ast::Expr::SliceExpr(slice) => {
+                let receiver = self.infer_opt(slice.expr());
+                for part in [slice.start(), slice.end(), slice.step()] {
+                    self.infer_opt(part);
+                }
+                self.check_indexable(slice.expr(), &receiver);
+                match receiver {
+                    ty @ (Ty::List(_) | Ty::String) => ty,
+                    _ => Ty::Unknown,
+                }
+            }
+            ast::Expr::LambdaExpr(_) => Ty::Unknown,
+        }
+    }
+
+    fn infer_name(&mut self, ident: &ast::Ident) -> Ty {
+        match self.scopes.resolve_token(ident.syntax()) {
+            Some(Resolution::Bindings { scope, .. }) => self.name_ty(scope, ident.syntax().text()),
+            Some(Resolution::Unresolved) => match builtin(ident.syntax().text(), self.bazel) {
+                Some(builtin) => match builtin.name {
+                    "None" => Ty::None,
+                    "True" | "False" => Ty::Bool,
+                    "native" => Ty::Native,
+                    _ if builtin.params.is_some() => Ty::Builtin(builtin),
+                    _ => Ty::Unknown,
+                },
+                None => Ty::Unknown,
+            },
+            None => Ty::Unknown,
+        }
+    }
+
+    /// The type of `name`, which is bound in `scope`.
+    fn name_ty(&mut self, scope: ScopeId, name: &str) -> Ty {
+        let key = (scope, name.to_string());
+        if let Some(ty) = self.names.get(&key) {
+            return ty.clone().unwrap_or(Ty::Unknown);
+        }
+        if self.mode == Mode::Full {
+            let owner = owner(self.scopes, scope);
+            if owner != self.body {
+                let result = match owner.and_then(|range| find_def(&self.root, range)) {
+                    Some(def) => {
+                        infer_function(self.db, Function::from_def(self.db, self.file, &def))
+                    }
+                    None => infer_file(self.db, self.file),
+                };
+                return result.name_ty(scope, name);
+            }
+        }
+
+        self.names.insert(key.clone(), None);
+        let bindings = match self.scopes.resolve_name(scope, name) {
+            Resolution::Bindings { bindings, .. } => bindings,
+            Resolution::Unresolved => Vec::new(),
+        };
+        let tys: Vec<Ty> = bindings
+            .into_iter()
+            .filter_map(|id| self.binding_ty(self.scopes.binding(id)))
+            .collect();
+        let ty = Ty::join_all(tys).unwrap_or(Ty::Unknown);
+        self.names.insert(key, Some(ty.clone()));
+        ty
+    }
+
+    /// The type of the value `binding` binds, or `None` if it doesn't change the type of
+    /// the name, as for an augmented assignment like `x += 1`.
+    fn binding_ty(&mut self, binding: &Binding) -> Option<Ty> {
+        let token = self
+            .root
+            .token_at_offset(binding.range.start())
+            .right_biased()?;
+        let ty = match &binding.kind {
+            BindingKind::Def => {
+                let def = token.parent().and_then(ast::DefStmt::cast)?;
+                Ty::Function(Function::from_def(self.db, self.file, &def))
+            }
+            BindingKind::Parameter => {
+                let param = token.parent().and_then(ast::Parameter::cast)?;
+                let declared = declared_ty(param.type_expr());
+                match (param.kind(), declared, binding.name.as_str()) {
+                    (ast::ParameterKind::Kwargs, declared, _) => {
+                        Ty::dict(Ty::String, declared.unwrap_or(Ty::Unknown))
+                    }
+                    (ast::ParameterKind::Normal, Some(ty), _) => ty,
+                    (ast::ParameterKind::Normal, None, "ctx") if self.bazel => Ty::Ctx,
+                    (ast::ParameterKind::Normal, None, "repository_ctx") if self.bazel => {
+                        Ty::RepositoryCtx
+                    }
+                    _ => Ty::Unknown,
+                }
+            }
+            BindingKind::Assign | BindingKind::LoopVariable => {
+                // Find the value being unpacked, and the position of the name in the
+                // targets, such as `[1, 0]` for `b` in `a, (b, c) = value`.
+                let mut path = Vec::new();
+                let mut target = token.parent()?;
+                loop {
+                    let parent = target.parent()?;
+                    match parent.kind() {
+                        TUPLE_EXPR | LIST_EXPR | LOOP_VARIABLES => {
+                            let siblings: Vec<SyntaxNode> = parent
+                                .children()
+                                .filter(|node| ast::Expr::can_cast(node.kind()))
+                                .collect();
+                            if parent.kind() != LOOP_VARIABLES || siblings.len() > 1 {
+                                path.push(siblings.iter().position(|node| *node == target)?);
+                            }
+                            target = parent;
+                        }
+                        _ => break,
+                    }
+                }
+                let value = match target.parent().map(|parent| (parent.kind(), parent)) {
+                    Some((ASSIGN_STMT, stmt)) => {
+                        let stmt = ast::AssignStmt::cast(stmt)?;
+                        let is_plain_assignment = stmt
+                            .syntax()
+                            .children_with_tokens()
+                            .any(|el| el.kind() == T![=]);
+                        if !is_plain_assignment {
+                            return None;
+                        }
+                        match declared_ty(stmt.type_expr()) {
+                            Some(ty) if path.is_empty() => return Some(ty),
+                            _ => self.infer_opt(stmt.rhs()),
+                        }
+                    }
+                    Some((FOR_STMT, stmt)) => {
+                        self.infer_opt(ast::ForStmt::cast(stmt)?.expr()).elem()
+                    }
+                    Some((LIST_COMP_FOR, clause)) => {
+                        self.infer_opt(ast::CompFor::cast(clause)?.expr()).elem()
+                    }
+                    _ => Ty::Unknown,
+                };
+                path.into_iter().rev().fold(value, |ty, index| match ty {
+                    Ty::Tuple(mut elems) if index < elems.len() => elems.swap_remove(index),
+                    Ty::List(elem) => *elem,
+                    _ => Ty::Unknown,
+                })
+            }
+            BindingKind::Load { module, name } => self.loaded_ty(module, name),
+        };
+        Some(ty)
+    }
+
+    /// The type of `name` at the top level of the file loaded as `module`.
+    fn loaded_ty(&mut self, module: &str, name: &str) -> Ty {
+        if self.mode == Mode::Loaded {
+            return Ty::Unknown;
+        }
+        let file = match star_db::resolve_load(self.db.as_source_db(), self.file, module) {
+            Some(file) => file,
+            None => return Ty::Unknown,
+        };
+        let mut ctx = InferenceContext::new(self.db, file, None, Mode::Loaded);
+        let module_scope = ctx.scopes.module_scope();
+        match ctx.scopes.resolve_name(module_scope, name) {
+            Resolution::Bindings { .. } => ctx.name_ty(module_scope, name),
+            Resolution::Unresolved => Ty::Unknown,
+        }
+    }
+
+    fn check_indexable(&mut self, expr: Option<ast::Expr>, ty: &Ty) {
+        if let Some(expr) = expr {
+            if ty.variants().iter().all(|ty| !is_indexable(ty)) {
+                self.error(
+                    expr.syntax().text_range(),
+                    TypeErrorKind::NotIndexable(ty.clone()),
+                );
+            }
+        }
+    }
+
+    /// Checks that the arguments of a call match the parameters of the function it
+    /// calls, when that function is defined by a `def` statement. Arguments unpacked
+    /// with `*` or `**` could match any parameters.
+    fn check_arguments(&mut self, call: &ast::CallExpr, callee: &Ty, arguments: &[ast::Argument]) {
+        let mut keywords = HashSet::new();
+        for arg in arguments {
+            if let (ast::ArgumentKind::Normal, Some(name)) = (arg.kind(), arg.name()) {
+                if !keywords.insert(name.syntax().text().to_string()) {
+                    let kind = TypeErrorKind::DuplicateKeyword(name.syntax().text().to_string());
+                    self.error(name.syntax().text_range(), kind);
+                }
+            }
+        }
+
+        let def = match callee {
+            Ty::Function(function) => {
+                let parse = star_db::parse(self.db.as_source_db(), function.file(self.db));
+                match function.def(self.db, &parse.syntax()) {
+                    Some(def) => def,
+                    None => return,
+                }
+            }
+            _ => return,
+        };
+        let params = def
+            .parameters()
+            .map(|params| params.parameters())
+            .unwrap_or_default();
+        let positional_params = params
+            .iter()
+            .take_while(|param| matches!(param.kind(), ast::ParameterKind::Normal))
+            .count();
+        let has_args = params.iter().any(|param| {
+            matches!(param.kind(), ast::ParameterKind::Args) && param.name().is_some()
+        });
+        let has_kwargs = params
+            .iter()
+            .any(|param| matches!(param.kind(), ast::ParameterKind::Kwargs));
+        let unpacked = arguments
+            .iter()
+            .any(|arg| !matches!(arg.kind(), ast::ArgumentKind::Normal));
+        let positional_args: Vec<&ast::Argument> = arguments
+            .iter()
+            .filter(|arg| matches!(arg.kind(), ast::ArgumentKind::Normal) && arg.name().is_none())
+            .collect();
+
+        if !has_args && positional_args.len() > positional_params {
+            let kind = TypeErrorKind::TooManyArguments {
+                expected: positional_params,
+                found: positional_args.len(),
+            };
+            self.error(
+                positional_args[positional_params].syntax().text_range(),
+                kind,
+            );
+        }
+        if !has_kwargs {
+            for arg in arguments {
+                let name = match (arg.kind(), arg.name()) {
+                    (ast::ArgumentKind::Normal, Some(name)) => name,
+                    _ => continue,
+                };
+                let known = params.iter().any(|param| {
+                    matches!(param.kind(), ast::ParameterKind::Normal)
+                        && matches!(param.name(), Some(param) if param.syntax().text() == name.syntax().text())
+                });
+                if !known {
+                    let kind = TypeErrorKind::UnknownKeyword(name.syntax().text().to_string());
+                    self.error(name.syntax().text_range(), kind);
+                }
+            }
+        }
+        if !unpacked {
+            for (index, param) in params.iter().enumerate() {
+                let name = match (param.kind(), param.name()) {
+                    (ast::ParameterKind::Normal, Some(name)) => name.syntax().text().to_string(),
+                    _ => continue,
+                };
+                let passed = (index < positional_params && index < positional_args.len())
+                    || keywords.contains(&name);
+                if param.default().is_none() && !passed {
+                    let range = match call.arguments() {
+                        Some(args) => args.syntax().text_range(),
+                        None => call.syntax().text_range(),
+                    };
+                    self.error(range, TypeErrorKind::MissingArgument(name));
+                }
+            }
+        }
+    }
+
+    fn call_ty(&mut self, callee: Ty, args: &[Ty]) -> Ty {
+        match callee {
+            Ty::Function(function) if self.mode == Mode::Full => {
+                function_return_ty(self.db, function).clone()
+            }
+            Ty::Builtin(builtin) => builtin_call_ty(builtin.name, args),
+            Ty::Method(receiver, method) => method_call_ty(*receiver, method.name, args),
+            _ => Ty::Unknown,
+        }
+    }
+}
+
+/// The `def` statement whose body `scope` is in, or `None` for the top level.
+fn owner(scopes: &FileScopes, scope: ScopeId) -> Option<TextRange> {
+    let mut current = Some(scope);
+    while let Some(id) = current {
+        let scope = scopes.scope(id);
+        match scope.kind {
+            ScopeKind::Function => return Some(scope.range),
+            ScopeKind::Module => return None,
+            ScopeKind::Lambda | ScopeKind::Comprehension => current = scope.parent,
+        }
+    }
+    None
+}
+
+/// Finds the `def` statement at `range`.
+fn find_def(root: &SyntaxNode, range: TextRange) -> Option<ast::DefStmt> {
+    root.descendants()
+        .filter(|node| node.text_range() == range)
+        .find_map(ast::DefStmt::cast)
+}
+
+/// The `def` statements directly in `body`, a `def` statement or the root of a file,
+/// without those nested in other functions.
+fn body_defs(body: &SyntaxNode) -> Vec<ast::DefStmt> {
+    fn collect(node: &SyntaxNode, defs: &mut Vec<ast::DefStmt>) {
+        for child in node.children() {
+            match ast::DefStmt::cast(child.clone()) {
+                Some(def) => defs.push(def),
+                None => collect(&child, defs),
+            }
+        }
+    }
+
+    let mut defs = Vec::new();
+    collect(body, &mut defs);
+    defs
+}
+
+fn def_name(def: &ast::DefStmt) -> String {
+    def.name()
+        .map(|name| name.syntax().text().to_string())
+        .unwrap_or_default()
+}
+
+fn is_callable(ty: &Ty) -> bool {
+    matches!(
+        ty,
+        Ty::Unknown | Ty::Function(_) | Ty::Builtin(_) | Ty::Method(..)
+    )
+}
+
+fn is_indexable(ty: &Ty) -> bool {
+    matches!(
+        ty,
+        Ty::Unknown | Ty::String | Ty::List(_) | Ty::Tuple(_) | Ty::Dict(..)
+    )
+}
+
+/// Whether a value of type `ty` can be iterated over. Unlike in Python, strings can't.
+fn is_iterable(ty: &Ty) -> bool {
+    matches!(ty, Ty::Unknown | Ty::List(_) | Ty::Tuple(_) | Ty::Dict(..))
+}
+
+/// Whether values of type `ty` may have the attribute `name`. Only the members of the
+/// core types are known in full; Bazel's types may have members not listed here.
+fn has_attribute(ty: &Ty, name: &str) -> bool {
+    !is_core(ty) || ty.members().iter().any(|member| member.name == name)
+}
+
+/// Whether the end of `suite` can be reached, rather than it always ending in a
+/// `return` statement or a call to `fail`.
+fn falls_through(suite: &ast::Suite) -> bool {
+    match suite.statements().last() {
+        Some(ast::Stmt::SimpleStmt(stmt)) => match stmt.statements().last() {
+            Some(ast::SmallStmt::ReturnStmt(_)) => false,
+            Some(ast::SmallStmt::ExprStmt(ast::Expr::CallExpr(call))) => !matches!(
+                call.expr(),
+                Some(ast::Expr::Literal(literal)) if literal.syntax().text() == "fail"
+            ),
+            _ => true,
+        },
+        Some(ast::Stmt::IfStmt(stmt)) => match stmt.else_suite() {
+            Some(else_suite) => {
+                let if_falls_through = match stmt.if_suite() {
+                    Some(suite) => falls_through(&suite),
+                    None => true,
+                };
+                if_falls_through
+                    || stmt.elif_suites().iter().any(falls_through)
+                    || falls_through(&else_suite)
+            }
+            None => true,
+        },
+        _ => true,
+    }
+}
+
+/// The type declared by an annotation such as `list[str]` or `int | None`, or `None` if
+/// the annotation is missing or doesn't only name builtin types.
+fn declared_ty(annotation: Option<ast::Expr>) -> Option<Ty> {
+    match annotation_ty(&annotation?) {
+        Ty::Unknown => None,
+        ty => Some(ty),
+    }
+}
+
+fn annotation_ty(expr: &ast::Expr) -> Ty {
+    match expr {
+        ast::Expr::Literal(literal) => {
+            // Older dialects spell types as strings, as in `x: "string"`.
+            let name = match literal.kind() {
+                ast::LiteralKind::Ident(ident) => ident.syntax().text().to_string(),
+                ast::LiteralKind::String(string) => string.value().unwrap_or_default(),
+                _ => return Ty::Unknown,
+            };
+            match name.as_str() {
+                "None" | "NoneType" => Ty::None,
+                "bool" => Ty::Bool,
+                "int" => Ty::Int,
+                "float" => Ty::Float,
+                "str" | "string" => Ty::String,
+                "list" => Ty::list(Ty::Unknown),
+                "dict" => Ty::dict(Ty::Unknown, Ty::Unknown),
+                "struct" => Ty::Struct,
+                _ => Ty::Unknown,
+            }
+        }
+        ast::Expr::IndexExpr(index) => {
+            let name = match index.expr() {
+                Some(ast::Expr::Literal(literal)) => literal.syntax().text().to_string(),
+                _ => return Ty::Unknown,
+            };
+            let args: Vec<Ty> = match index.index() {
+                Some(ast::Expr::TupleExpr(tuple)) => {
+                    tuple.exprs().map(|arg| annotation_ty(&arg)).collect()
+                }
+                Some(arg) => vec![annotation_ty(&arg)],
+                None => Vec::new(),
+            };
+            match (name.as_str(), args.as_slice()) {
+                ("list", [elem]) => Ty::list(elem.clone()),
+                ("dict", [key, value]) => Ty::dict(key.clone(), value.clone()),
+                ("tuple", _) => Ty::Tuple(args),
+                _ => Ty::Unknown,
+            }
+        }
+        ast::Expr::BinaryExpr(binary) => match binary.op_details() {
+            Some((_, BinaryOp::BitOr)) => {
+                let lhs = binary.lhs().map_or(Ty::Unknown, |lhs| annotation_ty(&lhs));
+                let rhs = binary.rhs().map_or(Ty::Unknown, |rhs| annotation_ty(&rhs));
+                lhs.join(rhs)
+            }
+            _ => Ty::Unknown,
+        },
+        _ => Ty::Unknown,
+    }
+}
+
+fn int_literal(expr: &ast::Expr) -> Option<usize> {
+    match expr {
+        ast::Expr::Literal(literal) => match literal.kind() {
+            ast::LiteralKind::Int(int) => int.syntax().text().parse().ok(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The type of applying `op` to operands of types `lhs` and `rhs`, or `None` if the
+/// operator doesn't support them. Operands whose types aren't known exactly, such as
+/// unions, are assumed to be supported.
+fn binary_ty(op: BinaryOp, lhs: Ty, rhs: Ty) -> Option<Ty> {
+    use BinaryOp::*;
+
+    let ty = match (op, lhs, rhs) {
+        (And | Or, lhs, rhs) => lhs.join(rhs),
+        (Eq | Ne | Lt | Gt | Le | Ge | In | NotIn, _, _) => Ty::Bool,
+        (Add | Sub | Mul | FloorDiv | Mod, Ty::Int, Ty::Int) => Ty::Int,
+        (BitOr | BitXor | BitAnd | BitShiftLeft | BitShiftRight, Ty::Int, Ty::Int) => Ty::Int,
+        (Add | Sub | Mul | Div | FloorDiv | Mod, Ty::Int | Ty::Float, Ty::Int | Ty::Float) => {
+            Ty::Float
+        }
+        (Add, Ty::String, Ty::String) | (Mod, Ty::String, _) => Ty::String,
+        (Mul, Ty::String, Ty::Int) | (Mul, Ty::Int, Ty::String) => Ty::String,
+        (Add, Ty::List(a), Ty::List(b)) => Ty::list(a.join(*b)),
+        (Mul, list @ Ty::List(_), Ty::Int) | (Mul, Ty::Int, list @ Ty::List(_)) => list,
+        (Add, Ty::Tuple(mut a), Ty::Tuple(b)) => {
+            a.extend(b);
+            Ty::Tuple(a)
+        }
+        (Mul, Ty::Tuple(_), Ty::Int) | (Mul, Ty::Int, Ty::Tuple(_)) => Ty::Unknown,
+        (BitOr, lhs @ Ty::Dict(..), rhs @ Ty::Dict(..)) => lhs.join(rhs),
+        (_, lhs, rhs) if is_core(&lhs) && is_core(&rhs) => return None,
+        _ => Ty::Unknown,
+    };
+    Some(ty)
+}
+
+/// Whether `ty` is exactly one of the core Starlark types, whose operations are all
+/// known.
+fn is_core(ty: &Ty) -> bool {
+    matches!(
+        ty,
+        Ty::None
+            | Ty::Bool
+            | Ty::Int
+            | Ty::Float
+            | Ty::String
+            | Ty::List(_)
+            | Ty::Tuple(_)
+            | Ty::Dict(..)
+            | Ty::Function(_)
+            | Ty::Builtin(_)
+            | Ty::Method(..)
+    )
+}
+
+/// The type returned by calling the predeclared function `name` with positional
+/// arguments of types `args`.
+fn builtin_call_ty(name: &str, args: &[Ty]) -> Ty {
+    let first = args.first().cloned().unwrap_or(Ty::Unknown);
+    match name {
+        "abs" => match first {
+            Ty::Int | Ty::Float => first,
+            _ => Ty::Unknown,
+        },
+        "all" | "any" | "bool" | "hasattr" => Ty::Bool,
+        "dict" => Ty::dict(Ty::Unknown, Ty::Unknown),
+        "dir" => Ty::list(Ty::String),
+        "enumerate" => Ty::list(Ty::Tuple(vec![Ty::Int, first.elem()])),
+        "fail" | "print" => Ty::None,
+        "float" => Ty::Float,
+        "hash" | "int" | "len" => Ty::Int,
+        "list" | "reversed" | "sorted" => Ty::list(first.elem()),
+        "max" | "min" if args.len() == 1 => first.elem(),
+        "max" | "min" => Ty::join_all(args.iter().cloned()).unwrap_or(Ty::Unknown),
+        "range" => Ty::list(Ty::Int),
+        "repr" | "str" | "type" => Ty::String,
+        "struct" => Ty::Struct,
+        "zip" => Ty::list(Ty::Tuple(args.iter().map(Ty::elem).collect())),
+        _ => Ty::Unknown,
+    }
+}
+
+/// The type returned by calling the method `name` of a value of type `receiver`.
+fn method_call_ty(receiver: Ty, name: &str, args: &[Ty]) -> Ty {
+    match (receiver, name) {
+        (Ty::String, "count" | "find" | "index" | "rfind" | "rindex") => Ty::Int,
+        (Ty::String, "endswith" | "startswith") => Ty::Bool,
+        (Ty::String, name) if name.starts_with("is") => Ty::Bool,
+        (Ty::String, "elems" | "rsplit" | "split" | "splitlines") => Ty::list(Ty::String),
+        (Ty::String, "partition" | "rpartition") => Ty::Tuple(vec![Ty::String; 3]),
+        (Ty::String, _) => Ty::String,
+        (Ty::List(elem), "pop") => *elem,
+        (Ty::List(_), "index") => Ty::Int,
+        (Ty::List(_), _) => Ty::None,
+        (Ty::Dict(_, value), "get") => value.join(args.get(1).cloned().unwrap_or(Ty::None)),
+        (Ty::Dict(_, value), "pop" | "setdefault") => *value,
+        (Ty::Dict(key, _), "keys") => Ty::list(*key),
+        (Ty::Dict(_, value), "values") => Ty::list(*value),
+        (Ty::Dict(key, value), "items") => Ty::list(Ty::Tuple(vec![*key, *value])),
+        (Ty::Dict(key, value), "popitem") => Ty::Tuple(vec![*key, *value]),
+        (Ty::Dict(..), _) => Ty::None,
+        (Ty::Native, "glob" | "subpackages") => Ty::list(Ty::String),
+        (Ty::Native, "package_name" | "repository_name") => Ty::String,
+        (Ty::Native, "existing_rule" | "existing_rules") => Ty::Unknown,
+        (Ty::Native, _) => Ty::None,
+        (Ty::Ctx, "expand_location" | "expand_make_variables") => Ty::String,
+        (Ty::Ctx, "coverage_instrumented" | "target_platform_has_constraint") => Ty::Bool,
+        (Ty::RepositoryCtx, "read") => Ty::String,
+        (Ty::RepositoryCtx, "getenv") => Ty::String.join(args.get(1).cloned().unwrap_or(Ty::None)),
+        _ => Ty::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RootDatabase;
+    use star_db::SourceDatabase;
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        path::{Path, PathBuf},
+    };
+
+    /// Infers the type of the last expression statement in the last file of `files`.
+    fn infer_in(files: &[(&str, &str)]) -> Ty {
+        let mut db = SourceDatabase::<RootDatabase>::default();
+        db.set_workspace(PathBuf::from("/"), BTreeMap::new());
+        db.set_packages(BTreeSet::from([PathBuf::from("/")]));
+        for (path, text) in files {
+            db.set_overlay(Path::new(path), text.to_string());
+        }
+        infer_last(&db, files.last().unwrap().0)
+    }
+
+    /// Infers the type of the last expression statement in the file at `path`.
+    fn infer_last(db: &SourceDatabase<RootDatabase>, path: &str) -> Ty {
+        let file = db.file(Path::new(path)).unwrap();
+        let parse = star_db::parse(&db.db, file);
+        let expr = parse
+            .syntax()
+            .descendants()
+            .filter_map(ast::Expr::cast)
+            .filter(|expr| expr.syntax().parent().unwrap().kind() == SIMPLE_STMT)
+            .last()
+            .unwrap();
+        infer_expr(&db.db, file, &expr)
+    }
+
+    fn infer(path: &str, text: &str) -> Ty {
+        infer_in(&[(path, text)])
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(infer("/a.bzl", "1\n"), Ty::Int);
+        assert_eq!(infer("/a.bzl", "1.5\n"), Ty::Float);
+        assert_eq!(infer("/a.bzl", "'a'\n"), Ty::String);
+        assert_eq!(infer("/a.bzl", "None\n"), Ty::None);
+        assert_eq!(infer("/a.bzl", "True\n"), Ty::Bool);
+        assert_eq!(infer("/a.bzl", "f()\n"), Ty::Unknown);
+    }
+
+    #[test]
+    fn collections() {
+        assert_eq!(infer("/a.bzl", "[1, 2]\n"), Ty::list(Ty::Int));
+        assert_eq!(infer("/a.bzl", "[]\n"), Ty::list(Ty::Unknown));
+        assert_eq!(
+            infer("/a.bzl", "1, 'a'\n"),
+            Ty::Tuple(vec![Ty::Int, Ty::String])
+        );
+        assert_eq!(infer("/a.bzl", "{'a': 1}\n"), Ty::dict(Ty::String, Ty::Int));
+        assert_eq!(
+            infer("/a.bzl", "[str(x) for x in range(3)]\n"),
+            Ty::list(Ty::String)
+        );
+        assert_eq!(
+            infer("/a.bzl", "{k: v for k, v in [('a', 1)]}\n"),
+            Ty::dict(Ty::String, Ty::Int)
+        );
+        assert_eq!(
+            infer("/a.bzl", "{'a': [1]}.get('a')\n"),
+            Ty::Union(vec![Ty::list(Ty::Int), Ty::None])
+        );
+        assert_eq!(infer("/a.bzl", "(1, 'a')[1]\n"), Ty::String);
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(infer("/a.bzl", "1 + 2\n"), Ty::Int);
+        assert_eq!(infer("/a.bzl", "1 / 2\n"), Ty::Float);
+        assert_eq!(infer("/a.bzl", "'a' + 'b'\n"), Ty::String);
+        assert_eq!(infer("/a.bzl", "'%s' % 1\n"), Ty::String);
+        assert_eq!(
+            infer("/a.bzl", "[1] + ['a']\n"),
+            Ty::list(Ty::Union(vec![Ty::Int, Ty::String]))
+        );
+        assert_eq!(infer("/a.bzl", "1 < 2\n"), Ty::Bool);
+        assert_eq!(infer("/a.bzl", "not 1\n"), Ty::Bool);
+        assert_eq!(infer("/a.bzl", "-1.5\n"), Ty::Float);
+        assert_eq!(infer("/a.bzl", "'a' + 1\n"), Ty::Unknown);
+        assert_eq!(
+            infer("/a.bzl", "1 if x else 'a'\n"),
+            Ty::Union(vec![Ty::Int, Ty::String])
+        );
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(infer("/a.bzl", "x = []\ny = x\ny\n"), Ty::list(Ty::Unknown));
+        assert_eq!(
+            infer("/a.bzl", "x = [1]\nif x:\n    x = [2]\nx\n"),
+            Ty::list(Ty::Int)
+        );
+        assert_eq!(
+            infer("/a.bzl", "x = None\nif y:\n    x = 1\nx\n"),
+            Ty::Union(vec![Ty::None, Ty::Int])
+        );
+        assert_eq!(infer("/a.bzl", "x = y\ny = x\nx\n"), Ty::Unknown);
+        assert_eq!(infer("/a.bzl", "x = 1\nx += 1\nx\n"), Ty::Int);
+        assert_eq!(
+            infer("/a.bzl", "a, (b, c) = 1, ('a', 2.0)\nb\n"),
+            Ty::String
+        );
+        assert_eq!(
+            infer("/a.bzl", "for k, v in {'a': 1}.items():\n    v\n"),
+            Ty::Int
+        );
+        assert_eq!(infer("/a.bzl", "native\n"), Ty::Native);
+        assert_eq!(infer("/a.star", "native\n"), Ty::Unknown);
+        assert_eq!(infer("/a.bzl", "None = 1\nNone\n"), Ty::Int);
+        assert_eq!(infer("/a.bzl", "x = 1\ndef f():\n    x\n"), Ty::Int);
+        assert_eq!(
+            infer("/a.bzl", "def f(**kwargs):\n    kwargs\n"),
+            Ty::dict(Ty::String, Ty::Unknown)
+        );
+    }
+
+    #[test]
+    fn calls() {
+        assert_eq!(infer("/a.bzl", "len([])\n"), Ty::Int);
+        assert_eq!(infer("/a.bzl", "sorted({'a': 1})\n"), Ty::list(Ty::String));
+        assert_eq!(infer("/a.bzl", "'a,b'.split(',')\n"), Ty::list(Ty::String));
+        assert_eq!(infer("/a.bzl", "def f():\n    return 1\nf()\n"), Ty::Int);
+        assert_eq!(
+            infer("/a.bzl", "def f(x):\n    if x:\n        return 'a'\nf(1)\n"),
+            Ty::Union(vec![Ty::String, Ty::None])
+        );
+        assert_eq!(
+            infer(
+                "/a.bzl",
+                "def f(x):\n    if x:\n        return 1\n    else:\n        fail('no')\nf(1)\n"
+            ),
+            Ty::Int
+        );
+        assert_eq!(infer("/a.bzl", "def f():\n    pass\nf()\n"), Ty::None);
+        // Calls in the body of a function aren't followed to infer its return type.
+        assert_eq!(
+            infer("/a.bzl", "def f():\n    return f()\nf()\n"),
+            Ty::Unknown
+        );
+        assert_eq!(
+            infer_in(&[
+                ("/lib.bzl", "def f():\n    return [1]\n"),
+                ("/a.bzl", "load(':lib.bzl', 'f')\nf()\n"),
+            ]),
+            Ty::list(Ty::Int)
+        );
+    }
+
+    #[test]
+    fn functions_survive_edits() {
+        let mut db = SourceDatabase::<RootDatabase>::default();
+        let path = Path::new("/a.bzl");
+        // The function defined by the last `def` statement in `text`.
+        let function = |db: &mut SourceDatabase<RootDatabase>, text: &str| {
+            db.set_overlay(path, text.to_string());
+            let file = db.file(path).unwrap();
+            let parse = star_db::parse(&db.db, file);
+            let def = parse
+                .syntax()
+                .descendants()
+                .filter_map(ast::DefStmt::cast)
+                .last();
+            Function::from_def(&db.db, file, &def.unwrap())
+        };
+
+        let text = "def f():\n    def g():\n        pass\n    return g\ndef f():\n    def g():\n        return 1\n    return g\n";
+        let before = function(&mut db, text);
+        let after = function(&mut db, &format!("x = 1\n\n{}", text));
+        assert_eq!(before, after);
+        assert_eq!(
+            after.path(&db.db),
+            &[("f".to_string(), 1), ("g".to_string(), 0)]
+        );
+        let parse = star_db::parse(&db.db, after.file(&db.db));
+        let def = after.def(&db.db, &parse.syntax()).unwrap();
+        assert_eq!(def.syntax().to_string(), "def g():\n        return 1\n    ");
+    }
+
+    #[test]
+    fn annotations() {
+        assert_eq!(
+            infer("/a.star", "def f(x: str):\n    return x\nf(1)\n"),
+            Ty::String
+        );
+        assert_eq!(
+            infer("/a.star", "def f() -> list[int]:\n    return g()\nf()\n"),
+            Ty::list(Ty::Int)
+        );
+        assert_eq!(
+            infer("/a.star", "def f() -> int | None:\n    pass\nf()\n"),
+            Ty::Union(vec![Ty::Int, Ty::None])
+        );
+        assert_eq!(
+            infer("/a.star", "x: dict[str, bool] = g()\nx\n"),
+            Ty::dict(Ty::String, Ty::Bool)
+        );
+        assert_eq!(
+            infer("/a.star", "def f(**kwargs: int):\n    return kwargs\nf()\n"),
+            Ty::dict(Ty::String, Ty::Int)
+        );
+        // Types we know nothing about don't override what is inferred.
+        assert_eq!(
+            infer("/a.star", "def f() -> Foo:\n    return 1\nf()\n"),
+            Ty::Int
+        );
+    }
+
+    #[test]
+    fn loads_follow_the_workspace() {
+        let mut db = SourceDatabase::<RootDatabase>::default();
+        db.set_overlay(Path::new("/lib/defs.bzl"), "x = 1\n".to_string());
+        db.set_overlay(
+            Path::new("/a.bzl"),
+            "load('//lib:defs.bzl', 'x')\nx\n".to_string(),
+        );
+        assert_eq!(infer_last(&db, "/a.bzl"), Ty::Unknown);
+        db.set_workspace(PathBuf::from("/"), BTreeMap::new());
+        db.set_packages(BTreeSet::from([PathBuf::from("/lib")]));
+        assert_eq!(infer_last(&db, "/a.bzl"), Ty::Int);
+
+        // Loaded files are found once they have been read.
+        db.set_overlay(
+            Path::new("/a.bzl"),
+            "load('//lib:more.bzl', 'y')\ny\n".to_string(),
+        );
+        assert_eq!(infer_last(&db, "/a.bzl"), Ty::Unknown);
+        db.add_files(vec![(
+            PathBuf::from("/lib/more.bzl"),
+            "y = 'a'\n".to_string(),
+        )]);
+        assert_eq!(infer_last(&db, "/a.bzl"), Ty::String);
+    }
+
+    #[test]
+    fn buck_projects_use_buck_dialect() {
+        let mut db = SourceDatabase::<RootDatabase>::default();
+        db.set_overlay(Path::new("/p/defs.bzl"), "x = f'{1}'\n".to_string());
+        let file = db.file(Path::new("/p/defs.bzl")).unwrap();
+        assert_eq!(star_db::parse(&db.db, file).errors().len(), 1);
+        db.set_buck_root(Some(PathBuf::from("/p")));
+        assert_eq!(star_db::parse(&db.db, file).errors(), &[]);
+    }
+}
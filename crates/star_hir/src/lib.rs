@@ -0,0 +1,89 @@
+mod builtins;
+mod diagnostics;
+mod infer;
+mod scope;
+mod symbols;
+mod ty;
+
+pub use crate::{
+    builtins::{builtin, Builtin, BAZEL_BUILTINS, BUILTINS},
+    diagnostics::{TypeError, TypeErrorKind},
+    infer::{
+        function_return_ty, infer_expr, infer_file, infer_function, infer_name, Function,
+        InferenceResult,
+    },
+    scope::{Binding, BindingId, BindingKind, FileScopes, Resolution, Scope, ScopeId, ScopeKind},
+    symbols::{
+        file_index, file_symbols, file_targets, search_symbols, symbol_index, workspace_index,
+        FileIndex, SearchResult, Symbol, SymbolIndex, SymbolKind, Target, WorkspaceIndex,
+    },
+    ty::Ty,
+};
+
+use star_db::File;
+use star_syntax::{
+    ast::{self, AstNode},
+    SyntaxToken,
+};
+
+#[salsa::jar(db = Db)]
+pub struct Jar(
+    file_scopes,
+    file_symbols,
+    file_targets,
+    file_index,
+    workspace_index,
+    symbol_index,
+    Function,
+    infer_file,
+    infer_function,
+    function_return_ty,
+);
+
+pub trait Db: salsa::DbWithJar<Jar> + star_db::Db {
+    fn as_source_db(&self) -> &dyn star_db::Db;
+}
+
+impl<DB> Db for DB
+where
+    DB: salsa::DbWithJar<Jar> + star_db::Db,
+{
+    fn as_source_db(&self) -> &dyn star_db::Db {
+        self
+    }
+}
+
+#[derive(Default)]
+#[salsa::db(star_db::Jar, crate::Jar)]
+pub struct RootDatabase {
+    storage: salsa::Storage<Self>,
+    inputs: star_db::Inputs,
+}
+
+impl star_db::Db for RootDatabase {
+    fn inputs(&self) -> &star_db::Inputs {
+        &self.inputs
+    }
+}
+
+impl salsa::Database for RootDatabase {}
+
+impl salsa::ParallelDatabase for RootDatabase {
+    fn snapshot(&self) -> salsa::Snapshot<Self> {
+        salsa::Snapshot::new(RootDatabase {
+            storage: self.storage.snapshot(),
+            inputs: self.inputs.clone(),
+        })
+    }
+}
+
+#[salsa::tracked(return_ref)]
+pub fn file_scopes(db: &dyn Db, file: File) -> FileScopes {
+    let parse = star_db::parse(db.as_source_db(), file);
+    scope::lower(&ast::File::cast(parse.syntax()).unwrap())
+}
+
+/// Resolves a name token in `file`. See [`FileScopes::resolve_token`].
+pub fn resolve(db: &dyn Db, file: File, token: &SyntaxToken) -> Option<Resolution> {
+    file_scopes(db, file).resolve_token(token)
+}
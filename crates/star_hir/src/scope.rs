@@ -0,0 +1,530 @@
+//! Scope analysis for a single file.
+//!
+//! Starlark is statically scoped. A name bound anywhere in a function body, whether by
+//! an assignment, a `for` loop, a nested `def` or a parameter, is local to the whole
+//! function, even where it is used before the binding. Names bound at the top level of
+//! a file, including those introduced by `load`, are global. Lambdas and comprehensions
+//! introduce scopes of their own; `if` and `for` blocks don't.
+
+use star_syntax::{
+    ast::{self, AstNode, AstToken},
+    SyntaxKind::*,
+    SyntaxNode, SyntaxToken, TextRange, TextSize,
+};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ScopeId(u32);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BindingId(u32);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScopeKind {
+    Module,
+    Function,
+    Lambda,
+    Comprehension,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scope {
+    pub kind: ScopeKind,
+    pub parent: Option<ScopeId>,
+    /// The range of the node that introduces this scope.
+    pub range: TextRange,
+    names: HashMap<String, Vec<BindingId>>,
+}
+
+impl Scope {
+    /// The names bound in this scope, along with all of their bindings.
+    pub fn names(&self) -> impl Iterator<Item = (&str, &[BindingId])> {
+        self.names
+            .iter()
+            .map(|(name, bindings)| (name.as_str(), bindings.as_slice()))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BindingKind {
+    Def,
+    Assign,
+    Parameter,
+    LoopVariable,
+    /// A symbol loaded from `module`, where it is called `name`.
+    Load {
+        module: String,
+        name: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Binding {
+    pub name: String,
+    pub kind: BindingKind,
+    pub scope: ScopeId,
+    /// The range of the token that introduces the name.
+    pub range: TextRange,
+}
+
+/// What a name refers to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    /// The name is bound in `scope`, by each of `bindings`.
+    Bindings {
+        scope: ScopeId,
+        bindings: Vec<BindingId>,
+    },
+    /// The name isn't bound in this file. It may be a builtin, or undefined.
+    Unresolved,
+}
+
+/// The scopes of a file and the names bound in them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileScopes {
+    scopes: Vec<Scope>,
+    bindings: Vec<Binding>,
+    /// The scope every name token appears in, keyed by the token's start offset.
+    names: HashMap<TextSize, ScopeId>,
+}
+
+impl FileScopes {
+    pub fn module_scope(&self) -> ScopeId {
+        ScopeId(0)
+    }
+
+    pub fn scope(&self, id: ScopeId) -> &Scope {
+        &self.scopes[id.0 as usize]
+    }
+
+    pub fn binding(&self, id: BindingId) -> &Binding {
+        &self.bindings[id.0 as usize]
+    }
+
+    pub fn bindings(&self) -> impl Iterator<Item = (BindingId, &Binding)> {
+        self.bindings
+            .iter()
+            .enumerate()
+            .map(|(index, binding)| (BindingId(index as u32), binding))
+    }
+
+    /// The scope introduced by the node at `range`, such as a `def` statement or a
+    /// comprehension. A `def` statement can span the whole file, so the innermost
+    /// scope wins.
+    pub fn scope_for_range(&self, range: TextRange) -> Option<ScopeId> {
+        self.scopes
+            .iter()
+            .rposition(|scope| scope.range == range)
+            .map(|index| ScopeId(index as u32))
+    }
+
+    /// Looks up `name` in `scope` and its ancestors.
+    pub fn resolve_name(&self, scope: ScopeId, name: &str) -> Resolution {
+        let mut current = Some(scope);
+        while let Some(scope) = current {
+            let data = self.scope(scope);
+            if let Some(bindings) = data.names.get(name) {
+                return Resolution::Bindings {
+                    scope,
+                    bindings: bindings.clone(),
+                };
+            }
+            current = data.parent;
+        }
+        Resolution::Unresolved
+    }
+
+    /// The scope a name token appears in, or `None` for tokens that aren't names.
+    pub fn token_scope(&self, token: &SyntaxToken) -> Option<ScopeId> {
+        self.names.get(&token.text_range().start()).copied()
+    }
+
+    /// Resolves a name token, which is either an identifier or the string naming a
+    /// symbol in a `load` statement. Returns `None` for tokens that aren't names, such
+    /// as attribute names and keyword arguments.
+    pub fn resolve_token(&self, token: &SyntaxToken) -> Option<Resolution> {
+        let scope = self.token_scope(token)?;
+        let name = match token.kind() {
+            IDENT => token.text().to_string(),
+            STRING => ast::String::cast(token.clone())?.value()?,
+            _ => return None,
+        };
+        Some(self.resolve_name(scope, &name))
+    }
+}
+
+pub(crate) fn lower(file: &ast::File) -> FileScopes {
+    let mut lowerer = Lowerer {
+        scopes: Vec::new(),
+        bindings: Vec::new(),
+        names: HashMap::new(),
+    };
+    let module = lowerer.new_scope(ScopeKind::Module, None, file.syntax().text_range());
+    for stmt in file.statements() {
+        lowerer.stmt(stmt, module);
+    }
+
+    FileScopes {
+        scopes: lowerer.scopes,
+        bindings: lowerer.bindings,
+        names: lowerer.names,
+    }
+}
+
+struct Lowerer {
+    scopes: Vec<Scope>,
+    bindings: Vec<Binding>,
+    names: HashMap<TextSize, ScopeId>,
+}
+
+impl Lowerer {
+    fn new_scope(&mut self, kind: ScopeKind, parent: Option<ScopeId>, range: TextRange) -> ScopeId {
+        let id = ScopeId(self.scopes.len() as u32);
+        self.scopes.push(Scope {
+            kind,
+            parent,
+            range,
+            names: HashMap::new(),
+        });
+        id
+    }
+
+    fn bind(&mut self, scope: ScopeId, token: &SyntaxToken, name: String, kind: BindingKind) {
+        let id = BindingId(self.bindings.len() as u32);
+        self.scopes[scope.0 as usize]
+            .names
+            .entry(name.clone())
+            .or_default()
+            .push(id);
+        self.bindings.push(Binding {
+            name,
+            kind,
+            scope,
+            range: token.text_range(),
+        });
+        self.names.insert(token.text_range().start(), scope);
+    }
+
+    fn bind_ident(&mut self, scope: ScopeId, ident: &ast::Ident, kind: BindingKind) {
+        let token = ident.syntax();
+        self.bind(scope, token, token.text().to_string(), kind);
+    }
+
+    fn stmt(&mut self, stmt: ast::Stmt, scope: ScopeId) {
+        match stmt {
+            ast::Stmt::DefStmt(def) => {
+                if let Some(name) = def.name() {
+                    self.bind_ident(scope, &name, BindingKind::Def);
+                }
+                let function =
+                    self.new_scope(ScopeKind::Function, Some(scope), def.syntax().text_range());
+                self.parameters(def.parameters(), scope, function);
+                if let Some(ty) = def.return_type() {
+                    self.expr(ty.syntax(), scope);
+                }
+                self.suite(def.suite(), function);
+            }
+            ast::Stmt::IfStmt(stmt) => {
+                for child in stmt.syntax().children() {
+                    match ast::Suite::cast(child.clone()) {
+                        Some(suite) => self.suite(Some(suite), scope),
+                        None => self.expr(&child, scope),
+                    }
+                }
+            }
+            ast::Stmt::ForStmt(stmt) => {
+                if let Some(expr) = stmt.expr() {
+                    self.expr(expr.syntax(), scope);
+                }
+                for variable in stmt.loop_variables() {
+                    self.target(variable, scope, BindingKind::LoopVariable);
+                }
+                self.suite(stmt.suite(), scope);
+            }
+            ast::Stmt::WhileStmt(stmt) => {
+                if let Some(condition) = stmt.condition() {
+                    self.expr(condition.syntax(), scope);
+                }
+                self.suite(stmt.suite(), scope);
+            }
+            ast::Stmt::SimpleStmt(stmt) => {
+                for stmt in stmt.statements() {
+                    self.small_stmt(stmt, scope);
+                }
+            }
+        }
+    }
+
+    fn suite(&mut self, suite: Option<ast::Suite>, scope: ScopeId) {
+        for stmt in suite.iter().flat_map(ast::Suite::statements) {
+            self.stmt(stmt, scope);
+        }
+    }
+
+    fn small_stmt(&mut self, stmt: ast::SmallStmt, scope: ScopeId) {
+        match stmt {
+            ast::SmallStmt::AssignStmt(stmt) => {
+                if let Some(rhs) = stmt.rhs() {
+                    self.expr(rhs.syntax(), scope);
+                }
+                if let Some(ty) = stmt.type_expr() {
+                    self.expr(ty.syntax(), scope);
+                }
+                if let Some(lhs) = stmt.lhs() {
+                    self.target(lhs, scope, BindingKind::Assign);
+                }
+            }
+            ast::SmallStmt::LoadStmt(stmt) => {
+                let module = match stmt.module().and_then(|module| module.value()) {
+                    Some(module) => module,
+                    None => return,
+                };
+                for symbol in stmt.symbols() {
+                    let token = match symbol.name() {
+                        Some(token) => token,
+                        None => continue,
+                    };
+                    if let Some(name) = token.value() {
+                        let kind = BindingKind::Load {
+                            module: module.clone(),
+                            name: name.clone(),
+                        };
+                        self.bind(scope, token.syntax(), name, kind);
+                    }
+                }
+                for alias in stmt.aliases() {
+                    if let (Some(ident), Some(name)) =
+                        (alias.alias(), alias.name().and_then(|name| name.value()))
+                    {
+                        let kind = BindingKind::Load {
+                            module: module.clone(),
+                            name,
+                        };
+                        self.bind_ident(scope, &ident, kind);
+                    }
+                }
+            }
+            stmt => self.expr(stmt.syntax(), scope),
+        }
+    }
+
+    /// Lowers the target of an assignment or a loop, binding the names it contains.
+    fn target(&mut self, target: ast::Expr, scope: ScopeId, kind: BindingKind) {
+        match target {
+            ast::Expr::Literal(literal) => match literal.kind() {
+                ast::LiteralKind::Ident(ident) => self.bind_ident(scope, &ident, kind),
+                _ => self.expr(literal.syntax(), scope),
+            },
+            ast::Expr::TupleExpr(tuple) => {
+                for expr in tuple.exprs() {
+                    self.target(expr, scope, kind.clone());
+                }
+            }
+            ast::Expr::ListExpr(list) => {
+                for expr in list.elements() {
+                    self.target(expr, scope, kind.clone());
+                }
+            }
+            // Attribute and index targets don't bind anything.
+            target => self.expr(target.syntax(), scope),
+        }
+    }
+
+    /// Records the uses of names in an expression. Attribute names and keyword arguments
+    /// are plain identifier tokens rather than literals, so they are skipped.
+    fn expr(&mut self, node: &SyntaxNode, scope: ScopeId) {
+        match node.kind() {
+            LITERAL => {
+                if let Some(literal) = ast::Literal::cast(node.clone()) {
+                    if let ast::LiteralKind::Ident(ident) = literal.kind() {
+                        self.names
+                            .insert(ident.syntax().text_range().start(), scope);
+                    }
+                }
+            }
+            LAMBDA_EXPR => {
+                let lambda = ast::LambdaExpr::cast(node.clone()).unwrap();
+                let inner = self.new_scope(ScopeKind::Lambda, Some(scope), node.text_range());
+                self.parameters(lambda.parameters(), scope, inner);
+                if let Some(body) = lambda.body() {
+                    self.expr(body.syntax(), inner);
+                }
+            }
+            LIST_COMP => {
+                let comp = ast::ListComp::cast(node.clone()).unwrap();
+                let inner = self.comprehension(node, comp.comp_clauses(), scope);
+                if let Some(expr) = comp.expr() {
+                    self.expr(expr.syntax(), inner);
+                }
+            }
+            DICT_COMP => {
+                let comp = ast::DictComp::cast(node.clone()).unwrap();
+                let inner = self.comprehension(node, comp.comp_clauses(), scope);
+                if let Some(entry) = comp.entry() {
+                    self.expr(entry.syntax(), inner);
+                }
+            }
+            _ => {
+                for child in node.children() {
+                    self.expr(&child, scope);
+                }
+            }
+        }
+    }
+
+    /// Lowers the clauses of a comprehension and returns the scope of its body. The
+    /// sequence of the first `for` clause is evaluated in the enclosing scope.
+    fn comprehension(
+        &mut self,
+        node: &SyntaxNode,
+        clauses: Vec<ast::CompClause>,
+        scope: ScopeId,
+    ) -> ScopeId {
+        let inner = self.new_scope(ScopeKind::Comprehension, Some(scope), node.text_range());
+        for (index, clause) in clauses.into_iter().enumerate() {
+            match clause {
+                ast::CompClause::ForComp(clause) => {
+                    if let Some(expr) = clause.expr() {
+                        self.expr(expr.syntax(), if index == 0 { scope } else { inner });
+                    }
+                    for variable in clause.loop_variables() {
+                        self.target(variable, inner, BindingKind::LoopVariable);
+                    }
+                }
+                ast::CompClause::IfComp(clause) => {
+                    if let Some(expr) = clause.expr() {
+                        self.expr(expr.syntax(), inner);
+                    }
+                }
+            }
+        }
+        inner
+    }
+
+    /// Binds parameters in `inner`. Default values and type annotations are evaluated in
+    /// `outer`.
+    fn parameters(&mut self, parameters: Option<ast::Parameters>, outer: ScopeId, inner: ScopeId) {
+        for parameter in parameters.iter().flat_map(ast::Parameters::parameters) {
+            if let Some(ty) = parameter.type_expr() {
+                self.expr(ty.syntax(), outer);
+            }
+            if let Some(default) = parameter.default() {
+                self.expr(default.syntax(), outer);
+            }
+            if let Some(name) = parameter.name() {
+                self.bind_ident(inner, &name, BindingKind::Parameter);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use star_syntax::{parse_file, Dialect};
+
+    /// Resolves the name at `$0` and checks that it resolves to the bindings marked
+    /// with `$1`.
+    fn check(fixture: &str) {
+        let mut text = String::new();
+        let mut cursor = None;
+        let mut expected = Vec::new();
+        let mut rest = fixture;
+        while let Some(index) = rest.find('$') {
+            text.push_str(&rest[..index]);
+            match &rest[index + 1..index + 2] {
+                "0" => cursor = Some(text.len()),
+                "1" => expected.push(text.len()),
+                marker => panic!("unknown marker ${}", marker),
+            }
+            rest = &rest[index + 2..];
+        }
+        text.push_str(rest);
+
+        let parse = parse_file(&text, Dialect::default());
+        let scopes = lower(&ast::File::cast(parse.syntax()).unwrap());
+        let offset = TextSize::try_from(cursor.expect("no cursor")).unwrap();
+        let token = parse
+            .syntax()
+            .token_at_offset(offset)
+            .right_biased()
+            .unwrap();
+
+        let actual: Vec<usize> = match scopes.resolve_token(&token) {
+            Some(Resolution::Bindings { bindings, .. }) => bindings
+                .into_iter()
+                .map(|id| scopes.binding(id).range.start().into())
+                .collect(),
+            _ => Vec::new(),
+        };
+        assert_eq!(actual, expected, "{}", text);
+    }
+
+    #[test]
+    fn global() {
+        check("$1x = 1\ndef f():\n    return $0x\n");
+        check("$1x = 1\nif True:\n    $1x = 2\nprint($0x)\n");
+        check("print($0undefined)\n");
+    }
+
+    #[test]
+    fn assignment_anywhere_makes_local() {
+        check("x = 1\ndef f():\n    print($0x)\n    if True:\n        $1x = 2\n");
+        check("x = 1\ndef f():\n    $1x += 1\n    return $0x\n");
+        check("x = 1\ndef f():\n    for $1x in []:\n        pass\n    return $0x\n");
+        check("x = 1\ndef f():\n    ($1x, y) = 1, 2\n    return $0x\n");
+        check("def f():\n    def $1g():\n        pass\n    return $0g\n");
+    }
+
+    #[test]
+    fn nested_functions() {
+        check("def f():\n    $1x = 1\n    def g():\n        return $0x\n");
+        check("def $1f():\n    return $0f()\n");
+    }
+
+    #[test]
+    fn parameters() {
+        check("def f($1a, b = 1):\n    return $0a\n");
+        check("$1a = 1\ndef f(a, b = $0a):\n    return a\n");
+        check("def f(*$1args, **kwargs):\n    return $0args\n");
+        check("f = lambda $1y: $0y\n");
+    }
+
+    #[test]
+    fn type_annotations() {
+        check("$1T = int\ndef f(a: $0T) -> T:\n    pass\n");
+        check("$1T = int\ndef f(a: T) -> $0T:\n    pass\n");
+        check("def f(T, a: $0T):\n    pass\n");
+        check("$1T = int\nx: $0T = 1\n");
+    }
+
+    #[test]
+    fn comprehensions() {
+        check("x = [1]\ny = [$0x for $1x in x]\n");
+        check("$1x = [1]\ny = [x for x in $0x]\n");
+        check("y = [a for $1a in [] for b in $0a if a]\n");
+        check("y = {$0k: v for $1k, v in []}\n");
+        check("y = [a for a in []]\nz = $0a\n");
+    }
+
+    #[test]
+    fn loads() {
+        check("load(\"//a:b.bzl\", $1\"sym\")\nsym2 = $0sym\n");
+        check("load(\"//a:b.bzl\", $1alias = \"sym\")\n$0alias()\n");
+        check("load(\"//a:b.bzl\", $0$1\"sym\")\n");
+    }
+
+    #[test]
+    fn attributes_and_keywords_are_not_names() {
+        let parse = parse_file("x = 1\nx.x\nf(x = 1)\n", Dialect::default());
+        let scopes = lower(&ast::File::cast(parse.syntax()).unwrap());
+        let names: Vec<usize> = parse
+            .syntax()
+            .descendants_with_tokens()
+            .filter_map(|el| el.into_token())
+            .filter(|token| token.kind() == IDENT && scopes.resolve_token(token).is_some())
+            .map(|token| token.text_range().start().into())
+            .collect();
+        assert_eq!(names, vec![0, 6, 10]);
+    }
+}
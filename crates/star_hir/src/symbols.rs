@@ -0,0 +1,576 @@
+//! Symbol tables: the definitions each file exports, the targets each BUILD file
+//! declares, per-file indexes of both, and a workspace index merging them that is
+//! searched by name.
+
+use crate::{file_scopes, BindingKind, Db};
+use star_db::{File, Workspace, BUILD_FILES};
+use star_syntax::{
+    ast::{self, AstNode, AstToken},
+    SyntaxKind::*,
+    TextRange,
+};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    path::Path,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Variable,
+}
+
+/// A name defined at the top level of a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The range of the name where it is first bound.
+    pub range: TextRange,
+}
+
+/// A target declared in a BUILD file, by a top-level call with a `name` argument.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Target {
+    pub name: String,
+    /// The name of the rule or macro that declares the target, such as `cc_library`.
+    pub rule: String,
+    /// The range of the call.
+    pub range: TextRange,
+    /// The range of the string naming the target.
+    pub name_range: TextRange,
+}
+
+/// The symbols defined at the top level of `file`, in the order they are first bound.
+/// Symbols that are only loaded from another file aren't included.
+#[salsa::tracked(return_ref)]
+pub fn file_symbols(db: &dyn Db, file: File) -> Vec<Symbol> {
+    let scopes = file_scopes(db, file);
+    let mut symbols: Vec<Symbol> = scopes
+        .scope(scopes.module_scope())
+        .names()
+        .filter_map(|(name, bindings)| {
+            let binding = scopes.binding(bindings[0]);
+            let kind = match binding.kind {
+                BindingKind::Def => SymbolKind::Function,
+                BindingKind::Assign | BindingKind::LoopVariable => SymbolKind::Variable,
+                BindingKind::Parameter | BindingKind::Load { .. } => return None,
+            };
+            Some(Symbol {
+                name: name.to_string(),
+                kind,
+                range: binding.range,
+            })
+        })
+        .collect();
+    symbols.sort_by_key(|symbol| symbol.range.start());
+    symbols
+}
+
+/// The targets declared in `file`, in order. Only BUILD files declare targets, but
+/// this doesn't check that `file` is one.
+#[salsa::tracked(return_ref)]
+pub fn file_targets(db: &dyn Db, file: File) -> Vec<Target> {
+    let parse = star_db::parse(db.as_source_db(), file);
+    parse
+        .syntax()
+        .children()
+        .filter(|stmt| stmt.kind() == SIMPLE_STMT)
+        .flat_map(|stmt| stmt.children())
+        .filter_map(ast::CallExpr::cast)
+        .filter_map(|call| {
+            let rule = match call.expr()? {
+                ast::Expr::Literal(literal) => match literal.kind() {
+                    ast::LiteralKind::Ident(ident) => ident.syntax().text().to_string(),
+                    _ => return None,
+                },
+                _ => return None,
+            };
+            let name = call
+                .arguments()?
+                .arguments()
+                .into_iter()
+                .find(|argument| {
+                    matches!(argument.name(), Some(keyword) if keyword.syntax().text() == "name")
+                })?
+                .value()?;
+            let name = match name {
+                ast::Expr::Literal(literal) => match literal.kind() {
+                    ast::LiteralKind::String(name) => name,
+                    _ => return None,
+                },
+                _ => return None,
+            };
+            Some(Target {
+                name: name.value()?,
+                rule,
+                range: call.syntax().text_range(),
+                name_range: name.syntax().text_range(),
+            })
+        })
+        .collect()
+}
+
+/// The names a file defines and the files it loads, without their ranges, so that edits
+/// that only move definitions around leave the index unchanged.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileIndex {
+    /// Sorted by name.
+    entries: Vec<IndexEntry>,
+    /// The files that the `load` statements of this file resolve to, without duplicates.
+    loads: Vec<File>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct IndexEntry {
+    name: String,
+    target: bool,
+    /// The [`char_set`] of the name, which rules out most names without looking at them
+    /// when searching.
+    char_set: u64,
+}
+
+/// Indexes the symbols of `file`, its targets if it is a BUILD file, and its loads.
+#[salsa::tracked(return_ref)]
+pub fn file_index(db: &dyn Db, file: File) -> FileIndex {
+    let source_db = db.as_source_db();
+    let entry = |name: &str, target| IndexEntry {
+        name: name.to_string(),
+        target,
+        char_set: char_set(name),
+    };
+    let mut entries: Vec<IndexEntry> = file_symbols(db, file)
+        .iter()
+        .map(|symbol| entry(&symbol.name, false))
+        .collect();
+    if is_build_file(file.path(source_db)) {
+        entries.extend(
+            file_targets(db, file)
+                .iter()
+                .map(|target| entry(&target.name, true)),
+        );
+    }
+    entries.sort();
+    entries.dedup();
+
+    let mut loads: Vec<File> = Vec::new();
+    for load in star_db::resolve_loads(source_db, file) {
+        if let Ok(loaded) = load.resolution {
+            if !loads.contains(&loaded) {
+                loads.push(loaded);
+            }
+        }
+    }
+    FileIndex { entries, loads }
+}
+
+/// The files that load each file of a workspace.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorkspaceIndex {
+    loaded_by: HashMap<File, Vec<File>>,
+}
+
+impl WorkspaceIndex {
+    /// The files with a `load` statement that resolves to `file`.
+    pub fn loaded_by(&self, file: File) -> &[File] {
+        self.loaded_by.get(&file).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Indexes the loads between the files of `workspace` that have been found so far. It is
+/// built from the [`file_index`] of each file, so it is only built again when a file
+/// starts or stops loading another.
+#[salsa::tracked(return_ref)]
+pub fn workspace_index(db: &dyn Db, workspace: Workspace) -> WorkspaceIndex {
+    let source_db = db.as_source_db();
+    let mut index = WorkspaceIndex::default();
+    for &file in workspace.files(source_db) {
+        if !file.exists(source_db) {
+            continue;
+        }
+        for &loaded in &file_index(db, file).loads {
+            index.loaded_by.entry(loaded).or_default().push(file);
+        }
+    }
+    index
+}
+
+/// The names of the symbols and targets of a workspace, merged from the [`file_index`] of
+/// each file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SymbolIndex {
+    /// Each name once, sorted by its lowercase form so that the names starting with a
+    /// query are next to each other.
+    names: Vec<IndexedName>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct IndexedName {
+    name: String,
+    lower: String,
+    char_set: u64,
+    /// The files defining a symbol with this name, then the files declaring a target with
+    /// it, marked by `true`.
+    locations: Vec<(File, bool)>,
+}
+
+/// Indexes the symbols and targets of the files of `workspace` that have been found so
+/// far. It is built from the [`file_index`] of each file, so it is only built again when
+/// a file's names change, not when definitions only move around.
+#[salsa::tracked(return_ref)]
+pub fn symbol_index(db: &dyn Db, workspace: Workspace) -> SymbolIndex {
+    let source_db = db.as_source_db();
+    let mut names: Vec<IndexedName> = Vec::new();
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    for &file in workspace.files(source_db) {
+        if !file.exists(source_db) {
+            continue;
+        }
+        for entry in &file_index(db, file).entries {
+            let position = *positions.entry(&entry.name).or_insert_with(|| {
+                names.push(IndexedName {
+                    name: entry.name.clone(),
+                    lower: entry.name.chars().flat_map(char::to_lowercase).collect(),
+                    char_set: entry.char_set,
+                    locations: Vec::new(),
+                });
+                names.len() - 1
+            });
+            names[position].locations.push((file, entry.target));
+        }
+    }
+    for name in &mut names {
+        name.locations.sort_by_key(|&(_, target)| target);
+    }
+    names.sort_by(|a, b| a.lower.cmp(&b.lower).then_with(|| a.name.cmp(&b.name)));
+    SymbolIndex { names }
+}
+
+/// A symbol or target found by [`search_symbols`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchResult<'a> {
+    Symbol(File, &'a Symbol),
+    Target(File, &'a Target),
+}
+
+impl<'a> SearchResult<'a> {
+    pub fn name(&self) -> &'a str {
+        match self {
+            SearchResult::Symbol(_, symbol) => &symbol.name,
+            SearchResult::Target(_, target) => &target.name,
+        }
+    }
+}
+
+/// Finds the symbols and targets in `workspace` whose names contain the characters of
+/// `query` in order, ignoring case, best matches first. Exact matches come first, then
+/// prefixes, then names containing the query, then the other names by how many
+/// characters match at the start of a word. At most `limit` results are returned.
+///
+/// The names starting with the query are found in the [`symbol_index`] by binary search,
+/// and the other names are only scored if those don't fill the results. Only the matches
+/// that are returned are looked up in the symbols and targets of their files.
+pub fn search_symbols<'db>(
+    db: &'db dyn Db,
+    workspace: Workspace,
+    query: &str,
+    limit: usize,
+) -> Vec<SearchResult<'db>> {
+    if limit == 0 {
+        return Vec::new();
+    }
+    let names = &symbol_index(db, workspace).names;
+    let query: String = query.chars().flat_map(char::to_lowercase).collect();
+    let query_set = char_set(&query);
+
+    // The best names so far, by score, length and name, with the worst on top.
+    let mut best: BinaryHeap<(Reverse<u32>, usize, &str, usize)> = BinaryHeap::new();
+    let offer = |best: &mut BinaryHeap<_>, index: usize| {
+        let name: &IndexedName = &names[index];
+        if name.char_set & query_set != query_set {
+            return;
+        }
+        if let Some(score) = fuzzy_score(&query, &name.name, &name.lower) {
+            best.push((Reverse(score), name.name.len(), name.name.as_str(), index));
+            if best.len() > limit {
+                best.pop();
+            }
+        }
+    };
+
+    let start = names.partition_point(|name| name.lower < query);
+    let end = start
+        + names[start..]
+            .iter()
+            .take_while(|name| name.lower.starts_with(&query))
+            .count();
+    for index in start..end {
+        offer(&mut best, index);
+    }
+    // Names that don't start with the query score at most this, below any prefix.
+    let bound = 2000 + 10 * query.chars().count() as u32;
+    let full =
+        best.len() == limit && matches!(best.peek(), Some((Reverse(score), ..)) if *score > bound);
+    if !full {
+        for index in (0..start).chain(end..names.len()) {
+            offer(&mut best, index);
+        }
+    }
+
+    best.into_sorted_vec()
+        .into_iter()
+        .flat_map(|(.., index)| {
+            let name = &names[index];
+            name.locations
+                .iter()
+                .map(move |&(file, target)| (file, name.name.as_str(), target))
+        })
+        .filter_map(|(file, name, target)| resolve(db, file, name, target))
+        .take(limit)
+        .collect()
+}
+
+/// Looks up the symbol, or the target if `target` is set, called `name` in `file`.
+fn resolve<'db>(
+    db: &'db dyn Db,
+    file: File,
+    name: &str,
+    target: bool,
+) -> Option<SearchResult<'db>> {
+    if target {
+        let target = file_targets(db, file)
+            .iter()
+            .find(|target| target.name == name)?;
+        Some(SearchResult::Target(file, target))
+    } else {
+        let symbol = file_symbols(db, file)
+            .iter()
+            .find(|symbol| symbol.name == name)?;
+        Some(SearchResult::Symbol(file, symbol))
+    }
+}
+
+/// A bit set of the characters in `name`, ignoring case. Letters, digits and `_` each
+/// have a bit, and other characters share the rest.
+fn char_set(name: &str) -> u64 {
+    name.chars()
+        .flat_map(char::to_lowercase)
+        .fold(0, |set, c| set | char_bit(c))
+}
+
+fn char_bit(c: char) -> u64 {
+    let index = match c {
+        'a'..='z' => c as u32 - 'a' as u32,
+        '0'..='9' => 26 + c as u32 - '0' as u32,
+        '_' => 36,
+        _ => 37 + c as u32 % 27,
+    };
+    1 << index
+}
+
+/// Scores how well `name`, whose lowercase form is `lower`, matches the lowercase
+/// `query`, or returns `None` if the characters of `query` don't appear in `name` in
+/// order.
+fn fuzzy_score(query: &str, name: &str, lower: &str) -> Option<u32> {
+    if lower == query {
+        return Some(u32::MAX);
+    }
+    let mut score = if lower.starts_with(query) {
+        3000
+    } else if lower.contains(query) {
+        2000
+    } else {
+        0
+    };
+    // The lowercase characters of `name`, and whether each starts a word, as in
+    // `cc_library` or `CcInfo`.
+    let mut chars = name
+        .chars()
+        .scan(None, |previous: &mut Option<char>, c| {
+            let word_start = match *previous {
+                None => true,
+                Some(previous) => previous == '_' || (previous.is_lowercase() && c.is_uppercase()),
+            };
+            *previous = Some(c);
+            Some(
+                c.to_lowercase()
+                    .enumerate()
+                    .map(move |(i, lower)| (lower, word_start && i == 0)),
+            )
+        })
+        .flatten();
+    for c in query.chars() {
+        let (_, word_start) = chars.find(|&(lower, _)| lower == c)?;
+        if word_start {
+            score += 10;
+        }
+    }
+    Some(score)
+}
+
+fn is_build_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|name| name.to_str());
+    matches!(name, Some(name) if BUILD_FILES.contains(&name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RootDatabase;
+    use star_db::SourceDatabase;
+    use std::{collections::BTreeMap, path::PathBuf};
+
+    fn build_file(text: &str) -> (SourceDatabase<RootDatabase>, File) {
+        let mut db = SourceDatabase::default();
+        db.set_overlay(Path::new("/BUILD"), text.to_string());
+        let file = db.file(Path::new("/BUILD")).unwrap();
+        (db, file)
+    }
+
+    #[test]
+    fn symbols() {
+        let (db, file) = build_file(
+            r#"
+load("//:defs.bzl", "rule")
+def f():
+    x = 1
+y = 2
+def y():
+    pass
+for z in []:
+    pass
+"#,
+        );
+        let symbols: Vec<(&str, SymbolKind)> = file_symbols(&db.db, file)
+            .iter()
+            .map(|symbol| (symbol.name.as_str(), symbol.kind))
+            .collect();
+        assert_eq!(
+            symbols,
+            [
+                ("f", SymbolKind::Function),
+                ("y", SymbolKind::Variable),
+                ("z", SymbolKind::Variable)
+            ]
+        );
+    }
+
+    #[test]
+    fn targets() {
+        let (db, file) = build_file(
+            r#"
+cc_library(name = "lib", srcs = ["lib.cc"])
+cc_binary(
+    name = "bin",
+    deps = [":lib"],
+)
+package(default_visibility = ["//visibility:public"])
+native.genrule(name = "attribute")
+def f():
+    cc_library(name = "nested")
+"#,
+        );
+        let targets: Vec<(&str, &str)> = file_targets(&db.db, file)
+            .iter()
+            .map(|target| (target.rule.as_str(), target.name.as_str()))
+            .collect();
+        assert_eq!(targets, [("cc_library", "lib"), ("cc_binary", "bin")]);
+    }
+
+    #[test]
+    fn search() {
+        let mut db = SourceDatabase::<RootDatabase>::default();
+        db.set_workspace(PathBuf::from("/"), BTreeMap::new());
+        db.add_files(vec![
+            (
+                PathBuf::from("/defs.bzl"),
+                "def cc_rule():\n    pass\nCcInfo = 1\nrule = 2\nlarge_thing = 3\n".to_string(),
+            ),
+            (
+                PathBuf::from("/BUILD"),
+                "cc_library(name = \"core\")\n".to_string(),
+            ),
+        ]);
+        let workspace = db.workspace().unwrap();
+        let search = |query: &str| -> Vec<&str> {
+            search_symbols(&db.db, workspace, query, 10)
+                .into_iter()
+                .map(|result| result.name())
+                .collect()
+        };
+        assert_eq!(search("RULE"), ["rule", "cc_rule"]);
+        assert_eq!(search("cr"), ["cc_rule", "core"]);
+        assert_eq!(search("ci"), ["CcInfo"]);
+        assert_eq!(search("xyz"), Vec::<&str>::new());
+        assert_eq!(search_symbols(&db.db, workspace, "", 2).len(), 2);
+    }
+
+    #[test]
+    fn search_merges_files() {
+        let mut db = SourceDatabase::<RootDatabase>::default();
+        db.set_workspace(PathBuf::from("/"), BTreeMap::new());
+        db.add_files(vec![
+            (
+                PathBuf::from("/a.bzl"),
+                "def helper():
+    pass
+help = 1
+"
+                .to_string(),
+            ),
+            (
+                PathBuf::from("/b.bzl"),
+                "helper = 2
+helpers = 3
+whelp = 4
+"
+                .to_string(),
+            ),
+        ]);
+        let workspace = db.workspace().unwrap();
+        let search = |query: &str, limit: usize| -> Vec<(&str, PathBuf)> {
+            search_symbols(&db.db, workspace, query, limit)
+                .into_iter()
+                .map(|result| {
+                    let file = match result {
+                        SearchResult::Symbol(file, _) | SearchResult::Target(file, _) => file,
+                    };
+                    (result.name(), file.path(&db.db).clone())
+                })
+                .collect()
+        };
+        // Both definitions of `helper` are found, in the order of the files.
+        assert_eq!(
+            search("helper", 10),
+            [
+                ("helper", PathBuf::from("/a.bzl")),
+                ("helper", PathBuf::from("/b.bzl")),
+                ("helpers", PathBuf::from("/b.bzl")),
+            ]
+        );
+        // The prefixes fill the results, so `whelp` is left out.
+        assert_eq!(
+            search("help", 2),
+            [
+                ("help", PathBuf::from("/a.bzl")),
+                ("helper", PathBuf::from("/a.bzl")),
+            ]
+        );
+        assert_eq!(search("help", 10).last().unwrap().0, "whelp");
+        assert_eq!(search("help", 0), []);
+    }
+
+    #[test]
+    fn index_ignores_ranges() {
+        let (mut db, file) = build_file("x = 1\ndef f():\n    pass\ncc_library(name = \"lib\")\n");
+        let before = file_index(&db.db, file).clone();
+        db.set_overlay(
+            Path::new("/BUILD"),
+            "def f():\n    return 1\n\ncc_library(\n    name = \"lib\",\n)\nx = 2\n".to_string(),
+        );
+        assert_eq!(*file_index(&db.db, file), before);
+        db.set_overlay(Path::new("/BUILD"), "def g():\n    pass\n".to_string());
+        assert_ne!(*file_index(&db.db, file), before);
+    }
+}
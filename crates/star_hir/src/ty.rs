@@ -0,0 +1,197 @@
+//! The types of Starlark values.
+
+use crate::{
+    builtins::{
+        Builtin, CTX_MEMBERS, DICT_METHODS, LIST_METHODS, NATIVE_MEMBERS, REPOSITORY_CTX_MEMBERS,
+        STRING_METHODS,
+    },
+    Function,
+};
+use std::fmt;
+
+/// The type of a value, as far as it is known statically.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Ty {
+    /// Any value.
+    Unknown,
+    None,
+    Bool,
+    Int,
+    Float,
+    String,
+    /// A list, with the type of its elements.
+    List(Box<Ty>),
+    /// A tuple, with the type of each element.
+    Tuple(Vec<Ty>),
+    /// A dict, with the types of its keys and values.
+    Dict(Box<Ty>, Box<Ty>),
+    /// A struct created by the builtin `struct`.
+    Struct,
+    /// A function defined by a `def` statement.
+    Function(Function),
+    /// A predeclared function.
+    Builtin(&'static Builtin),
+    /// A method of a value of the given type.
+    Method(Box<Ty>, &'static Builtin),
+    /// Bazel's `native` module.
+    Native,
+    /// The context passed to the implementation function of a rule.
+    Ctx,
+    /// The context passed to the implementation function of a repository rule.
+    RepositoryCtx,
+    /// A value of one of several types, such as a name assigned in both branches of an
+    /// `if`. Always has at least two distinct members, none of them unions.
+    Union(Vec<Ty>),
+}
+
+impl Ty {
+    pub fn list(elem: Ty) -> Ty {
+        Ty::List(Box::new(elem))
+    }
+
+    pub fn dict(key: Ty, value: Ty) -> Ty {
+        Ty::Dict(Box::new(key), Box::new(value))
+    }
+
+    /// The type of a value that has either type. Nothing is known about a value that
+    /// may be of an unknown type, so `Unknown` absorbs every other type.
+    pub fn join(self, other: Ty) -> Ty {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Ty::Unknown, _) | (_, Ty::Unknown) => Ty::Unknown,
+            (Ty::List(a), Ty::List(b)) => Ty::list(a.join(*b)),
+            (Ty::Dict(k1, v1), Ty::Dict(k2, v2)) => Ty::dict(k1.join(*k2), v1.join(*v2)),
+            (a, b) => {
+                let mut tys = Vec::new();
+                for ty in [a, b] {
+                    match ty {
+                        Ty::Union(members) => tys.extend(members),
+                        ty => tys.push(ty),
+                    }
+                }
+                let mut union: Vec<Ty> = Vec::new();
+                for ty in tys {
+                    // Lists and dicts with different element types are merged rather
+                    // than kept apart.
+                    match union.iter().position(|member| member.same_kind(&ty)) {
+                        Some(index) => {
+                            let member = std::mem::replace(&mut union[index], Ty::Unknown);
+                            union[index] = member.join(ty);
+                        }
+                        None => union.push(ty),
+                    }
+                }
+                if union.len() == 1 {
+                    union.pop().unwrap()
+                } else {
+                    Ty::Union(union)
+                }
+            }
+        }
+    }
+
+    /// Joins all of `tys`, or returns `None` if there are none.
+    pub fn join_all(tys: impl IntoIterator<Item = Ty>) -> Option<Ty> {
+        tys.into_iter().reduce(Ty::join)
+    }
+
+    fn same_kind(&self, other: &Ty) -> bool {
+        match (self, other) {
+            (Ty::List(_), Ty::List(_)) | (Ty::Dict(..), Ty::Dict(..)) => true,
+            (a, b) => a == b,
+        }
+    }
+
+    /// The types a value of this type may have: the members of a union, or else just
+    /// this type.
+    pub fn variants(&self) -> &[Ty] {
+        match self {
+            Ty::Union(members) => members,
+            ty => std::slice::from_ref(ty),
+        }
+    }
+
+    /// Whether a value of this type may be `None`.
+    pub fn is_optional(&self) -> bool {
+        match self {
+            Ty::None => true,
+            Ty::Union(members) => members.contains(&Ty::None),
+            _ => false,
+        }
+    }
+
+    /// The type of the elements produced by iterating over a value of this type.
+    pub fn elem(&self) -> Ty {
+        match self {
+            Ty::List(elem) => (**elem).clone(),
+            Ty::Tuple(elems) => Ty::join_all(elems.iter().cloned()).unwrap_or(Ty::Unknown),
+            Ty::Dict(key, _) => (**key).clone(),
+            _ => Ty::Unknown,
+        }
+    }
+
+    /// The methods and fields of values of this type. A value that may be `None` has the
+    /// members its other types have in common.
+    pub fn members(&self) -> &'static [Builtin] {
+        match self {
+            Ty::String => STRING_METHODS,
+            Ty::List(_) => LIST_METHODS,
+            Ty::Dict(..) => DICT_METHODS,
+            Ty::Native => NATIVE_MEMBERS,
+            Ty::Ctx => CTX_MEMBERS,
+            Ty::RepositoryCtx => REPOSITORY_CTX_MEMBERS,
+            Ty::Union(members) => {
+                let mut members = members
+                    .iter()
+                    .filter(|ty| **ty != Ty::None)
+                    .map(Ty::members);
+                let first = members.next().unwrap_or(&[]);
+                if members.all(|other| std::ptr::eq(first, other)) {
+                    first
+                } else {
+                    &[]
+                }
+            }
+            _ => &[],
+        }
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ty::Unknown => f.write_str("unknown"),
+            Ty::None => f.write_str("None"),
+            Ty::Bool => f.write_str("bool"),
+            Ty::Int => f.write_str("int"),
+            Ty::Float => f.write_str("float"),
+            Ty::String => f.write_str("str"),
+            Ty::List(elem) => write!(f, "list[{}]", elem),
+            Ty::Tuple(elems) => {
+                f.write_str("tuple[")?;
+                for (i, elem) in elems.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", elem)?;
+                }
+                f.write_str("]")
+            }
+            Ty::Dict(key, value) => write!(f, "dict[{}, {}]", key, value),
+            Ty::Struct => f.write_str("struct"),
+            Ty::Function(_) | Ty::Builtin(_) | Ty::Method(..) => f.write_str("function"),
+            Ty::Native => f.write_str("native"),
+            Ty::Ctx => f.write_str("ctx"),
+            Ty::RepositoryCtx => f.write_str("repository_ctx"),
+            Ty::Union(members) => {
+                for (i, member) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" | ")?;
+                    }
+                    write!(f, "{}", member)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,588 @@
+use crate::FilePosition;
+use star_hir::{BindingKind, Db, FileScopes, ScopeId, ScopeKind, BAZEL_BUILTINS, BUILTINS};
+use star_syntax::{
+    ast::{self, AstNode, AstToken},
+    SyntaxKind::*,
+    SyntaxNode, SyntaxToken, TextRange, TextSize,
+};
+use std::{collections::HashSet, mem};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompletionItem {
+    pub label: String,
+    pub kind: CompletionKind,
+    /// A short description, such as the signature of a function.
+    pub detail: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompletionKind {
+    Function,
+    Variable,
+    Parameter,
+    Constant,
+    Keyword,
+    Method,
+    Field,
+}
+
+/// Where the name being completed appears.
+enum Location {
+    /// At the start of a statement, where statement keywords are allowed.
+    Statement,
+    /// Where an expression starts.
+    Expression,
+    /// After a complete expression, where only operators and clauses can follow.
+    AfterExpression,
+    /// After a dot, naming an attribute of `receiver`.
+    Attribute { receiver: ast::Expr },
+}
+
+/// Completes the name at `position` with the names in scope there, the predeclared
+/// names and the keywords that can appear there, or with the members of the inferred
+/// type of the receiver after a dot. There are no completions inside strings and
+/// comments, or for the names of new functions.
+pub fn completions(db: &dyn Db, position: FilePosition) -> Vec<CompletionItem> {
+    let parse = star_db::parse(db.as_source_db(), position.file);
+    let text = position.file.text(db.as_source_db());
+    let context = match CompletionContext::new(&parse.syntax(), text, position.offset) {
+        Some(context) => context,
+        None => return Vec::new(),
+    };
+
+    let mut items = Vec::new();
+    if let Location::Attribute { receiver } = &context.location {
+        let ty = star_hir::infer_expr(db, position.file, receiver);
+        for member in ty.members() {
+            items.push(CompletionItem {
+                label: member.name.to_string(),
+                kind: match member.params {
+                    Some(_) => CompletionKind::Method,
+                    None => CompletionKind::Field,
+                },
+                detail: Some(member.signature()),
+            });
+        }
+        return items;
+    }
+    if let Location::AfterExpression = context.location {
+        for keyword in ["and", "else", "for", "if", "in", "not", "or"] {
+            items.push(keyword_item(keyword));
+        }
+        return items;
+    }
+
+    let scopes = star_hir::file_scopes(db, position.file);
+    let scope = context.scope(scopes);
+    let mut seen = HashSet::new();
+    let mut current = Some(scope);
+    while let Some(id) = current {
+        let scope = scopes.scope(id);
+        let mut names: Vec<_> = scope.names().collect();
+        names.sort_by_key(|(name, _)| *name);
+        for (name, bindings) in names {
+            if !seen.insert(name) {
+                continue;
+            }
+            let binding = scopes.binding(bindings[0]);
+            let (kind, detail) = match &binding.kind {
+                BindingKind::Def => (
+                    CompletionKind::Function,
+                    def_signature(&parse.syntax(), binding.range),
+                ),
+                BindingKind::Parameter => (CompletionKind::Parameter, None),
+                BindingKind::Assign | BindingKind::LoopVariable => (CompletionKind::Variable, None),
+                BindingKind::Load { module, name } => (
+                    CompletionKind::Variable,
+                    Some(format!("load({:?}, {:?})", module, name)),
+                ),
+            };
+            items.push(CompletionItem {
+                label: name.to_string(),
+                kind,
+                detail,
+            });
+        }
+        current = scope.parent;
+    }
+
+    let bazel = star_db::is_bazel_file(position.file.path(db.as_source_db()));
+    let bazel_builtins = if bazel { BAZEL_BUILTINS } else { &[] };
+    for builtin in BUILTINS.iter().chain(bazel_builtins) {
+        if seen.insert(builtin.name) {
+            items.push(CompletionItem {
+                label: builtin.name.to_string(),
+                kind: match builtin.params {
+                    Some(_) => CompletionKind::Function,
+                    None => CompletionKind::Constant,
+                },
+                detail: Some(builtin.signature()),
+            });
+        }
+    }
+
+    let keywords = match context.location {
+        Location::Statement => context.statement_keywords(scopes.scope(scope).kind),
+        _ => vec!["lambda", "not"],
+    };
+    items.extend(keywords.into_iter().map(keyword_item));
+    items
+}
+
+fn keyword_item(keyword: &str) -> CompletionItem {
+    CompletionItem {
+        label: keyword.to_string(),
+        kind: CompletionKind::Keyword,
+        detail: None,
+    }
+}
+
+struct CompletionContext<'a> {
+    text: &'a str,
+    location: Location,
+    /// The last token before the name being completed, skipping whitespace, comments
+    /// and the empty tokens that mark indentation.
+    prev: Option<SyntaxToken>,
+    /// The column at which the name being completed starts.
+    column: usize,
+}
+
+impl<'a> CompletionContext<'a> {
+    fn new(root: &SyntaxNode, text: &'a str, offset: TextSize) -> Option<CompletionContext<'a>> {
+        let mut start = offset;
+        let mut before = None;
+        for token in root.token_at_offset(offset) {
+            let range = token.text_range();
+            if range.start() >= offset {
+                continue;
+            }
+            match token.kind() {
+                COMMENT => return None,
+                STRING
+                    if range.end() > offset
+                        || ast::String::cast(token.clone())?.value().is_none() =>
+                {
+                    return None
+                }
+                _ if is_name(token.text()) => {
+                    start = range.start();
+                    before = token.prev_token();
+                }
+                _ => before = Some(token),
+            }
+        }
+
+        let prev = skip_trivia(before);
+        // Only strings can be loaded.
+        let in_load = prev
+            .iter()
+            .filter_map(SyntaxToken::parent)
+            .flat_map(|node| node.ancestors())
+            .any(|node| node.kind() == LOAD_STMT);
+        if in_load {
+            return None;
+        }
+        let location = match prev.as_ref().map(|token| (token.kind(), token.parent())) {
+            None | Some((NEWLINE | SEMICOLON, _)) => Location::Statement,
+            Some((COLON, Some(parent)))
+                if matches!(parent.kind(), IF_STMT | FOR_STMT | WHILE_STMT | DEF_STMT) =>
+            {
+                Location::Statement
+            }
+            Some((DOT, Some(parent))) => {
+                let receiver = ast::DotExpr::cast(parent)?.expr()?;
+                Location::Attribute { receiver }
+            }
+            Some((DOT | DEF_KW, _)) => return None,
+            Some((IDENT | INT | FLOAT | STRING | R_PAREN | R_BRACK | R_BRACE, _)) => {
+                Location::AfterExpression
+            }
+            Some(_) => Location::Expression,
+        };
+
+        Some(CompletionContext {
+            text,
+            location,
+            prev,
+            column: column(text, start),
+        })
+    }
+
+    /// The nodes enclosing the name being completed. At the start of a statement, these
+    /// are the blocks that the preceding statement is in and that are indented less
+    /// than the name.
+    fn ancestors(&self) -> impl Iterator<Item = SyntaxNode> + '_ {
+        self.enclosing_nodes().filter(move |node| {
+            !matches!(self.location, Location::Statement)
+                || !matches!(node.kind(), DEF_STMT | IF_STMT | FOR_STMT | WHILE_STMT)
+                || column(self.text, node.text_range().start()) < self.column
+        })
+    }
+
+    /// The ancestors of the token before the name.
+    fn enclosing_nodes(&self) -> impl Iterator<Item = SyntaxNode> + '_ {
+        self.prev
+            .iter()
+            .filter_map(SyntaxToken::parent)
+            .flat_map(|node| node.ancestors())
+    }
+
+    /// The innermost scope enclosing the name being completed. Default values of
+    /// parameters are evaluated in the scope enclosing the function.
+    fn scope(&self, scopes: &FileScopes) -> ScopeId {
+        let mut in_parameters = false;
+        for node in self.ancestors() {
+            match node.kind() {
+                PARAMETERS => in_parameters = true,
+                DEF_STMT | LAMBDA_EXPR | LIST_COMP | DICT_COMP => {
+                    if mem::take(&mut in_parameters) {
+                        continue;
+                    }
+                    if let Some(scope) = scopes.scope_for_range(node.text_range()) {
+                        return scope;
+                    }
+                }
+                _ => {}
+            }
+        }
+        scopes.module_scope()
+    }
+
+    fn statement_keywords(&self, scope: ScopeKind) -> Vec<&'static str> {
+        let mut keywords = vec!["def", "for", "if", "pass"];
+        if scope == ScopeKind::Module && self.column == 0 {
+            keywords.push("load");
+        }
+        if scope == ScopeKind::Function {
+            keywords.push("return");
+        }
+        let in_loop = self
+            .ancestors()
+            .take_while(|node| node.kind() != DEF_STMT)
+            .any(|node| matches!(node.kind(), FOR_STMT | WHILE_STMT));
+        if in_loop {
+            keywords.extend(["break", "continue"]);
+        }
+        // An `if` statement at the same indentation that the name directly follows.
+        let preceding_if = self.enclosing_nodes().find(|node| {
+            node.kind() == IF_STMT && column(self.text, node.text_range().start()) == self.column
+        });
+        if let Some(stmt) = preceding_if {
+            if !stmt.children_with_tokens().any(|el| el.kind() == ELSE_KW) {
+                keywords.extend(["elif", "else"]);
+            }
+        }
+        keywords
+    }
+}
+
+fn column(text: &str, offset: TextSize) -> usize {
+    let offset = usize::from(offset);
+    offset - text[..offset].rfind('\n').map_or(0, |index| index + 1)
+}
+
+fn is_name(text: &str) -> bool {
+    let mut chars = text.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn skip_trivia(mut token: Option<SyntaxToken>) -> Option<SyntaxToken> {
+    while let Some(current) = &token {
+        if !current.kind().is_whitespace() && !current.text_range().is_empty() {
+            break;
+        }
+        token = current.prev_token();
+    }
+    token
+}
+
+/// The signature of the function whose name is at `range`, such as `f(a, b = 1)`.
+fn def_signature(root: &SyntaxNode, range: TextRange) -> Option<String> {
+    let name = root.token_at_offset(range.start()).right_biased()?;
+    let def = name.parent().filter(|node| node.kind() == DEF_STMT)?;
+    let parameters = def
+        .children()
+        .find(|node| node.kind() == PARAMETERS)
+        .map(|node| node.text().to_string())
+        .unwrap_or_default();
+    let parameters: Vec<&str> = parameters.split_whitespace().collect();
+    Some(format!("{}({})", name.text(), parameters.join(" ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    fn completion_items(fixture: &str) -> Vec<CompletionItem> {
+        let (fixture, position) = Fixture::new(fixture);
+        completions(&fixture.db.db, position)
+    }
+
+    /// Checks that the completions at `$0` include all of `included` and none of
+    /// `excluded`.
+    fn check(fixture: &str, included: &[&str], excluded: &[&str]) {
+        let labels: Vec<String> = completion_items(fixture)
+            .into_iter()
+            .map(|item| item.label)
+            .collect();
+        for label in included {
+            assert!(
+                labels.iter().any(|l| l == label),
+                "missing {}: {:?}",
+                label,
+                labels
+            );
+        }
+        for label in excluded {
+            assert!(
+                !labels.iter().any(|l| l == label),
+                "unexpected {}: {:?}",
+                label,
+                labels
+            );
+        }
+    }
+
+    #[test]
+    fn names_in_scope() {
+        check(
+            r#"
+//- /main.bzl
+load("//:lib.bzl", "loaded", alias = "original")
+GLOBAL = 1
+def helper():
+    pass
+def f(param):
+    local = [x for x in []]
+    return $0
+"#,
+            &[
+                "local", "param", "GLOBAL", "helper", "f", "loaded", "alias", "len", "True",
+            ],
+            &["original", "x", "return", "def"],
+        );
+        check(
+            r#"
+//- /main.bzl
+def f(param):
+    pass
+g = lambda y: $0
+"#,
+            &["y", "f", "g", "not", "lambda"],
+            &["param"],
+        );
+        check(
+            r#"
+//- /main.bzl
+z = [a for a in [] if $0]
+"#,
+            &["a", "z"],
+            &[],
+        );
+        check(
+            r#"
+//- /main.bzl
+default = 1
+def f(param = $0):
+    pass
+"#,
+            &["default", "f"],
+            &["param"],
+        );
+    }
+
+    #[test]
+    fn details() {
+        let items = completion_items(
+            r#"
+//- /main.bzl
+load("//:lib.bzl", "loaded")
+def f(a,
+      b = 1):
+    pass
+len = 1
+$0
+"#,
+        );
+        let item = |label: &str| items.iter().find(|item| item.label == label).unwrap();
+        assert_eq!(item("f").kind, CompletionKind::Function);
+        assert_eq!(item("f").detail.as_deref(), Some("f(a, b = 1)"));
+        assert_eq!(
+            item("loaded").detail.as_deref(),
+            Some(r#"load("//:lib.bzl", "loaded")"#)
+        );
+        assert_eq!(
+            item("range").detail.as_deref(),
+            Some("range(start_or_stop, stop=None, step=1)")
+        );
+        assert_eq!(item("None").kind, CompletionKind::Constant);
+        // Builtins are shadowed by globals.
+        assert_eq!(items.iter().filter(|item| item.label == "len").count(), 1);
+        assert_eq!(item("len").kind, CompletionKind::Variable);
+    }
+
+    #[test]
+    fn statement_keywords() {
+        check(
+            r#"
+//- /main.bzl
+x = 1
+$0
+"#,
+            &["def", "for", "if", "pass", "load", "x"],
+            &["return", "break", "elif", "lambda"],
+        );
+        check(
+            r#"
+//- /main.bzl
+def f():
+    for x in []:
+        pass
+        $0
+"#,
+            &["break", "continue", "return", "x"],
+            &["load"],
+        );
+        check(
+            r#"
+//- /main.bzl
+def f():
+    for x in []:
+        pass
+    ret$0
+"#,
+            &["return"],
+            &["break", "continue"],
+        );
+        check(
+            r#"
+//- /main.bzl
+def f(param):
+    pass
+$0
+"#,
+            &["load", "f"],
+            &["return", "param"],
+        );
+        check(
+            r#"
+//- /main.bzl
+if True:
+    pass
+el$0
+"#,
+            &["elif", "else"],
+            &[],
+        );
+        check(
+            r#"
+//- /main.bzl
+if True:
+    pass
+else:
+    pass
+$0
+"#,
+            &["if"],
+            &["elif", "else"],
+        );
+        check(
+            r#"
+//- /main.bzl
+if True: $0
+"#,
+            &["pass", "len"],
+            &["load"],
+        );
+    }
+
+    #[test]
+    fn incomplete_code() {
+        check(
+            r#"
+//- /main.bzl
+def f(param):
+    y = pa$0"#,
+            &["param", "y"],
+            &[],
+        );
+        check(
+            r#"
+//- /main.bzl
+def f(param):
+    print(1, $0
+"#,
+            &["param", "len"],
+            &[],
+        );
+        check(
+            r#"
+//- /main.bzl
+x = 1
+y = x $0
+"#,
+            &["and", "or", "if", "in"],
+            &["x", "len"],
+        );
+    }
+
+    #[test]
+    fn attributes() {
+        check(
+            "//- /main.bzl\n\"a\".$0\n",
+            &["upper", "split", "format"],
+            &["append", "len"],
+        );
+        check(
+            "//- /main.bzl\nx = []\nx.ap$0\n",
+            &["append", "extend"],
+            &["upper"],
+        );
+        check(
+            "//- /main.bzl\ndef f():\n    d = {}\n    return d.$0\n",
+            &["get", "keys", "items"],
+            &["append"],
+        );
+        check(
+            "//- /main.bzl\ndef _impl(ctx):\n    ctx.$0\n",
+            &["actions", "attr", "label"],
+            &[],
+        );
+        check(
+            "//- /main.bzl\ndef _impl(repository_ctx):\n    repository_ctx.$0\n",
+            &["execute", "download"],
+            &["actions"],
+        );
+        check("//- /BUILD\nnative.$0\n", &["glob", "cc_library"], &[]);
+        check("//- /main.star\nnative.$0\n", &[], &["glob"]);
+        check("//- /main.bzl\nx = 1\nx = \"a\"\nx.$0\n", &[], &["upper"]);
+
+        let items = completion_items("//- /main.bzl\n[].$0\n");
+        let append = items.iter().find(|item| item.label == "append").unwrap();
+        assert_eq!(append.kind, CompletionKind::Method);
+        assert_eq!(append.detail.as_deref(), Some("append(x)"));
+    }
+
+    #[test]
+    fn bazel_builtins() {
+        check("//- /main.bzl\n$0\n", &["native", "select", "len"], &[]);
+        check("//- /main.star\n$0\n", &["len"], &["native", "select"]);
+    }
+
+    #[test]
+    fn no_completions() {
+        for fixture in [
+            "x = 1\nx.$0\n",
+            "x = \"a$0b\"\n",
+            "x = \"a$0\n",
+            "# a $0\n",
+            "def $0",
+            "load(\"//:lib.bzl\", $0)\n",
+        ] {
+            let items = completion_items(&format!("//- /main.bzl\n{}", fixture));
+            assert_eq!(items, [], "{}", fixture);
+        }
+    }
+}
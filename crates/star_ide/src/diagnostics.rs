@@ -0,0 +1,178 @@
+use star_db::File;
+use star_hir::{Db, Function, TypeError, TypeErrorKind};
+use star_syntax::{
+    ast::{self, AstNode},
+    TextRange,
+};
+
+/// A semantic error in a file. Syntax errors are reported by the parser instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub range: TextRange,
+    /// Identifies the kind of error, so that clients can filter them.
+    pub code: &'static str,
+    pub message: String,
+}
+
+/// The type errors in `file`, in order.
+pub fn diagnostics(db: &dyn Db, file: File) -> Vec<Diagnostic> {
+    let parse = star_db::parse(db.as_source_db(), file);
+    let mut errors: Vec<&TypeError> = star_hir::infer_file(db, file).errors().iter().collect();
+    for def in parse.syntax().descendants().filter_map(ast::DefStmt::cast) {
+        let function = Function::from_def(db, file, &def);
+        errors.extend(star_hir::infer_function(db, function).errors());
+    }
+    errors.sort_by_key(|error| error.range.start());
+    errors.into_iter().map(diagnostic).collect()
+}
+
+fn diagnostic(error: &TypeError) -> Diagnostic {
+    let (code, message) = match &error.kind {
+        TypeErrorKind::UnsupportedOperands { op, lhs, rhs } => (
+            "unsupported-operands",
+            format!(
+                "unsupported operand types for `{}`: `{}` and `{}`",
+                op, lhs, rhs
+            ),
+        ),
+        TypeErrorKind::NoAttribute { ty, name } => (
+            "no-attribute",
+            format!("`{}` has no attribute `{}`", ty, name),
+        ),
+        TypeErrorKind::NotCallable(ty) => ("not-callable", format!("`{}` is not callable", ty)),
+        TypeErrorKind::NotIndexable(ty) => ("not-indexable", format!("`{}` cannot be indexed", ty)),
+        TypeErrorKind::NotIterable(ty) => ("not-iterable", format!("`{}` is not iterable", ty)),
+        TypeErrorKind::TooManyArguments { expected, found } => (
+            "too-many-arguments",
+            format!(
+                "expected at most {} positional argument{}, found {}",
+                expected,
+                if *expected == 1 { "" } else { "s" },
+                found
+            ),
+        ),
+        TypeErrorKind::MissingArgument(name) => (
+            "missing-argument",
+            format!("missing argument for parameter `{}`", name),
+        ),
+        TypeErrorKind::UnknownKeyword(name) => (
+            "unknown-keyword-argument",
+            format!("unexpected keyword argument `{}`", name),
+        ),
+        TypeErrorKind::DuplicateKeyword(name) => (
+            "duplicate-keyword-argument",
+            format!("keyword argument `{}` is repeated", name),
+        ),
+    };
+    Diagnostic {
+        range: error.range,
+        code,
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    /// Checks the code and the text of the range of each diagnostic in the file with the
+    /// cursor.
+    fn check(fixture: &str, expected: &[(&str, &str)]) {
+        let (fixture, position) = Fixture::new(fixture);
+        let db = &fixture.db.db;
+        let text = star_db::parse(db, position.file).syntax().to_string();
+        let actual: Vec<(&str, &str)> = diagnostics(db, position.file)
+            .iter()
+            .map(|diagnostic| (diagnostic.code, &text[diagnostic.range]))
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn type_errors() {
+        check(
+            r#"
+//- /main.bzl
+$0x = "a" + 1
+None.foo
+y = 1
+y()
+y[0]
+for z in 1:
+    pass
+[w for w in "abc"]
+"#,
+            &[
+                ("unsupported-operands", "\"a\" + 1"),
+                ("no-attribute", "foo"),
+                ("not-callable", "y"),
+                ("not-indexable", "y"),
+                ("not-iterable", "1"),
+                ("not-iterable", "\"abc\""),
+            ],
+        );
+    }
+
+    #[test]
+    fn valid_code() {
+        check(
+            r#"
+//- /main.bzl
+$0x = None
+if True:
+    x = "a"
+x.upper()
+"%s" % 1
+[1] * 2
+def f(ctx):
+    ctx.anything.goes
+    for y in ctx.files:
+        y[0]
+    z = unknown + 1
+    z.foo()
+"#,
+            &[],
+        );
+    }
+
+    #[test]
+    fn arguments() {
+        check(
+            r#"
+//- /main.bzl
+$0def f(a, b = 1, *, c = 2):
+    pass
+def g(*args, **kwargs):
+    pass
+f(1, 2, 3)
+f()
+f(1, d = 3)
+f(1, c = 1, c = 2)
+f(*[1, 2, 3])
+g(1, 2, x = 3)
+"#,
+            &[
+                ("too-many-arguments", "3"),
+                ("missing-argument", "f()"),
+                ("unknown-keyword-argument", "d"),
+                ("duplicate-keyword-argument", "c"),
+            ],
+        );
+    }
+
+    #[test]
+    fn loaded_function() {
+        check(
+            r#"
+//- /lib.bzl
+def f(a):
+    pass
+//- /main.bzl
+load(":lib.bzl", "f")
+$0f(1, 2)
+"#,
+            &[("too-many-arguments", "2")],
+        );
+    }
+}
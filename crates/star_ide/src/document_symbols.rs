@@ -0,0 +1,268 @@
+use star_db::{File, BUILD_FILES};
+use star_hir::{BindingKind, Db};
+use star_syntax::{
+    ast::{self, AstNode, AstToken},
+    SyntaxKind::*,
+    SyntaxNode, TextRange,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocumentSymbolKind {
+    Function,
+    Parameter,
+    Variable,
+    /// A `load` statement, with the names it loads as children.
+    Module,
+    /// A target declared in a BUILD file.
+    Target,
+}
+
+/// An entry in the outline of a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DocumentSymbol {
+    pub name: String,
+    /// The rule of a target, or the loaded name of a symbol loaded under another name.
+    pub detail: Option<String>,
+    pub kind: DocumentSymbolKind,
+    /// The range of the whole statement, parameter or call.
+    pub range: TextRange,
+    /// The range of the identifier or string naming the symbol.
+    pub selection_range: TextRange,
+    pub children: Vec<DocumentSymbol>,
+}
+
+/// The outline of `file`: its top-level functions with their parameters, variables and
+/// `load` statements with the names they load, and in BUILD files, its targets.
+pub fn document_symbols(db: &dyn Db, file: File) -> Vec<DocumentSymbol> {
+    let parse = star_db::parse(db.as_source_db(), file);
+    let scopes = star_hir::file_scopes(db, file);
+    let mut symbols: Vec<DocumentSymbol> = Vec::new();
+
+    for (_, binding) in scopes.bindings() {
+        if binding.scope != scopes.module_scope() {
+            continue;
+        }
+        let token = match parse
+            .syntax()
+            .token_at_offset(binding.range.start())
+            .right_biased()
+        {
+            Some(token) => token,
+            None => continue,
+        };
+
+        match &binding.kind {
+            BindingKind::Def => {
+                let def = match token.parent().and_then(ast::DefStmt::cast) {
+                    Some(def) => def,
+                    None => continue,
+                };
+                let children = def
+                    .parameters()
+                    .map(|parameters| parameters.parameters())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|parameter| {
+                        let name = parameter.name()?;
+                        Some(DocumentSymbol {
+                            name: name.syntax().text().to_string(),
+                            detail: None,
+                            kind: DocumentSymbolKind::Parameter,
+                            range: parameter.syntax().text_range(),
+                            selection_range: name.syntax().text_range(),
+                            children: Vec::new(),
+                        })
+                    })
+                    .collect();
+                symbols.push(DocumentSymbol {
+                    name: binding.name.clone(),
+                    detail: None,
+                    kind: DocumentSymbolKind::Function,
+                    range: def.syntax().text_range(),
+                    selection_range: binding.range,
+                    children,
+                });
+            }
+            BindingKind::Assign | BindingKind::LoopVariable => {
+                let stmt = match enclosing(token.parent(), &[ASSIGN_STMT, FOR_STMT]) {
+                    Some(stmt) => stmt,
+                    None => continue,
+                };
+                symbols.push(DocumentSymbol {
+                    name: binding.name.clone(),
+                    detail: None,
+                    kind: DocumentSymbolKind::Variable,
+                    range: stmt.text_range(),
+                    selection_range: binding.range,
+                    children: Vec::new(),
+                });
+            }
+            BindingKind::Load { name, .. } => {
+                let item = match enclosing(token.parent(), &[LOAD_SYMBOL, LOAD_ALIAS]) {
+                    Some(item) => item,
+                    None => continue,
+                };
+                let load = match item.parent().and_then(ast::LoadStmt::cast) {
+                    Some(load) => load,
+                    None => continue,
+                };
+                let symbol = DocumentSymbol {
+                    name: binding.name.clone(),
+                    detail: (*name != binding.name).then(|| name.clone()),
+                    kind: DocumentSymbolKind::Variable,
+                    range: item.text_range(),
+                    selection_range: binding.range,
+                    children: Vec::new(),
+                };
+
+                // Names are grouped under the `load` statement they are loaded by.
+                let range = load.syntax().text_range();
+                match symbols.iter_mut().find(|symbol| symbol.range == range) {
+                    Some(parent) => parent.children.push(symbol),
+                    None => {
+                        let module = match load.module() {
+                            Some(module) => module,
+                            None => continue,
+                        };
+                        symbols.push(DocumentSymbol {
+                            name: module.value().unwrap_or_default(),
+                            detail: None,
+                            kind: DocumentSymbolKind::Module,
+                            range,
+                            selection_range: module.syntax().text_range(),
+                            children: vec![symbol],
+                        });
+                    }
+                }
+            }
+            BindingKind::Parameter => {}
+        }
+    }
+
+    let path = file.path(db.as_source_db());
+    if BUILD_FILES.iter().any(|name| path.ends_with(name)) {
+        symbols.extend(
+            star_hir::file_targets(db, file)
+                .iter()
+                .map(|target| DocumentSymbol {
+                    name: target.name.clone(),
+                    detail: Some(target.rule.clone()),
+                    kind: DocumentSymbolKind::Target,
+                    range: target.range,
+                    selection_range: target.name_range,
+                    children: Vec::new(),
+                }),
+        );
+    }
+
+    symbols.sort_by_key(|symbol| symbol.selection_range.start());
+    for symbol in &mut symbols {
+        symbol
+            .children
+            .sort_by_key(|symbol| symbol.selection_range.start());
+    }
+    symbols
+}
+
+/// The innermost node of one of `kinds` among `node` and its ancestors.
+fn enclosing(node: Option<SyntaxNode>, kinds: &[star_syntax::SyntaxKind]) -> Option<SyntaxNode> {
+    node?.ancestors().find(|node| kinds.contains(&node.kind()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    /// Renders the outline of the file at `$0`, one symbol per line, with the text of its
+    /// range and of its selection range.
+    fn check(fixture: &str, expected: &str) {
+        let (fixture, position) = Fixture::new(fixture);
+        let db = &fixture.db.db;
+        let text = position.file.text(db);
+        let mut actual = String::new();
+        let mut stack: Vec<(usize, DocumentSymbol)> = document_symbols(db, position.file)
+            .into_iter()
+            .rev()
+            .map(|symbol| (0, symbol))
+            .collect();
+        while let Some((depth, symbol)) = stack.pop() {
+            let range = &text[std::ops::Range::<usize>::from(symbol.range)];
+            let selection = &text[std::ops::Range::<usize>::from(symbol.selection_range)];
+            actual.push_str(&format!(
+                "{}{:?} {}{} [{}] {:?}\n",
+                "  ".repeat(depth),
+                symbol.kind,
+                symbol.name,
+                symbol
+                    .detail
+                    .map(|detail| format!(" ({})", detail))
+                    .unwrap_or_default(),
+                selection,
+                range,
+            ));
+            stack.extend(
+                symbol
+                    .children
+                    .into_iter()
+                    .rev()
+                    .map(|child| (depth + 1, child)),
+            );
+        }
+        assert_eq!(actual, expected.trim_start());
+    }
+
+    #[test]
+    fn bzl_files() {
+        check(
+            r#"
+//- /main.bzl
+$0load("//:lib.bzl", "foo", bar = "baz")
+X = 1
+def f(a, *args, b = 2, **kwargs):
+    y = a
+    return y
+for i in []:
+    pass
+a, b = 1, 2
+"#,
+            r#"
+Module //:lib.bzl ["//:lib.bzl"] "load(\"//:lib.bzl\", \"foo\", bar = \"baz\")"
+  Variable foo ["foo"] "\"foo\""
+  Variable bar (baz) [bar] "bar = \"baz\""
+Variable X [X] "X = 1"
+Function f [f] "def f(a, *args, b = 2, **kwargs):\n    y = a\n    return y\n"
+  Parameter a [a] "a"
+  Parameter args [args] "*args"
+  Parameter b [b] "b = 2"
+  Parameter kwargs [kwargs] "**kwargs"
+Variable i [i] "for i in []:\n    pass\n"
+Variable a [a] "a, b = 1, 2"
+Variable b [b] "a, b = 1, 2"
+"#,
+        );
+    }
+
+    #[test]
+    fn build_files() {
+        check(
+            r#"
+//- /BUILD
+$0load("//:rules.bzl", "my_rule")
+SRCS = ["a.cc"]
+cc_library(
+    name = "lib",
+    srcs = SRCS,
+)
+my_rule(name = "gen")
+"#,
+            r#"
+Module //:rules.bzl ["//:rules.bzl"] "load(\"//:rules.bzl\", \"my_rule\")"
+  Variable my_rule ["my_rule"] "\"my_rule\""
+Variable SRCS [SRCS] "SRCS = [\"a.cc\"]"
+Target lib (cc_library) ["lib"] "cc_library(\n    name = \"lib\",\n    srcs = SRCS,\n)"
+Target gen (my_rule) ["gen"] "my_rule(name = \"gen\")"
+"#,
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use crate::{FilePosition, NavigationTarget};
+use star_db::File;
+use star_hir::{Binding, BindingId, BindingKind, Db, Resolution};
+use star_syntax::{
+    ast::{self, AstNode, AstToken},
+    SyntaxKind::*,
+    SyntaxToken, T,
+};
+use std::collections::HashSet;
+
+/// Finds the bindings of the name at `position`. A symbol loaded from another file
+/// resolves to its top-level bindings in that file, following any `load` statements
+/// that re-export it. If the loaded file can't be found, the `load` statement itself
+/// is the definition.
+pub fn goto_definition(db: &dyn Db, position: FilePosition) -> Vec<NavigationTarget> {
+    let token = match name_token(db, position) {
+        Some(token) => token,
+        None => return Vec::new(),
+    };
+
+    let scopes = star_hir::file_scopes(db, position.file);
+    let bindings = match scopes.resolve_token(&token) {
+        Some(Resolution::Bindings { bindings, .. }) => bindings,
+        _ => return Vec::new(),
+    };
+
+    let mut targets = Vec::new();
+    let mut visited = HashSet::new();
+    for binding in bindings.into_iter().map(|id| scopes.binding(id)) {
+        let found = match &binding.kind {
+            BindingKind::Load { module, name } => {
+                loaded_definitions(db, position.file, module, name, &mut visited, &mut targets)
+            }
+            _ => false,
+        };
+        if !found {
+            targets.push(NavigationTarget {
+                file: position.file,
+                range: binding.range,
+            });
+        }
+    }
+    targets
+}
+
+/// Returns the name token at `position`, preferring identifiers when the position is
+/// between two tokens.
+pub(crate) fn name_token(db: &dyn Db, position: FilePosition) -> Option<SyntaxToken> {
+    let parse = star_db::parse(db.as_source_db(), position.file);
+    let tokens = parse.syntax().token_at_offset(position.offset);
+    tokens
+        .clone()
+        .find(|token| token.kind() == IDENT)
+        .or_else(|| tokens.into_iter().find(|token| token.kind() == STRING))
+}
+
+/// Adds the top-level bindings of `name` in the file loaded by `load(module, ...)` in
+/// `file` to `targets`. Returns `false` if there are none.
+fn loaded_definitions(
+    db: &dyn Db,
+    file: File,
+    module: &str,
+    name: &str,
+    visited: &mut HashSet<(File, String)>,
+    targets: &mut Vec<NavigationTarget>,
+) -> bool {
+    let loaded = match star_db::resolve_load(db.as_source_db(), file, module) {
+        Some(loaded) => loaded,
+        None => return false,
+    };
+
+    // Guard against cycles of loads.
+    if !visited.insert((loaded, name.to_string())) {
+        return false;
+    }
+
+    let scopes = star_hir::file_scopes(db, loaded);
+    match scopes.resolve_name(scopes.module_scope(), name) {
+        Resolution::Bindings { bindings, .. } => {
+            exported_definitions(db, loaded, bindings, visited, targets);
+            true
+        }
+        Resolution::Unresolved => false,
+    }
+}
+
+/// Adds the definitions behind the top-level `bindings` of a loaded file to `targets`,
+/// following symbols that the file loads or assigns from a loaded symbol, as in
+/// `foo = _foo`.
+fn exported_definitions(
+    db: &dyn Db,
+    file: File,
+    bindings: Vec<BindingId>,
+    visited: &mut HashSet<(File, String)>,
+    targets: &mut Vec<NavigationTarget>,
+) {
+    let scopes = star_hir::file_scopes(db, file);
+    for binding in bindings.into_iter().map(|id| scopes.binding(id)) {
+        let found = match &binding.kind {
+            BindingKind::Load { module, name } => {
+                loaded_definitions(db, file, module, name, visited, targets)
+            }
+            BindingKind::Assign => match reexported(db, file, binding) {
+                Some(Resolution::Bindings { bindings, .. })
+                    if bindings
+                        .iter()
+                        .all(|id| matches!(scopes.binding(*id).kind, BindingKind::Load { .. })) =>
+                {
+                    exported_definitions(db, file, bindings, visited, targets);
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        };
+        if !found {
+            targets.push(NavigationTarget {
+                file,
+                range: binding.range,
+            });
+        }
+    }
+}
+
+/// If `binding` is the target of an assignment of one name to another, as in
+/// `foo = _foo`, resolves the name on the right.
+fn reexported(db: &dyn Db, file: File, binding: &Binding) -> Option<Resolution> {
+    let parse = star_db::parse(db.as_source_db(), file);
+    let lhs = parse
+        .syntax()
+        .token_at_offset(binding.range.start())
+        .right_biased()?
+        .parent()?;
+    let stmt = ast::AssignStmt::cast(lhs.parent()?)?;
+    let is_plain_assignment = stmt
+        .syntax()
+        .children_with_tokens()
+        .any(|el| el.kind() == T![=]);
+    if stmt.lhs()?.syntax() != &lhs || !is_plain_assignment {
+        return None;
+    }
+
+    let rhs = match stmt.rhs()? {
+        ast::Expr::Literal(literal) => match literal.kind() {
+            ast::LiteralKind::Ident(ident) => ident,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    star_hir::file_scopes(db, file).resolve_token(rhs.syntax())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    /// Checks the definitions of the name at `$0`, given as `path: text` pairs.
+    fn check(fixture: &str, expected: &[(&str, &str)]) {
+        let (fixture, position) = Fixture::new(fixture);
+        let db = &fixture.db.db;
+        let actual: Vec<(&str, String)> = goto_definition(db, position)
+            .into_iter()
+            .map(|target| {
+                let text = target.file.text(db);
+                let range = std::ops::Range::<usize>::from(target.range);
+                (
+                    target.file.path(db).to_str().unwrap(),
+                    text[range].to_string(),
+                )
+            })
+            .collect();
+        let expected: Vec<(&str, String)> = expected
+            .iter()
+            .map(|(path, text)| (*path, text.to_string()))
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn local_definitions() {
+        check(
+            r#"
+//- /main.bzl
+def foo():
+    pass
+
+foo$0()
+"#,
+            &[("/main.bzl", "foo")],
+        );
+        check(
+            r#"
+//- /main.bzl
+x = 1
+def f(x):
+    return $0x
+"#,
+            &[("/main.bzl", "x")],
+        );
+        check(
+            r#"
+//- /main.bzl
+print($0undefined)
+"#,
+            &[],
+        );
+    }
+
+    #[test]
+    fn loaded_symbols() {
+        let fixture = r#"
+//- /main.bzl
+load("//:lib.bzl", "foo", bar = "baz")
+foo$0()
+//- /lib.bzl
+def foo():
+    pass
+baz = 1
+"#;
+        check(fixture, &[("/lib.bzl", "foo")]);
+        check(&fixture.replace("foo$0()", "$0bar"), &[("/lib.bzl", "baz")]);
+        check(
+            &fixture
+                .replace("\"foo\"", "\"f$0oo\"")
+                .replace("foo$0()", "foo()"),
+            &[("/lib.bzl", "foo")],
+        );
+    }
+
+    #[test]
+    fn labels_relative_to_package() {
+        let fixture = r#"
+//- /pkg/BUILD
+//- /pkg/rules/main.bzl
+load(":rules/lib.bzl", "foo")
+foo$0()
+//- /pkg/rules/lib.bzl
+def foo():
+    pass
+"#;
+        check(fixture, &[("/pkg/rules/lib.bzl", "foo")]);
+        check(
+            &fixture.replace(":rules/lib.bzl", "//pkg:rules/lib.bzl"),
+            &[("/pkg/rules/lib.bzl", "foo")],
+        );
+        check(
+            &fixture.replace(":rules/lib.bzl", ":lib.bzl"),
+            &[("/pkg/rules/main.bzl", "\"foo\"")],
+        );
+    }
+
+    #[test]
+    fn reexports() {
+        check(
+            r#"
+//- /main.bzl
+load("//:reexport.bzl", "foo")
+$0foo()
+//- /reexport.bzl
+load("//:lib.bzl", _foo = "foo")
+foo = _foo
+//- /lib.bzl
+def foo():
+    pass
+"#,
+            &[("/lib.bzl", "foo")],
+        );
+        check(
+            r#"
+//- /main.bzl
+load("//:reexport.bzl", "foo")
+$0foo()
+//- /reexport.bzl
+load("//:lib.bzl", "foo")
+//- /lib.bzl
+def foo():
+    pass
+"#,
+            &[("/lib.bzl", "foo")],
+        );
+    }
+
+    #[test]
+    fn assignments_are_not_followed_locally() {
+        check(
+            r#"
+//- /main.bzl
+load("//:lib.bzl", _foo = "foo")
+foo = _foo
+$0foo()
+//- /lib.bzl
+def foo():
+    pass
+"#,
+            &[("/main.bzl", "foo")],
+        );
+        check(
+            r#"
+//- /main.bzl
+load("//:reexport.bzl", "foo")
+$0foo()
+//- /reexport.bzl
+def _foo():
+    pass
+foo = _foo
+"#,
+            &[("/reexport.bzl", "foo")],
+        );
+    }
+
+    #[test]
+    fn unresolved_loads() {
+        check(
+            r#"
+//- /main.bzl
+load("//:missing.bzl", "foo")
+$0foo()
+"#,
+            &[("/main.bzl", "\"foo\"")],
+        );
+        check(
+            r#"
+//- /a.bzl
+load("//:b.bzl", "foo")
+$0foo()
+//- /b.bzl
+load("//:a.bzl", "foo")
+"#,
+            &[("/a.bzl", "\"foo\"")],
+        );
+    }
+}
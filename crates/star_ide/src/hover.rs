@@ -0,0 +1,353 @@
+use crate::{goto_definition::name_token, signature_help::Signature, FilePosition};
+use star_db::File;
+use star_hir::{BindingKind, Builtin, Db, Resolution};
+use star_syntax::{
+    ast::{self, AstNode, AstToken},
+    SyntaxKind::*,
+    TextRange,
+};
+
+/// The documentation shown when hovering over a name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HoverResult {
+    /// The documentation, in Markdown.
+    pub markup: String,
+    /// The range of the name.
+    pub range: TextRange,
+}
+
+/// What a name refers to, as shown in a hover.
+struct Description {
+    /// A line of Starlark, such as the signature of a function or the name of a variable
+    /// with its type.
+    code: String,
+    /// The docstring of a function, or the documentation of a builtin.
+    doc: Option<String>,
+}
+
+/// Describes the name at `position`: the signature and docstring of a function, the
+/// inferred type of a variable or the documentation of a builtin. For a symbol loaded
+/// from another file, this describes its definition in that file and where it was
+/// loaded from.
+pub fn hover(db: &dyn Db, position: FilePosition) -> Option<HoverResult> {
+    let token = name_token(db, position)?;
+    let scopes = star_hir::file_scopes(db, position.file);
+    let mut loaded_from = None;
+    let description = match scopes.resolve_token(&token) {
+        Some(Resolution::Bindings { bindings, .. }) => {
+            let binding = scopes.binding(bindings[0]);
+            if let BindingKind::Load { module, .. } = &binding.kind {
+                loaded_from = Some(module.as_str());
+                let definition = crate::goto_definition(db, position)
+                    .into_iter()
+                    .find(|target| target.file != position.file)
+                    .and_then(|target| describe_definition(db, target.file, target.range));
+                match definition {
+                    Some(definition) => definition,
+                    None => describe_definition(db, position.file, binding.range)?,
+                }
+            } else {
+                describe_definition(db, position.file, binding.range)?
+            }
+        }
+        Some(Resolution::Unresolved) if token.kind() == IDENT => {
+            let bazel = star_db::is_bazel_file(position.file.path(db.as_source_db()));
+            describe_builtin(star_hir::builtin(token.text(), bazel)?)
+        }
+        Some(Resolution::Unresolved) => return None,
+        // Attribute names aren't resolved in scopes, but by the type of the receiver.
+        None => {
+            let dot = token.parent().and_then(ast::DotExpr::cast)?;
+            if dot.ident()?.syntax() != &token {
+                return None;
+            }
+            let receiver = star_hir::infer_expr(db, position.file, &dot.expr()?);
+            let member = receiver
+                .members()
+                .iter()
+                .find(|member| member.name == token.text())?;
+            describe_builtin(member)
+        }
+    };
+
+    let mut markup = format!("```python\n{}\n```", description.code);
+    if let Some(module) = loaded_from {
+        markup.push_str(&format!("\n\nLoaded from `{}`.", module));
+    }
+    if let Some(doc) = description.doc {
+        markup.push_str("\n\n");
+        markup.push_str(&doc);
+    }
+    Some(HoverResult {
+        markup,
+        range: token.text_range(),
+    })
+}
+
+/// Describes the function or variable whose name is bound at `range` in `file`.
+fn describe_definition(db: &dyn Db, file: File, range: TextRange) -> Option<Description> {
+    let parse = star_db::parse(db.as_source_db(), file);
+    let token = parse
+        .syntax()
+        .token_at_offset(range.start())
+        .right_biased()?;
+    if let Some(def) = token.parent().and_then(ast::DefStmt::cast) {
+        let signature = Signature::from_def(&def);
+        return Some(Description {
+            code: format!("def {}", signature.label().0),
+            doc: signature.doc,
+        });
+    }
+
+    let (scope, name) = match star_hir::resolve(db, file, &token)? {
+        Resolution::Bindings { scope, bindings } => {
+            let scopes = star_hir::file_scopes(db, file);
+            (scope, scopes.binding(bindings[0]).name.clone())
+        }
+        Resolution::Unresolved => return None,
+    };
+    let ty = star_hir::infer_name(db, file, scope, &name);
+    Some(Description {
+        code: format!("{}: {}", name, ty),
+        doc: None,
+    })
+}
+
+fn describe_builtin(builtin: &Builtin) -> Description {
+    Description {
+        code: match builtin.params {
+            Some(_) => format!("def {}", builtin.signature()),
+            None => builtin.name.to_string(),
+        },
+        doc: Some(builtin.doc.to_string()),
+    }
+}
+
+/// The docstring of a function: a string literal that is the first statement of its
+/// body, with the indentation of its lines removed.
+pub(crate) fn docstring(def: &ast::DefStmt) -> Option<String> {
+    let stmt = match def.suite()?.statements().into_iter().next()? {
+        ast::Stmt::SimpleStmt(stmt) => stmt.statements().into_iter().next()?,
+        _ => return None,
+    };
+    let value = match stmt {
+        ast::SmallStmt::ExprStmt(ast::Expr::Literal(literal)) => match literal.kind() {
+            ast::LiteralKind::String(string) => string.value()?,
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    // The first line starts right after the quotes, while the others are indented
+    // like the body of the function.
+    let mut lines = value.lines();
+    let first = lines.next().unwrap_or_default().trim();
+    let rest: Vec<&str> = lines.collect();
+    let indent = rest
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let mut doc = first.to_string();
+    for line in rest {
+        doc.push('\n');
+        doc.push_str(line.get(indent..).unwrap_or_default().trim_end());
+    }
+    let doc = doc.trim();
+    if doc.is_empty() {
+        None
+    } else {
+        Some(doc.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    /// Checks the markup of the hover at `$0`, or that there is none.
+    fn check(fixture: &str, expected: Option<&str>) {
+        let (fixture, position) = Fixture::new(fixture);
+        let actual = hover(&fixture.db.db, position).map(|result| result.markup);
+        assert_eq!(actual.as_deref(), expected);
+    }
+
+    #[test]
+    fn functions() {
+        check(
+            r#"
+//- /main.bzl
+def foo(a, b = [], *args, c: int = 1, **kwargs) -> str:
+    """Does foo.
+
+    Longer description,
+      indented.
+    """
+    pass
+
+$0foo()
+"#,
+            Some(
+                r#"```python
+def foo(a, b=[], *args, c: int = 1, **kwargs) -> str
+```
+
+Does foo.
+
+Longer description,
+  indented."#,
+            ),
+        );
+        check(
+            r#"
+//- /main.bzl
+def $0foo():
+    pass
+"#,
+            Some(
+                r#"```python
+def foo()
+```"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn variables() {
+        check(
+            r#"
+//- /main.bzl
+x = [1, 2]
+def f(y, z: str):
+    return $0x
+"#,
+            Some(
+                r#"```python
+x: list[int]
+```"#,
+            ),
+        );
+        check(
+            r#"
+//- /main.bzl
+def f(y, z: str):
+    return y + $0z
+"#,
+            Some(
+                r#"```python
+z: str
+```"#,
+            ),
+        );
+        check(
+            r#"
+//- /main.bzl
+print([$0x for x in ["a"]])
+"#,
+            Some(
+                r#"```python
+x: str
+```"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn loaded_symbols() {
+        let fixture = r#"
+//- /main.bzl
+load("//:lib.bzl", "foo", bar = "baz", "missing")
+$0foo()
+//- /lib.bzl
+def foo(x):
+    """Does foo."""
+    pass
+baz = {"a": 1}
+"#;
+        check(
+            fixture,
+            Some(
+                r#"```python
+def foo(x)
+```
+
+Loaded from `//:lib.bzl`.
+
+Does foo."#,
+            ),
+        );
+        check(
+            &fixture.replace("$0foo()", "$0bar"),
+            Some(
+                r#"```python
+baz: dict[str, int]
+```
+
+Loaded from `//:lib.bzl`."#,
+            ),
+        );
+        check(
+            &fixture.replace("$0foo()", "$0missing"),
+            Some(
+                r#"```python
+missing: unknown
+```
+
+Loaded from `//:lib.bzl`."#,
+            ),
+        );
+    }
+
+    #[test]
+    fn builtins() {
+        check(
+            r#"
+//- /main.bzl
+$0len([])
+"#,
+            Some(
+                r#"```python
+def len(x)
+```
+
+Returns the number of elements of a string or a collection."#,
+            ),
+        );
+        check(
+            r#"
+//- /main.bzl
+x = $0None
+"#,
+            Some(
+                r#"```python
+None
+```
+
+The value used to indicate the absence of any other value."#,
+            ),
+        );
+        check(
+            r#"
+//- /main.bzl
+x = []
+x.$0append(1)
+"#,
+            Some(
+                r#"```python
+def append(x)
+```
+
+Adds an element to the end of the list."#,
+            ),
+        );
+        check(
+            r#"
+//- /main.bzl
+$0undefined
+"#,
+            None,
+        );
+    }
+}
@@ -1,14 +1,106 @@
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
+mod completion;
+mod diagnostics;
+mod document_symbols;
+mod goto_definition;
+mod hover;
+mod references;
+mod rename;
+mod signature_help;
+mod workspace_symbols;
+
+pub use crate::{
+    completion::{completions, CompletionItem, CompletionKind},
+    diagnostics::{diagnostics, Diagnostic},
+    document_symbols::{document_symbols, DocumentSymbol, DocumentSymbolKind},
+    goto_definition::goto_definition,
+    hover::{hover, HoverResult},
+    references::{find_references, highlight_references, Reference, ReferenceKind},
+    rename::{prepare_rename, rename, RenameError, SourceEdit},
+    signature_help::{signature_help, SignatureHelp},
+    workspace_symbols::{workspace_symbols, WorkspaceSymbol},
+};
+
+use star_db::File;
+use star_syntax::{TextRange, TextSize};
+
+/// A position in a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FilePosition {
+    pub file: File,
+    pub offset: TextSize,
+}
+
+/// A range in a file that the editor can navigate to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NavigationTarget {
+    pub file: File,
+    pub range: TextRange,
 }
 
 #[cfg(test)]
-mod tests {
-    use super::*;
+mod fixture {
+    use crate::FilePosition;
+    use star_db::{SourceDatabase, BUILD_FILES};
+    use star_hir::RootDatabase;
+    use star_syntax::TextSize;
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        path::{Path, PathBuf},
+    };
+
+    /// A set of files, each starting with a `//- /path` line, in a workspace rooted at
+    /// `/`. The root and directories with a BUILD file are packages. All files are open
+    /// in the editor, and part of the workspace.
+    pub(crate) struct Fixture {
+        pub(crate) db: SourceDatabase<RootDatabase>,
+    }
+
+    impl Fixture {
+        /// Builds the fixture, returning it along with the position marked by `$0`.
+        pub(crate) fn new(fixture: &str) -> (Fixture, FilePosition) {
+            let mut texts: Vec<(&str, String)> = Vec::new();
+            for line in fixture.trim_start().lines() {
+                match line.strip_prefix("//- ") {
+                    Some(path) => texts.push((path.trim(), String::new())),
+                    None => {
+                        let (_, text) = texts.last_mut().expect("fixture must start with a path");
+                        text.push_str(line);
+                        text.push('\n');
+                    }
+                }
+            }
+
+            let mut db = SourceDatabase::<RootDatabase>::default();
+            let mut packages = BTreeSet::from([PathBuf::from("/")]);
+            for (path, _) in &texts {
+                let path = PathBuf::from(path);
+                if BUILD_FILES.iter().any(|name| path.ends_with(name)) {
+                    packages.insert(path.parent().unwrap().to_path_buf());
+                }
+            }
+            db.set_workspace(PathBuf::from("/"), BTreeMap::new());
+            db.set_packages(packages);
+
+            let mut position = None;
+            let paths: Vec<PathBuf> = texts.iter().map(|(path, _)| PathBuf::from(path)).collect();
+            for (path, text) in texts {
+                let cursor = text.find("$0");
+                db.set_overlay(Path::new(path), text.replacen("$0", "", 1));
+                if let Some(cursor) = cursor {
+                    position = Some(FilePosition {
+                        file: db.file(Path::new(path)).unwrap(),
+                        offset: TextSize::try_from(cursor).unwrap(),
+                    });
+                }
+            }
+            db.add_files(
+                paths
+                    .into_iter()
+                    .map(|path| (path, String::new()))
+                    .collect(),
+            );
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+            (Fixture { db }, position.expect("no cursor in fixture"))
+        }
     }
 }
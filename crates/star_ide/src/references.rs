@@ -0,0 +1,471 @@
+use crate::{goto_definition::name_token, FilePosition};
+use star_db::File;
+use star_hir::{BindingKind, Db, Resolution, ScopeId};
+use star_syntax::{
+    ast::{self, AstNode, AstToken},
+    SyntaxKind::*,
+    TextRange,
+};
+use std::collections::HashSet;
+
+/// How a reference uses a name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReferenceKind {
+    /// The name is bound, as by a `def` statement, an assignment or a `load` statement.
+    Write,
+    /// The value of the name is read, or a `load` statement names the symbol under
+    /// another name.
+    Read,
+}
+
+/// A use of a name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Reference {
+    pub file: File,
+    /// The range of the identifier, or of the string naming a symbol in a `load`
+    /// statement.
+    pub range: TextRange,
+    pub kind: ReferenceKind,
+}
+
+/// Finds the references to the name at `position`, including its bindings. Top-level
+/// names are also referenced by the files that load them: by the strings naming them in
+/// `load` statements, and by the uses of the names they are loaded under. A loaded
+/// symbol is looked up in the file that defines it, so that its references in every
+/// other file loading it are found too.
+///
+/// The declaration of the name, its first binding in the file it originates in, is left
+/// out unless `include_declaration` is set.
+pub fn find_references(
+    db: &dyn Db,
+    position: FilePosition,
+    include_declaration: bool,
+) -> Vec<Reference> {
+    let (scope, name) = match resolve_position(db, position) {
+        Some(resolved) => resolved,
+        None => return Vec::new(),
+    };
+
+    let scopes = star_hir::file_scopes(db, position.file);
+    if scope != scopes.module_scope() {
+        let mut references = local_references(db, position.file, scope, &name);
+        if !include_declaration {
+            remove_declaration(db, position.file, scope, &name, &mut references);
+        }
+        return references;
+    }
+
+    let (file, name) = origin(db, position.file, name, true, &mut HashSet::new());
+    let mut references = Vec::new();
+    global_references(db, file, &name, true, &mut HashSet::new(), &mut references);
+    if !include_declaration {
+        let scope = star_hir::file_scopes(db, file).module_scope();
+        remove_declaration(db, file, scope, &name, &mut references);
+    }
+    let source_db = db.as_source_db();
+    references.sort_by(|a, b| {
+        (a.file.path(source_db), a.range.start()).cmp(&(b.file.path(source_db), b.range.start()))
+    });
+    references.dedup();
+    references
+}
+
+/// Removes the first binding of `name` in `scope` of `file` from `references`.
+fn remove_declaration(
+    db: &dyn Db,
+    file: File,
+    scope: ScopeId,
+    name: &str,
+    references: &mut Vec<Reference>,
+) {
+    let scopes = star_hir::file_scopes(db, file);
+    let declaration = match scopes.resolve_name(scope, name) {
+        Resolution::Bindings {
+            scope: resolved,
+            bindings,
+        } if resolved == scope => scopes.binding(bindings[0]).range,
+        _ => return,
+    };
+    references.retain(|reference| reference.file != file || reference.range != declaration);
+}
+
+/// Finds the references to the name at `position` in its own file, to highlight them.
+pub fn highlight_references(db: &dyn Db, position: FilePosition) -> Vec<Reference> {
+    match resolve_position(db, position) {
+        Some((scope, name)) => local_references(db, position.file, scope, &name),
+        None => Vec::new(),
+    }
+}
+
+/// Resolves the name at `position` to the scope it is bound in and its name.
+pub(crate) fn resolve_position(db: &dyn Db, position: FilePosition) -> Option<(ScopeId, String)> {
+    let token = name_token(db, position)?;
+    let scopes = star_hir::file_scopes(db, position.file);
+    match scopes.resolve_token(&token)? {
+        Resolution::Bindings { scope, bindings } => {
+            Some((scope, scopes.binding(bindings[0]).name.clone()))
+        }
+        Resolution::Unresolved => None,
+    }
+}
+
+/// Follows the `load` statements binding the top-level `name` in `file` to the file
+/// that defines it, returning that file and the name it has there. Symbols that can't
+/// be followed, because they aren't all loaded from the same file or it can't be found,
+/// originate in the file binding them. So do symbols loaded under another name, unless
+/// `follow_aliases` is set.
+pub(crate) fn origin(
+    db: &dyn Db,
+    file: File,
+    name: String,
+    follow_aliases: bool,
+    visited: &mut HashSet<(File, String)>,
+) -> (File, String) {
+    // Guard against cycles of loads.
+    if !visited.insert((file, name.clone())) {
+        return (file, name);
+    }
+
+    let scopes = star_hir::file_scopes(db, file);
+    let bindings = match scopes.resolve_name(scopes.module_scope(), &name) {
+        Resolution::Bindings { bindings, .. } => bindings,
+        Resolution::Unresolved => return (file, name),
+    };
+    let mut loaded = bindings.iter().map(|id| match &scopes.binding(*id).kind {
+        BindingKind::Load {
+            module,
+            name: loaded_name,
+        } if follow_aliases || *loaded_name == name => {
+            star_db::resolve_load(db.as_source_db(), file, module).map(|file| (file, loaded_name))
+        }
+        _ => None,
+    });
+    let first = loaded.next().flatten();
+    match first {
+        Some((loaded_file, loaded_name)) if loaded.all(|other| other == first) => origin(
+            db,
+            loaded_file,
+            loaded_name.clone(),
+            follow_aliases,
+            visited,
+        ),
+        _ => (file, name),
+    }
+}
+
+/// Adds the references to the top-level `name` of `file` to `references`, along with
+/// its references in the files that load it, directly or through other loads. The
+/// references to the names it is loaded under are only added if `follow_aliases` is set.
+pub(crate) fn global_references(
+    db: &dyn Db,
+    file: File,
+    name: &str,
+    follow_aliases: bool,
+    visited: &mut HashSet<(File, String)>,
+    references: &mut Vec<Reference>,
+) {
+    if !visited.insert((file, name.to_string())) {
+        return;
+    }
+
+    let scopes = star_hir::file_scopes(db, file);
+    references.extend(local_references(db, file, scopes.module_scope(), name));
+
+    let source_db = db.as_source_db();
+    let workspace = source_db.inputs().workspace();
+    for &loader in star_hir::workspace_index(db, workspace).loaded_by(file) {
+        let parse = star_db::parse(source_db, loader);
+        for load in parse.syntax().descendants().filter_map(ast::LoadStmt::cast) {
+            let loads_file = load
+                .module()
+                .and_then(|module| module.value())
+                .and_then(|module| star_db::resolve_load(source_db, loader, &module));
+            if loads_file != Some(file) {
+                continue;
+            }
+
+            // The symbol string is a binding in the loader, found with its local
+            // references.
+            let loads_name = load
+                .symbols()
+                .iter()
+                .filter_map(|symbol| symbol.name()?.value())
+                .any(|symbol| symbol == name);
+            if loads_name {
+                global_references(db, loader, name, follow_aliases, visited, references);
+            }
+
+            for alias in load.aliases() {
+                let (ident, string) = match (alias.alias(), alias.name()) {
+                    (Some(ident), Some(string)) => (ident, string),
+                    _ => continue,
+                };
+                if string.value().as_deref() != Some(name) {
+                    continue;
+                }
+                references.push(Reference {
+                    file: loader,
+                    range: string.syntax().text_range(),
+                    kind: ReferenceKind::Read,
+                });
+                if follow_aliases {
+                    let alias = ident.syntax().text();
+                    global_references(db, loader, alias, follow_aliases, visited, references);
+                }
+            }
+        }
+    }
+}
+
+/// Finds the name tokens of `file` that resolve to `name` in `scope`.
+pub(crate) fn local_references(
+    db: &dyn Db,
+    file: File,
+    scope: ScopeId,
+    name: &str,
+) -> Vec<Reference> {
+    let parse = star_db::parse(db.as_source_db(), file);
+    let scopes = star_hir::file_scopes(db, file);
+    parse
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| matches!(token.kind(), IDENT | STRING))
+        .filter_map(|token| {
+            let bindings = match scopes.resolve_token(&token)? {
+                Resolution::Bindings {
+                    scope: resolved,
+                    bindings,
+                } if resolved == scope => bindings,
+                _ => return None,
+            };
+            let range = token.text_range();
+            let mut bindings = bindings.into_iter().map(|id| scopes.binding(id));
+            if bindings.clone().next()?.name != name {
+                return None;
+            }
+            let kind = if bindings.any(|binding| binding.range == range) {
+                ReferenceKind::Write
+            } else {
+                ReferenceKind::Read
+            };
+            Some(Reference { file, range, kind })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    /// Checks the references to the name at `$0`, given as `path: text` pairs with
+    /// bindings marked by a leading `=`.
+    fn check(fixture: &str, expected: &[(&str, &str)]) {
+        check_references(fixture, true, expected);
+    }
+
+    fn check_references(fixture: &str, include_declaration: bool, expected: &[(&str, &str)]) {
+        let (fixture, position) = Fixture::new(fixture);
+        let db = &fixture.db.db;
+        let actual: Vec<(&str, String)> = find_references(db, position, include_declaration)
+            .into_iter()
+            .map(|reference| {
+                let text = reference.file.text(db);
+                let range = std::ops::Range::<usize>::from(reference.range);
+                let marker = match reference.kind {
+                    ReferenceKind::Write => "=",
+                    ReferenceKind::Read => "",
+                };
+                (
+                    reference.file.path(db).to_str().unwrap(),
+                    format!("{}{}", marker, &text[range]),
+                )
+            })
+            .collect();
+        let expected: Vec<(&str, String)> = expected
+            .iter()
+            .map(|(path, text)| (*path, text.to_string()))
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn locals() {
+        check(
+            r#"
+//- /main.bzl
+x = 1
+def f(x):
+    x = x + 1
+    return [$0x for x in []] + [x]
+"#,
+            &[("/main.bzl", "x"), ("/main.bzl", "=x")],
+        );
+        check(
+            r#"
+//- /main.bzl
+x = 1
+def f($0x):
+    x = x + 1
+    return [x for x in []] + [x]
+"#,
+            &[
+                ("/main.bzl", "=x"),
+                ("/main.bzl", "=x"),
+                ("/main.bzl", "x"),
+                ("/main.bzl", "x"),
+            ],
+        );
+        check(
+            r#"
+//- /main.bzl
+print($0undefined)
+"#,
+            &[],
+        );
+    }
+
+    #[test]
+    fn globals() {
+        check(
+            r#"
+//- /main.bzl
+def foo():
+    return foo
+x = foo()
+$0foo = 1
+"#,
+            &[
+                ("/main.bzl", "=foo"),
+                ("/main.bzl", "foo"),
+                ("/main.bzl", "foo"),
+                ("/main.bzl", "=foo"),
+            ],
+        );
+    }
+
+    #[test]
+    fn loaded_symbols() {
+        let fixture = r#"
+//- /lib.bzl
+def $0foo():
+    pass
+//- /main.bzl
+load("//:lib.bzl", "foo", bar = "foo")
+foo()
+bar()
+//- /other.bzl
+load(":lib.bzl", "foo")
+foo
+//- /unrelated.bzl
+load("//:other.bzl", foo = "bar")
+foo
+"#;
+        let expected = [
+            ("/lib.bzl", "=foo"),
+            ("/main.bzl", "=\"foo\""),
+            ("/main.bzl", "=bar"),
+            ("/main.bzl", "\"foo\""),
+            ("/main.bzl", "foo"),
+            ("/main.bzl", "bar"),
+            ("/other.bzl", "=\"foo\""),
+            ("/other.bzl", "foo"),
+        ];
+        check(fixture, &expected);
+        check(
+            &fixture
+                .replace("def $0foo", "def foo")
+                .replace("bar()", "$0bar()"),
+            &expected,
+        );
+        check(
+            &fixture.replace("def $0foo", "def foo").replace(
+                "load(\":lib.bzl\", \"foo\")",
+                "load(\":lib.bzl\", \"f$0oo\")",
+            ),
+            &expected,
+        );
+    }
+
+    #[test]
+    fn reexported_symbols() {
+        check(
+            r#"
+//- /main.bzl
+load("//:reexport.bzl", "foo")
+$0foo()
+//- /reexport.bzl
+load("//:lib.bzl", "foo")
+//- /lib.bzl
+foo = 1
+"#,
+            &[
+                ("/lib.bzl", "=foo"),
+                ("/main.bzl", "=\"foo\""),
+                ("/main.bzl", "foo"),
+                ("/reexport.bzl", "=\"foo\""),
+            ],
+        );
+        check(
+            r#"
+//- /main.bzl
+load("//:missing.bzl", "foo")
+$0foo()
+"#,
+            &[("/main.bzl", "=\"foo\""), ("/main.bzl", "foo")],
+        );
+    }
+
+    #[test]
+    fn without_declaration() {
+        check_references(
+            r#"
+//- /main.bzl
+def f(x):
+    x = x + 1
+    return $0x
+"#,
+            false,
+            &[("/main.bzl", "=x"), ("/main.bzl", "x"), ("/main.bzl", "x")],
+        );
+        check_references(
+            r#"
+//- /main.bzl
+load("//:lib.bzl", "foo")
+$0foo()
+//- /lib.bzl
+foo = 1
+foo += 1
+"#,
+            false,
+            &[
+                ("/lib.bzl", "=foo"),
+                ("/main.bzl", "=\"foo\""),
+                ("/main.bzl", "foo"),
+            ],
+        );
+    }
+
+    #[test]
+    fn highlights() {
+        let (fixture, position) = Fixture::new(
+            r#"
+//- /main.bzl
+load("//:lib.bzl", "foo")
+$0foo()
+//- /lib.bzl
+foo = 1
+"#,
+        );
+        let db = &fixture.db.db;
+        let kinds: Vec<ReferenceKind> = highlight_references(db, position)
+            .into_iter()
+            .map(|reference| {
+                assert_eq!(reference.file, position.file);
+                reference.kind
+            })
+            .collect();
+        assert_eq!(kinds, [ReferenceKind::Write, ReferenceKind::Read]);
+    }
+}
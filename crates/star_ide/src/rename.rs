@@ -0,0 +1,491 @@
+use crate::{
+    goto_definition::name_token,
+    references::{global_references, local_references, origin, resolve_position, Reference},
+    FilePosition,
+};
+use star_db::File;
+use star_hir::{Db, FileScopes, Resolution, ScopeId};
+use star_syntax::{
+    ast::{self, AstToken},
+    lexer::{Lexer, LexerReturn},
+    SyntaxKind::*,
+    SyntaxToken, TextRange, TextSize,
+};
+use std::{collections::HashSet, fmt, path::PathBuf};
+
+/// A replacement of the text at `range` in `file`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceEdit {
+    pub file: File,
+    pub range: TextRange,
+    pub new_text: String,
+}
+
+/// Why a name can't be renamed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RenameError {
+    /// There is no name at the position, or it isn't bound anywhere.
+    NoName,
+    Builtin(String),
+    /// The new name isn't an identifier.
+    InvalidName(String),
+    /// The new name is already bound where it would be used, or it would shadow a
+    /// binding in `path`.
+    Conflict {
+        name: String,
+        path: PathBuf,
+    },
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenameError::NoName => write!(f, "no symbol to rename"),
+            RenameError::Builtin(name) => write!(f, "cannot rename builtin `{}`", name),
+            RenameError::InvalidName(name) => write!(f, "`{}` is not a valid name", name),
+            RenameError::Conflict { name, path } => write!(
+                f,
+                "`{}` would conflict with an existing binding in {}",
+                name,
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RenameError {}
+
+/// Checks that the name at `position` can be renamed, returning its range. For the
+/// string naming a symbol in a `load` statement, this is the range inside the quotes.
+pub fn prepare_rename(db: &dyn Db, position: FilePosition) -> Result<TextRange, RenameError> {
+    let token = name_token(db, position).ok_or(RenameError::NoName)?;
+    match star_hir::resolve(db, position.file, &token) {
+        Some(Resolution::Bindings { .. }) => Ok(name_range(token.text(), token.text_range())),
+        Some(Resolution::Unresolved) if token.kind() == IDENT => {
+            let bazel = star_db::is_bazel_file(position.file.path(db.as_source_db()));
+            match star_hir::builtin(token.text(), bazel) {
+                Some(_) => Err(RenameError::Builtin(token.text().to_string())),
+                None => Err(RenameError::NoName),
+            }
+        }
+        _ => Err(RenameError::NoName),
+    }
+}
+
+/// Renames the name at `position` and all of its references to `new_name`. A top-level
+/// name is also renamed in the files that load it, including in the `load` statements.
+/// Where it is loaded under another name, only the string naming it is changed, and
+/// renaming such an alias leaves the loaded symbol alone.
+pub fn rename(
+    db: &dyn Db,
+    position: FilePosition,
+    new_name: &str,
+) -> Result<Vec<SourceEdit>, RenameError> {
+    prepare_rename(db, position)?;
+    if !is_identifier(new_name) {
+        return Err(RenameError::InvalidName(new_name.to_string()));
+    }
+    let (scope, name) = resolve_position(db, position).ok_or(RenameError::NoName)?;
+
+    let scopes = star_hir::file_scopes(db, position.file);
+    let references = if scope == scopes.module_scope() {
+        let (file, name) = origin(db, position.file, name, false, &mut HashSet::new());
+        let mut references = Vec::new();
+        global_references(db, file, &name, false, &mut HashSet::new(), &mut references);
+        references
+    } else {
+        local_references(db, position.file, scope, &name)
+    };
+
+    let mut files: Vec<File> = Vec::new();
+    for reference in &references {
+        if !files.contains(&reference.file) {
+            files.push(reference.file);
+        }
+    }
+    for file in files {
+        // References from other files are always to top-level names.
+        let scope = if file == position.file {
+            scope
+        } else {
+            star_hir::file_scopes(db, file).module_scope()
+        };
+        check_conflicts(db, file, scope, &references, new_name)?;
+    }
+
+    Ok(references
+        .into_iter()
+        .map(|reference| {
+            let text = reference.file.text(db.as_source_db());
+            let range = std::ops::Range::<usize>::from(reference.range);
+            SourceEdit {
+                file: reference.file,
+                range: name_range(&text[range], reference.range),
+                new_text: new_name.to_string(),
+            }
+        })
+        .collect())
+}
+
+/// Checks that renaming the `references` in `file` to a name bound in `scope` doesn't
+/// change what any name refers to: the renamed references must not resolve to an
+/// existing binding of the new name, and existing uses of the new name must not be
+/// captured by the renamed binding.
+fn check_conflicts(
+    db: &dyn Db,
+    file: File,
+    scope: ScopeId,
+    references: &[Reference],
+    new_name: &str,
+) -> Result<(), RenameError> {
+    let parse = star_db::parse(db.as_source_db(), file);
+    let scopes = star_hir::file_scopes(db, file);
+    let conflict = || RenameError::Conflict {
+        name: new_name.to_string(),
+        path: file.path(db.as_source_db()).clone(),
+    };
+
+    for reference in references.iter().filter(|reference| reference.file == file) {
+        let token = match parse
+            .syntax()
+            .token_at_offset(reference.range.start())
+            .right_biased()
+        {
+            Some(token) => token,
+            None => continue,
+        };
+        // The strings naming aliased symbols in `load` statements don't bind anything.
+        let site = match scopes.token_scope(&token) {
+            Some(site) => site,
+            None => continue,
+        };
+        if let Resolution::Bindings { .. } = scopes.resolve_name(site, new_name) {
+            return Err(conflict());
+        }
+    }
+
+    let tokens = parse
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token());
+    for token in tokens {
+        let site = match scopes.token_scope(&token) {
+            Some(site) if is_within(scopes, site, scope) => site,
+            _ => continue,
+        };
+        if token_name(&token).as_deref() != Some(new_name) {
+            continue;
+        }
+        match scopes.resolve_name(site, new_name) {
+            Resolution::Unresolved => return Err(conflict()),
+            Resolution::Bindings { scope: bound, .. }
+                if bound != scope && is_within(scopes, scope, bound) =>
+            {
+                return Err(conflict())
+            }
+            Resolution::Bindings { .. } => {}
+        }
+    }
+    Ok(())
+}
+
+/// Whether `scope` is `ancestor` or nested in it.
+fn is_within(scopes: &FileScopes, scope: ScopeId, ancestor: ScopeId) -> bool {
+    let mut current = Some(scope);
+    while let Some(scope) = current {
+        if scope == ancestor {
+            return true;
+        }
+        current = scopes.scope(scope).parent;
+    }
+    false
+}
+
+fn token_name(token: &SyntaxToken) -> Option<String> {
+    match token.kind() {
+        IDENT => Some(token.text().to_string()),
+        STRING => ast::String::cast(token.clone())?.value(),
+        _ => None,
+    }
+}
+
+/// The range of the name in a token with the given text at `range`, which excludes the
+/// prefix and quotes of a string.
+fn name_range(text: &str, range: TextRange) -> TextRange {
+    let start = match text.find(['"', '\'']) {
+        Some(start) => start,
+        None => return range,
+    };
+    let quotes = if text[start..].starts_with("\"\"\"") || text[start..].starts_with("'''") {
+        3
+    } else {
+        1
+    };
+    let prefix = TextSize::try_from(start + quotes).unwrap();
+    let suffix = TextSize::try_from(quotes).unwrap();
+    TextRange::new(range.start() + prefix, range.end() - suffix)
+}
+
+/// Whether `name` is a single identifier token, and not a keyword.
+fn is_identifier(name: &str) -> bool {
+    matches!(
+        Lexer::new(name).next(),
+        Some(LexerReturn(token, None)) if token.kind == IDENT && token.len == name.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+    use std::collections::BTreeMap;
+
+    /// Renames the name at `$0` to `new_name`, checking the resulting text of each
+    /// changed file.
+    fn check(fixture: &str, new_name: &str, expected: &[(&str, &str)]) {
+        let (fixture, position) = Fixture::new(fixture);
+        let db = &fixture.db.db;
+        let mut changed: BTreeMap<&str, String> = BTreeMap::new();
+        let mut edits = rename(db, position, new_name).unwrap();
+        edits.sort_by_key(|edit| std::cmp::Reverse(edit.range.start()));
+        for edit in edits {
+            let text = changed
+                .entry(edit.file.path(db).to_str().unwrap())
+                .or_insert_with(|| edit.file.text(db).clone());
+            text.replace_range(std::ops::Range::<usize>::from(edit.range), &edit.new_text);
+        }
+        let expected: BTreeMap<&str, String> = expected
+            .iter()
+            .map(|(path, text)| (*path, text.trim_start().to_string()))
+            .collect();
+        assert_eq!(changed, expected);
+    }
+
+    fn check_error(fixture: &str, new_name: &str, expected: &str) {
+        let (fixture, position) = Fixture::new(fixture);
+        let error = rename(&fixture.db.db, position, new_name).unwrap_err();
+        assert_eq!(error.to_string(), expected);
+    }
+
+    #[test]
+    fn locals() {
+        check(
+            r#"
+//- /main.bzl
+x = 1
+def f($0x):
+    x = x + 1
+    return [x for x in []] + [x]
+"#,
+            "y",
+            &[(
+                "/main.bzl",
+                r#"
+x = 1
+def f(y):
+    y = y + 1
+    return [x for x in []] + [y]
+"#,
+            )],
+        );
+    }
+
+    #[test]
+    fn loaded_symbols() {
+        let fixture = r#"
+//- /lib.bzl
+def $0foo():
+    pass
+//- /main.bzl
+load("//:lib.bzl", "foo", bar = "foo")
+foo()
+bar()
+//- /other.bzl
+load(":lib.bzl", "foo")
+foo
+"#;
+        let expected = [
+            (
+                "/lib.bzl",
+                r#"
+def baz():
+    pass
+"#,
+            ),
+            (
+                "/main.bzl",
+                r#"
+load("//:lib.bzl", "baz", bar = "baz")
+baz()
+bar()
+"#,
+            ),
+            (
+                "/other.bzl",
+                r#"
+load(":lib.bzl", "baz")
+baz
+"#,
+            ),
+        ];
+        check(fixture, "baz", &expected);
+        check(
+            &fixture.replace("def $0foo", "def foo").replace(
+                "load(\":lib.bzl\", \"foo\")",
+                "load(\":lib.bzl\", \"f$0oo\")",
+            ),
+            "baz",
+            &expected,
+        );
+    }
+
+    #[test]
+    fn aliases() {
+        check(
+            r#"
+//- /lib.bzl
+def foo():
+    pass
+//- /main.bzl
+load("//:lib.bzl", "foo", bar = "foo")
+foo()
+$0bar()
+"#,
+            "baz",
+            &[(
+                "/main.bzl",
+                r#"
+load("//:lib.bzl", "foo", baz = "foo")
+foo()
+baz()
+"#,
+            )],
+        );
+    }
+
+    #[test]
+    fn prepare() {
+        let (fixture, position) = Fixture::new(
+            r#"
+//- /main.bzl
+load("//:lib.bzl", "f$0oo")
+"#,
+        );
+        let range = prepare_rename(&fixture.db.db, position).unwrap();
+        assert_eq!(std::ops::Range::<usize>::from(range), 20..23);
+    }
+
+    #[test]
+    fn errors() {
+        check_error(
+            r#"
+//- /main.bzl
+$0len([])
+"#,
+            "size",
+            "cannot rename builtin `len`",
+        );
+        check_error(
+            r#"
+//- /main.bzl
+$0undefined
+"#,
+            "x",
+            "no symbol to rename",
+        );
+        check_error(
+            r#"
+//- /main.bzl
+$0x = 1
+"#,
+            "def",
+            "`def` is not a valid name",
+        );
+        check_error(
+            r#"
+//- /main.bzl
+$0x = 1
+"#,
+            "a b",
+            "`a b` is not a valid name",
+        );
+    }
+
+    #[test]
+    fn conflicts() {
+        let conflict = "`y` would conflict with an existing binding in /main.bzl";
+        // The new name is already bound in the same scope.
+        check_error(
+            r#"
+//- /main.bzl
+$0x = 1
+y = 2
+"#,
+            "y",
+            conflict,
+        );
+        // A use of the renamed name would resolve to an inner binding.
+        check_error(
+            r#"
+//- /main.bzl
+$0x = 1
+def f(y):
+    return x + y
+"#,
+            "y",
+            conflict,
+        );
+        // The renamed binding would shadow an outer binding, or a builtin.
+        check_error(
+            r#"
+//- /main.bzl
+y = 1
+def f($0x):
+    return x + y
+"#,
+            "y",
+            conflict,
+        );
+        check_error(
+            r#"
+//- /main.bzl
+def f($0x):
+    return len(x)
+"#,
+            "len",
+            "`len` would conflict with an existing binding in /main.bzl",
+        );
+        // The new name is already loaded by a file that loads the renamed symbol.
+        check_error(
+            r#"
+//- /lib.bzl
+$0x = 1
+//- /main.bzl
+load("//:lib.bzl", "x")
+load("//:other.bzl", "y")
+"#,
+            "y",
+            conflict,
+        );
+        // Other bindings of the new name in unrelated scopes are fine.
+        check(
+            r#"
+//- /main.bzl
+def f($0x):
+    return x
+def g(y):
+    return y
+"#,
+            "y",
+            &[(
+                "/main.bzl",
+                r#"
+def f(y):
+    return y
+def g(y):
+    return y
+"#,
+            )],
+        );
+    }
+}
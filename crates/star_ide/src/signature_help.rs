@@ -0,0 +1,505 @@
+use crate::{hover::docstring, FilePosition};
+use star_db::File;
+use star_hir::{Builtin, Db, Ty};
+use star_syntax::{
+    ast::{self, AstNode, AstToken},
+    SyntaxNode, TextRange, TextSize, T,
+};
+
+/// The signature of the function being called at a position, with the parameter that
+/// the argument there is passed to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureHelp {
+    /// The signature, such as `f(x, y=1) -> str`.
+    pub label: String,
+    /// The docstring of the function, or the documentation of a builtin or rule.
+    pub doc: Option<String>,
+    /// The ranges of the parameters in `label`.
+    pub parameters: Vec<TextRange>,
+    /// The index of the parameter the argument at the position is passed to.
+    pub active_parameter: Option<usize>,
+}
+
+/// A function signature, whether it comes from a `def` statement, the parameters of a
+/// builtin or the attributes of a rule.
+pub(crate) struct Signature {
+    name: String,
+    parameters: Vec<Parameter>,
+    return_ty: Option<String>,
+    pub(crate) doc: Option<String>,
+}
+
+struct Parameter {
+    /// The parameter as it appears in the signature, such as `*args` or `x: int = 1`.
+    label: String,
+    kind: ast::ParameterKind,
+    /// `None` for the `*` that separates keyword-only parameters.
+    name: Option<String>,
+}
+
+impl Signature {
+    pub(crate) fn from_def(def: &ast::DefStmt) -> Signature {
+        let parameters = def
+            .parameters()
+            .map(|parameters| parameters.parameters())
+            .unwrap_or_default()
+            .iter()
+            .map(|parameter| Parameter {
+                label: parameter_label(parameter),
+                kind: parameter.kind(),
+                name: parameter
+                    .name()
+                    .map(|name| name.syntax().text().to_string()),
+            })
+            .collect();
+        Signature {
+            name: def
+                .name()
+                .map(|name| name.syntax().text().to_string())
+                .unwrap_or_default(),
+            parameters,
+            return_ty: def.return_type().map(|ty| collapse_whitespace(ty.syntax())),
+            doc: docstring(def),
+        }
+    }
+
+    fn from_builtin(builtin: &Builtin) -> Signature {
+        // The parameter lists of builtins have no commas other than the separators.
+        let parameters = builtin
+            .params
+            .unwrap_or_default()
+            .split(", ")
+            .filter(|label| !label.is_empty())
+            .map(|label| {
+                let kind = if label.starts_with("**") {
+                    ast::ParameterKind::Kwargs
+                } else if label.starts_with('*') {
+                    ast::ParameterKind::Args
+                } else {
+                    ast::ParameterKind::Normal
+                };
+                let name = label.trim_start_matches('*').split('=').next().unwrap();
+                Parameter {
+                    label: label.to_string(),
+                    kind,
+                    name: Some(name.to_string()).filter(|name| !name.is_empty()),
+                }
+            })
+            .collect();
+        Signature {
+            name: builtin.name.to_string(),
+            parameters,
+            return_ty: None,
+            doc: Some(builtin.doc.to_string()),
+        }
+    }
+
+    /// The signature as a string, such as `f(x, y=1) -> str`, along with the range of
+    /// each parameter in it.
+    pub(crate) fn label(&self) -> (String, Vec<TextRange>) {
+        let mut label = format!("{}(", self.name);
+        let mut ranges = Vec::new();
+        for (index, parameter) in self.parameters.iter().enumerate() {
+            if index > 0 {
+                label.push_str(", ");
+            }
+            let start = TextSize::of(label.as_str());
+            label.push_str(&parameter.label);
+            ranges.push(TextRange::at(start, TextSize::of(parameter.label.as_str())));
+        }
+        label.push(')');
+        if let Some(ty) = &self.return_ty {
+            label.push_str(" -> ");
+            label.push_str(ty);
+        }
+        (label, ranges)
+    }
+
+    /// The index of the parameter that the argument at `index` in `arguments` is passed
+    /// to. The argument may not have been written yet.
+    fn parameter_for(&self, arguments: &[ast::Argument], index: usize) -> Option<usize> {
+        let find = |kind: fn(&ast::ParameterKind) -> bool| {
+            self.parameters
+                .iter()
+                .position(|parameter| kind(&parameter.kind) && parameter.name.is_some())
+        };
+        let keyword = |argument: &ast::Argument| match (argument.kind(), argument.name()) {
+            (ast::ArgumentKind::Normal, Some(name)) => Some(name.syntax().text().to_string()),
+            _ => None,
+        };
+        let keyword_parameter = |name: &str| {
+            self.parameters.iter().position(|parameter| {
+                matches!(parameter.kind, ast::ParameterKind::Normal)
+                    && parameter.name.as_deref() == Some(name)
+            })
+        };
+        let before = &arguments[..index.min(arguments.len())];
+        let positional = before
+            .iter()
+            .filter(|argument| {
+                matches!(argument.kind(), ast::ArgumentKind::Normal) && argument.name().is_none()
+            })
+            .count();
+        // Parameters after `*args` or `*` can only be passed by keyword.
+        let positional_parameters = self
+            .parameters
+            .iter()
+            .take_while(|parameter| matches!(parameter.kind, ast::ParameterKind::Normal))
+            .count();
+
+        let argument = match arguments.get(index) {
+            Some(argument) => argument,
+            // Only keyword arguments can follow a keyword argument, so a new one is for
+            // the first parameter that hasn't been passed yet.
+            None if before.iter().any(|argument| keyword(argument).is_some()) => {
+                let passed: Vec<String> = before.iter().filter_map(keyword).collect();
+                return self
+                    .parameters
+                    .iter()
+                    .enumerate()
+                    .position(|(index, parameter)| {
+                        matches!(parameter.kind, ast::ParameterKind::Normal)
+                            && index >= positional.min(positional_parameters)
+                            && matches!(&parameter.name, Some(name) if !passed.contains(name))
+                    })
+                    .or_else(|| find(|kind| matches!(kind, ast::ParameterKind::Kwargs)));
+            }
+            None if positional < positional_parameters => return Some(positional),
+            None => return find(|kind| matches!(kind, ast::ParameterKind::Args)),
+        };
+        match argument.kind() {
+            ast::ArgumentKind::Args => find(|kind| matches!(kind, ast::ParameterKind::Args)),
+            ast::ArgumentKind::Kwargs => find(|kind| matches!(kind, ast::ParameterKind::Kwargs)),
+            ast::ArgumentKind::Normal => match keyword(argument) {
+                Some(name) => keyword_parameter(&name)
+                    .or_else(|| find(|kind| matches!(kind, ast::ParameterKind::Kwargs))),
+                None if positional < positional_parameters => Some(positional),
+                None => find(|kind| matches!(kind, ast::ParameterKind::Args)),
+            },
+        }
+    }
+}
+
+/// Finds the signature of the function called by the innermost call whose parentheses
+/// enclose `position`, and the parameter that the argument at `position` is passed to,
+/// matching keyword arguments by name. Functions defined with `def`, builtins, and
+/// rules declared with a literal dict of attributes have signatures.
+pub fn signature_help(db: &dyn Db, position: FilePosition) -> Option<SignatureHelp> {
+    let parse = star_db::parse(db.as_source_db(), position.file);
+    let token = parse
+        .syntax()
+        .token_at_offset(position.offset)
+        .left_biased()?;
+    let call = token
+        .parent_ancestors()
+        .filter_map(ast::CallExpr::cast)
+        .find(|call| in_parentheses(call.syntax(), position.offset))?;
+
+    let callee = call.expr()?;
+    let signature = match star_hir::infer_expr(db, position.file, &callee) {
+        Ty::Function(function) => {
+            let parse = star_db::parse(db.as_source_db(), function.file(db));
+            let def = function.def(db, &parse.syntax())?;
+            Signature::from_def(&def)
+        }
+        Ty::Builtin(builtin) | Ty::Method(_, builtin) => Signature::from_builtin(builtin),
+        _ => rule_signature(db, position.file, &callee)?,
+    };
+
+    // Commas between arguments are in the argument list, and a trailing comma follows
+    // it directly.
+    let arguments = call.arguments();
+    let commas = call
+        .syntax()
+        .children_with_tokens()
+        .chain(
+            arguments
+                .iter()
+                .flat_map(|arguments| arguments.syntax().children_with_tokens()),
+        )
+        .filter(|el| el.kind() == T![,] && el.text_range().end() <= position.offset)
+        .count();
+    let arguments = arguments
+        .map(|arguments| arguments.arguments())
+        .unwrap_or_default();
+    let active_parameter = signature.parameter_for(&arguments, commas);
+
+    let (label, parameters) = signature.label();
+    Some(SignatureHelp {
+        label,
+        doc: signature.doc,
+        parameters,
+        active_parameter,
+    })
+}
+
+/// Whether `offset` is after the opening parenthesis of `call` and not after its closing
+/// one, if it has one.
+fn in_parentheses(call: &SyntaxNode, offset: TextSize) -> bool {
+    let mut tokens = call.children_with_tokens().filter_map(|el| el.into_token());
+    let after_open = matches!(
+        tokens.clone().find(|token| token.kind() == T!['(']),
+        Some(open) if open.text_range().end() <= offset
+    );
+    let before_close = match tokens.find(|token| token.kind() == T![')']) {
+        Some(close) => offset <= close.text_range().start(),
+        None => true,
+    };
+    after_open && before_close
+}
+
+/// The signature of a rule declared by an assignment like `foo = rule(attrs = {...})`,
+/// where `callee` names `foo`. Rules take a `name` and their attributes, all by keyword.
+fn rule_signature(db: &dyn Db, file: File, callee: &ast::Expr) -> Option<Signature> {
+    let ident = match callee {
+        ast::Expr::Literal(literal) => match literal.kind() {
+            ast::LiteralKind::Ident(ident) => ident,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let position = FilePosition {
+        file,
+        offset: ident.syntax().text_range().start(),
+    };
+    let call = crate::goto_definition(db, position)
+        .into_iter()
+        .find_map(|target| {
+            let parse = star_db::parse(db.as_source_db(), target.file);
+            let lhs = parse
+                .syntax()
+                .token_at_offset(target.range.start())
+                .right_biased()?
+                .parent()?;
+            let stmt = ast::AssignStmt::cast(lhs.parent()?)?;
+            if stmt.lhs()?.syntax() != &lhs {
+                return None;
+            }
+            match stmt.rhs()? {
+                ast::Expr::CallExpr(call) => Some(call),
+                _ => None,
+            }
+        })?;
+    let declaration = call.expr()?.syntax().text().to_string();
+    if declaration != "rule" && declaration != "repository_rule" {
+        return None;
+    }
+
+    let arguments = call.arguments()?.arguments();
+    let keyword = |keyword: &str| {
+        arguments
+            .iter()
+            .find(
+                |argument| matches!(argument.name(), Some(name) if name.syntax().text() == keyword),
+            )
+            .and_then(|argument| argument.value())
+    };
+    let mut names = vec!["name".to_string()];
+    if let Some(ast::Expr::DictExpr(attrs)) = keyword("attrs") {
+        for entry in attrs.entries().iter().flat_map(|entries| entries.entries()) {
+            if let Some(name) = entry.key().as_ref().and_then(string_value) {
+                names.push(name);
+            }
+        }
+    }
+    let separator = Parameter {
+        label: "*".to_string(),
+        kind: ast::ParameterKind::Args,
+        name: None,
+    };
+    let parameters = std::iter::once(separator)
+        .chain(names.into_iter().map(|name| Parameter {
+            label: name.clone(),
+            kind: ast::ParameterKind::Normal,
+            name: Some(name),
+        }))
+        .collect();
+    Some(Signature {
+        name: ident.syntax().text().to_string(),
+        parameters,
+        return_ty: None,
+        doc: keyword("doc").as_ref().and_then(string_value),
+    })
+}
+
+fn string_value(expr: &ast::Expr) -> Option<String> {
+    match expr {
+        ast::Expr::Literal(literal) => match literal.kind() {
+            ast::LiteralKind::String(string) => string.value(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// A parameter as it appears in a signature, such as `*args` or `x: int = 1`.
+fn parameter_label(parameter: &ast::Parameter) -> String {
+    let mut label = match parameter.kind() {
+        ast::ParameterKind::Args => "*".to_string(),
+        ast::ParameterKind::Kwargs => "**".to_string(),
+        ast::ParameterKind::Normal => String::new(),
+    };
+    if let Some(name) = parameter.name() {
+        label.push_str(name.syntax().text());
+    }
+    let ty = parameter.type_expr();
+    if let Some(ty) = &ty {
+        label.push_str(": ");
+        label.push_str(&collapse_whitespace(ty.syntax()));
+    }
+    if let Some(default) = parameter.default() {
+        // As in Python, `=` is only spaced out after a type.
+        label.push_str(if ty.is_some() { " = " } else { "=" });
+        label.push_str(&collapse_whitespace(default.syntax()));
+    }
+    label
+}
+
+fn collapse_whitespace(node: &SyntaxNode) -> String {
+    let text = node.text().to_string();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    /// Checks the signature of the call at `$0`, and the label of its active parameter.
+    fn check(fixture: &str, label: &str, active: Option<&str>) {
+        let (fixture, position) = Fixture::new(fixture);
+        let help = signature_help(&fixture.db.db, position).expect("no signature help");
+        assert_eq!(help.label, label);
+        let active_label = help
+            .active_parameter
+            .map(|index| &help.label[std::ops::Range::<usize>::from(help.parameters[index])]);
+        assert_eq!(active_label, active);
+    }
+
+    fn check_none(fixture: &str) {
+        let (fixture, position) = Fixture::new(fixture);
+        assert_eq!(signature_help(&fixture.db.db, position), None);
+    }
+
+    #[test]
+    fn positional_arguments() {
+        let fixture = r#"
+//- /main.bzl
+def foo(a, b: int = 1, *args, c, **kwargs) -> str:
+    pass
+foo($0)
+"#;
+        let label = "foo(a, b: int = 1, *args, c, **kwargs) -> str";
+        check(fixture, label, Some("a"));
+        check(&fixture.replace("$0", "x, $0"), label, Some("b: int = 1"));
+        check(&fixture.replace("$0", "x, y, z$0"), label, Some("*args"));
+        check(&fixture.replace("$0", "x, b=1, $0"), label, Some("c"));
+        check_none(&fixture.replace("foo($0)", "foo()$0"));
+        check_none(&fixture.replace("foo($0)", "$0foo()"));
+    }
+
+    #[test]
+    fn keyword_arguments() {
+        let fixture = r#"
+//- /main.bzl
+def foo(a, b = 1, *, c, **kwargs):
+    pass
+foo($0)
+"#;
+        let label = "foo(a, b=1, *, c, **kwargs)";
+        check(&fixture.replace("$0", "1, b=$0"), label, Some("b=1"));
+        check(&fixture.replace("$0", "c = [$0]"), label, Some("c"));
+        check(&fixture.replace("$0", "d=$0"), label, Some("**kwargs"));
+        check(&fixture.replace("$0", "1, 2, $0"), label, None);
+        check(&fixture.replace("$0", "1, **$0"), label, Some("**kwargs"));
+    }
+
+    #[test]
+    fn nested_calls() {
+        let fixture = r#"
+//- /main.bzl
+def foo(a, b):
+    pass
+def bar(x):
+    pass
+foo(1, bar($0))
+"#;
+        check(fixture, "bar(x)", Some("x"));
+        check(
+            &fixture.replace("bar($0))", "bar(2)$0)"),
+            "foo(a, b)",
+            Some("b"),
+        );
+    }
+
+    #[test]
+    fn builtins() {
+        check(
+            r#"
+//- /main.bzl
+sorted([], reverse = $0)
+"#,
+            "sorted(x, key=None, reverse=False)",
+            Some("reverse=False"),
+        );
+        check(
+            r#"
+//- /main.bzl
+"a".replace("b", $0)
+"#,
+            "replace(old, new, count=-1)",
+            Some("new"),
+        );
+        check(
+            r#"
+//- /main.bzl
+depset([], transitive = $0)
+"#,
+            "depset(direct=None, order=\"default\", *, transitive=None)",
+            Some("transitive=None"),
+        );
+        check(
+            r#"
+//- /main.bzl
+native.cc_library(name = "a", $0)
+"#,
+            "cc_library(name, **kwargs)",
+            Some("**kwargs"),
+        );
+    }
+
+    #[test]
+    fn rules() {
+        let fixture = r#"
+//- /pkg/BUILD
+load("//:defs.bzl", "my_rule")
+my_rule(
+    name = "a",
+    $0
+)
+//- /defs.bzl
+my_rule = rule(
+    implementation = _impl,
+    attrs = {
+        "srcs": attr.label_list(),
+        "deps": attr.label_list(),
+    },
+    doc = "Does things.",
+)
+"#;
+        let (rule_fixture, position) = Fixture::new(fixture);
+        let help = signature_help(&rule_fixture.db.db, position).unwrap();
+        assert_eq!(help.doc.as_deref(), Some("Does things."));
+        check(fixture, "my_rule(*, name, srcs, deps)", Some("srcs"));
+        check(
+            &fixture.replace("$0", "\"a\"$0"),
+            "my_rule(*, name, srcs, deps)",
+            None,
+        );
+        check(
+            &fixture.replace("$0", "deps = [$0]"),
+            "my_rule(*, name, srcs, deps)",
+            Some("deps"),
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use crate::DocumentSymbolKind;
+use star_db::File;
+use star_hir::{Db, SearchResult, SymbolKind};
+use star_syntax::TextRange;
+
+/// The most symbols returned for a query. Editors filter them further as the query
+/// gets longer.
+const LIMIT: usize = 128;
+
+/// A top-level symbol or BUILD target found in the workspace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorkspaceSymbol {
+    pub name: String,
+    pub kind: DocumentSymbolKind,
+    pub file: File,
+    /// The range of the identifier or string naming the symbol.
+    pub range: TextRange,
+    /// The rule of a target.
+    pub container: Option<String>,
+}
+
+/// Searches the top-level functions and variables and the BUILD targets of every file
+/// in the workspace for names that fuzzily match `query`.
+pub fn workspace_symbols(db: &dyn Db, query: &str) -> Vec<WorkspaceSymbol> {
+    let workspace = db.as_source_db().inputs().workspace();
+    star_hir::search_symbols(db, workspace, query, LIMIT)
+        .into_iter()
+        .map(|result| match result {
+            SearchResult::Symbol(file, symbol) => WorkspaceSymbol {
+                name: symbol.name.clone(),
+                kind: match symbol.kind {
+                    SymbolKind::Function => DocumentSymbolKind::Function,
+                    SymbolKind::Variable => DocumentSymbolKind::Variable,
+                },
+                file,
+                range: symbol.range,
+                container: None,
+            },
+            SearchResult::Target(file, target) => WorkspaceSymbol {
+                name: target.name.clone(),
+                kind: DocumentSymbolKind::Target,
+                file,
+                range: target.name_range,
+                container: Some(target.rule.clone()),
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    #[test]
+    fn symbols_and_targets() {
+        let (fixture, _) = Fixture::new(
+            r#"
+//- /pkg/BUILD
+load("//:defs.bzl", "cc_rule")
+cc_rule(name = "rules")
+$0
+//- /defs.bzl
+def cc_rule():
+    pass
+_RULES = []
+"#,
+        );
+        let db = &fixture.db.db;
+        let actual: Vec<(String, DocumentSymbolKind, &str, Option<String>)> =
+            workspace_symbols(db, "rule")
+                .into_iter()
+                .map(|symbol| {
+                    let text = symbol.file.text(db);
+                    let range = std::ops::Range::<usize>::from(symbol.range);
+                    assert_eq!(text[range].trim_matches('"'), symbol.name);
+                    (
+                        symbol.name,
+                        symbol.kind,
+                        symbol.file.path(db).to_str().unwrap(),
+                        symbol.container,
+                    )
+                })
+                .collect();
+        assert_eq!(
+            actual,
+            [
+                (
+                    "rules".to_string(),
+                    DocumentSymbolKind::Target,
+                    "/pkg/BUILD",
+                    Some("cc_rule".to_string())
+                ),
+                (
+                    "_RULES".to_string(),
+                    DocumentSymbolKind::Variable,
+                    "/defs.bzl",
+                    None
+                ),
+                (
+                    "cc_rule".to_string(),
+                    DocumentSymbolKind::Function,
+                    "/defs.bzl",
+                    None
+                ),
+            ]
+        );
+    }
+}
@@ -11,11 +11,11 @@ fn main() -> Result<(), Box<dyn Error + Sync + Send>> {
     let (connection, io_threads) = Connection::stdio();
 
     // Run the server and wait for the two threads to end (typically by trigger LSP Exit event).
-    let server_capabilities = serde_json::to_value(star_ls::server_capabilities()).unwrap();
+    let (initialize_id, initialize_params) = connection.initialize_start()?;
+    let config = star_ls::Config::new(&initialize_params);
+    connection.initialize_finish(initialize_id, star_ls::initialize_result(&config))?;
 
-    let initialization_params = connection.initialize(server_capabilities)?;
-
-    star_ls::main_loop(connection)?;
+    star_ls::main_loop(connection, config)?;
 
     io_threads.join()?;
 
@@ -0,0 +1,98 @@
+use lsp_types::Url;
+use serde_json::Value;
+use star_syntax::lines::Encoding;
+use std::{collections::BTreeMap, path::PathBuf};
+
+/// Settings negotiated with the client during initialization.
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub(crate) position_encoding: Encoding,
+    /// Whether the client lets us register file watchers, to be told about files changed
+    /// outside the editor.
+    pub(crate) watch_files: bool,
+    /// Whether the client can show the progress of work started by the server.
+    pub(crate) work_done_progress: bool,
+    /// The directory the client opened.
+    pub(crate) root_path: Option<PathBuf>,
+    /// Repositories to load from local directories instead, like Bazel's
+    /// `--override_repository=name=path`. Set with the `overrideRepositories`
+    /// initialization option, as a list of `name=path` strings.
+    pub(crate) override_repositories: BTreeMap<String, PathBuf>,
+    /// Glob patterns for paths to skip while indexing the workspace, on top of the ones
+    /// in `.bazelignore`. Set with the `excludes` initialization option.
+    pub(crate) excludes: Vec<String>,
+}
+
+impl Config {
+    pub fn new(initialize_params: &Value) -> Config {
+        Config {
+            position_encoding: negotiate_position_encoding(initialize_params),
+            watch_files: initialize_params
+                .pointer("/capabilities/workspace/didChangeWatchedFiles/dynamicRegistration")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            work_done_progress: initialize_params
+                .pointer("/capabilities/window/workDoneProgress")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            root_path: root_path(initialize_params),
+            override_repositories: override_repositories(initialize_params),
+            excludes: initialize_params
+                .pointer("/initializationOptions/excludes")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    /// The value of the `positionEncoding` server capability.
+    pub fn position_encoding_kind(&self) -> &'static str {
+        match self.position_encoding {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf16 => "utf-16",
+            Encoding::Utf32 => "utf-32",
+        }
+    }
+}
+
+/// Picks one of the encodings the client offers in `general.positionEncodings`,
+/// preferring UTF-8 since that is how offsets are stored. Clients that don't offer
+/// any only support UTF-16.
+fn negotiate_position_encoding(initialize_params: &Value) -> Encoding {
+    let offered: Vec<&str> = initialize_params
+        .pointer("/capabilities/general/positionEncodings")
+        .and_then(Value::as_array)
+        .map(|encodings| encodings.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    if offered.contains(&"utf-8") {
+        Encoding::Utf8
+    } else if offered.contains(&"utf-32") {
+        Encoding::Utf32
+    } else {
+        Encoding::Utf16
+    }
+}
+
+fn root_path(initialize_params: &Value) -> Option<PathBuf> {
+    let root_uri = initialize_params
+        .pointer("/workspaceFolders/0/uri")
+        .or_else(|| initialize_params.get("rootUri"))
+        .and_then(Value::as_str)?;
+    Url::parse(root_uri).ok()?.to_file_path().ok()
+}
+
+fn override_repositories(initialize_params: &Value) -> BTreeMap<String, PathBuf> {
+    initialize_params
+        .pointer("/initializationOptions/overrideRepositories")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .filter_map(|option| option.split_once('='))
+        .map(|(name, path)| (name.to_string(), PathBuf::from(path)))
+        .collect()
+}
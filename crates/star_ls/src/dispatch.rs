@@ -0,0 +1,177 @@
+use crate::{
+    global_state::{GlobalState, GlobalStateSnapshot},
+    lsp_utils::LspError,
+    main_loop::Task,
+    Result,
+};
+use lsp_server::{ErrorCode, Notification, Request, RequestId, Response};
+use serde::{de::DeserializeOwned, Serialize};
+use std::panic::{self, AssertUnwindSafe};
+
+/// Routes a request to the handler registered for its method. Requests that no handler
+/// claims are answered with `MethodNotFound` by [`RequestDispatcher::finish`].
+pub(crate) struct RequestDispatcher<'a> {
+    req: Option<Request>,
+    global_state: &'a mut GlobalState,
+}
+
+impl<'a> RequestDispatcher<'a> {
+    pub(crate) fn new(req: Request, global_state: &'a mut GlobalState) -> RequestDispatcher<'a> {
+        RequestDispatcher {
+            req: Some(req),
+            global_state,
+        }
+    }
+
+    /// Runs a read-only handler on the task pool, against a snapshot of the current state.
+    pub(crate) fn on<R>(
+        &mut self,
+        f: fn(GlobalStateSnapshot, R::Params) -> Result<R::Result>,
+    ) -> &mut Self
+    where
+        R: lsp_types::request::Request,
+        R::Params: DeserializeOwned + Send + 'static,
+        R::Result: Serialize + 'static,
+    {
+        let (id, params) = match self.parse::<R>() {
+            Some(it) => it,
+            None => return self,
+        };
+
+        let snap = self.global_state.snapshot();
+        self.global_state
+            .task_pool
+            .spawn(move || Task::Response(run_handler(id, || f(snap, params))));
+        self
+    }
+
+    pub(crate) fn finish(&mut self) {
+        if let Some(req) = self.req.take() {
+            self.global_state.respond(Response::new_err(
+                req.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unknown request: {}", req.method),
+            ));
+        }
+    }
+
+    /// Takes the request if it is for `R`, answering with `InvalidParams` if its
+    /// parameters can't be deserialized.
+    fn parse<R>(&mut self) -> Option<(RequestId, R::Params)>
+    where
+        R: lsp_types::request::Request,
+        R::Params: DeserializeOwned,
+    {
+        let req = match &self.req {
+            Some(req) if req.method == R::METHOD => self.req.take().unwrap(),
+            _ => return None,
+        };
+
+        match serde_json::from_value(req.params) {
+            Ok(params) => Some((req.id, params)),
+            Err(err) => {
+                self.global_state.respond(Response::new_err(
+                    req.id,
+                    ErrorCode::InvalidParams as i32,
+                    err.to_string(),
+                ));
+                None
+            }
+        }
+    }
+}
+
+/// Runs a request handler, answering with an error if it fails or panics. Failures are
+/// `InternalError`s unless the handler returns an [`LspError`], and a handler that is
+/// cancelled because the database changed is answered with `ContentModified`.
+fn run_handler<T: Serialize>(id: RequestId, f: impl FnOnce() -> Result<T>) -> Response {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(result)) => Response::new_ok(id, result),
+        Ok(Err(err)) => match err.downcast::<LspError>() {
+            Ok(err) => Response::new_err(id, err.code, err.message),
+            Err(err) => Response::new_err(id, ErrorCode::InternalError as i32, err.to_string()),
+        },
+        Err(payload) if payload.is::<salsa::Cancelled>() => Response::new_err(
+            id,
+            ErrorCode::ContentModified as i32,
+            "content modified".to_string(),
+        ),
+        Err(payload) => {
+            let message = match payload.downcast_ref::<&str>() {
+                Some(message) => message.to_string(),
+                None => match payload.downcast_ref::<String>() {
+                    Some(message) => message.clone(),
+                    None => "unknown panic".to_string(),
+                },
+            };
+            Response::new_err(
+                id,
+                ErrorCode::InternalError as i32,
+                format!("request handler panicked: {}", message),
+            )
+        }
+    }
+}
+
+/// Routes a notification to the handler registered for its method. Notifications can't
+/// be answered, so ones with invalid parameters or unknown methods are only logged.
+pub(crate) struct NotificationDispatcher<'a> {
+    not: Option<Notification>,
+    global_state: &'a mut GlobalState,
+}
+
+impl<'a> NotificationDispatcher<'a> {
+    pub(crate) fn new(
+        not: Notification,
+        global_state: &'a mut GlobalState,
+    ) -> NotificationDispatcher<'a> {
+        NotificationDispatcher {
+            not: Some(not),
+            global_state,
+        }
+    }
+
+    pub(crate) fn on<N>(&mut self, f: fn(&mut GlobalState, N::Params)) -> &mut Self
+    where
+        N: lsp_types::notification::Notification,
+        N::Params: DeserializeOwned,
+    {
+        let not = match &self.not {
+            Some(not) if not.method == N::METHOD => self.not.take().unwrap(),
+            _ => return self,
+        };
+
+        match serde_json::from_value(not.params) {
+            Ok(params) => f(self.global_state, params),
+            Err(err) => eprintln!("invalid params for {}: {}", N::METHOD, err),
+        }
+        self
+    }
+
+    pub(crate) fn finish(&mut self) {
+        if let Some(not) = self.not.take() {
+            // Notifications starting with `$/` are optional and may be ignored silently.
+            if !not.method.starts_with("$/") {
+                eprintln!("unhandled notification: {:?}", not);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handler_panics_are_answered() {
+        let response = run_handler::<()>(RequestId::from(1), || panic!("boom"));
+        assert_eq!(response.id, RequestId::from(1));
+        assert!(response.result.is_none());
+        let error = response.error.unwrap();
+        assert_eq!(error.code, ErrorCode::InternalError as i32);
+        assert_eq!(error.message, "request handler panicked: boom");
+
+        let response = run_handler(RequestId::from(2), || Ok(1));
+        assert_eq!(response.result, Some(serde_json::json!(1)));
+    }
+}
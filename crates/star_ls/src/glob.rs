@@ -0,0 +1,74 @@
+/// A glob pattern, matched against relative paths separated by `/`. `*` matches any
+/// characters within a path segment, `?` matches a single one, and `**` matches any
+/// number of segments.
+#[derive(Clone, Debug)]
+pub(crate) struct Glob {
+    pattern: Vec<char>,
+}
+
+impl Glob {
+    pub(crate) fn new(pattern: &str) -> Glob {
+        Glob {
+            pattern: pattern.trim_matches('/').chars().collect(),
+        }
+    }
+
+    pub(crate) fn matches(&self, path: &str) -> bool {
+        let path: Vec<char> = path.chars().collect();
+        matches(&self.pattern, &path)
+    }
+}
+
+fn matches(pattern: &[char], path: &[char]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        ['*', '*', '/', rest @ ..] => {
+            // Matches zero or more whole segments.
+            matches(rest, path)
+                || (0..path.len())
+                    .filter(|&i| path[i] == '/')
+                    .any(|i| matches(rest, &path[i + 1..]))
+        }
+        ['*', '*', rest @ ..] => (0..=path.len()).any(|i| matches(rest, &path[i..])),
+        ['*', rest @ ..] => {
+            let segment_len = path.iter().take_while(|&&c| c != '/').count();
+            (0..=segment_len).any(|i| matches(rest, &path[i..]))
+        }
+        ['?', rest @ ..] => {
+            matches!(path.first(), Some(&c) if c != '/') && matches(rest, &path[1..])
+        }
+        [c, rest @ ..] => path.first() == Some(c) && matches(rest, &path[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob() {
+        let cases = [
+            ("node_modules", "node_modules", true),
+            ("node_modules", "a/node_modules", false),
+            ("**/node_modules", "a/b/node_modules", true),
+            ("**/node_modules", "node_modules", true),
+            ("third_party/*", "third_party/foo", true),
+            ("third_party/*", "third_party/foo/bar", false),
+            ("third_party/**", "third_party/foo/bar", true),
+            ("*.bzl", "defs.bzl", true),
+            ("*.bzl", "pkg/defs.bzl", false),
+            ("pkg/?.bzl", "pkg/a.bzl", true),
+            ("pkg/?.bzl", "pkg/ab.bzl", false),
+            ("/out/", "out", true),
+        ];
+        for (pattern, path, expected) in cases {
+            assert_eq!(
+                Glob::new(pattern).matches(path),
+                expected,
+                "{} {}",
+                pattern,
+                path
+            );
+        }
+    }
+}
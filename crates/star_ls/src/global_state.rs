@@ -1,34 +1,49 @@
 use crossbeam_channel::{Receiver, Sender};
 use lsp_server::{Connection, Message};
 use lsp_types::Url;
-use star_db::SourceDatabase;
+use star_db::{SourceDatabase, SourceDatabaseSnapshot};
+use star_hir::RootDatabase;
+use star_syntax::TextEdit;
 use std::{
     collections::{HashMap, HashSet},
     mem,
 };
 
-use crate::{main_loop::Task, subscriptions::Subscriptions};
+use crate::{main_loop::Task, subscriptions::Subscriptions, workspace_loader::Ignore, Config};
 
 pub(crate) struct GlobalState {
-    /// Changes to document contents.
-    pub(crate) changes: Vec<(Url, String)>,
+    /// Changes to document contents, with the edits that made them if they are known.
+    pub(crate) changes: Vec<(Url, String, Option<Vec<TextEdit>>)>,
+    /// Whether files were closed in the editor or changed on disk since the last event.
+    pub(crate) vfs_changed: bool,
+    pub(crate) config: Config,
     pub(crate) connection: Connection,
-    pub(crate) db: SourceDatabase,
+    pub(crate) db: SourceDatabase<RootDatabase>,
 
     /// Changes to calculated diagnostics.
     pub(crate) diagnostics_to_sync: HashSet<Url>,
     pub(crate) latest_diagnostics: HashMap<Url, Vec<lsp_types::Diagnostic>>,
+    /// The paths to skip in the workspace, once one is found.
+    pub(crate) ignore: Option<Ignore>,
+    /// How many files have been indexed, out of how many, while indexing is running.
+    pub(crate) indexing: Option<(usize, usize)>,
+    next_request_id: i32,
     pub(crate) task_pool: TaskPool,
 
     pub(crate) subscriptions: Subscriptions,
 }
 
 impl GlobalState {
-    pub(crate) fn new(connection: Connection) -> Self {
+    pub(crate) fn new(connection: Connection, config: Config) -> Self {
         Self {
             changes: Default::default(),
+            vfs_changed: false,
+            config,
             diagnostics_to_sync: Default::default(),
             latest_diagnostics: Default::default(),
+            ignore: None,
+            indexing: None,
+            next_request_id: 0,
             connection,
             db: SourceDatabase::default(),
             task_pool: TaskPool::new(),
@@ -36,6 +51,13 @@ impl GlobalState {
         }
     }
 
+    pub(crate) fn snapshot(&self) -> GlobalStateSnapshot {
+        GlobalStateSnapshot {
+            config: self.config.clone(),
+            db: self.db.snapshot(),
+        }
+    }
+
     pub(crate) fn process_incoming_diagnostics(
         &mut self,
         url: Url,
@@ -60,7 +82,7 @@ impl GlobalState {
         mem::take(&mut self.diagnostics_to_sync)
     }
 
-    pub(crate) fn take_changes(&mut self) -> Vec<(Url, String)> {
+    pub(crate) fn take_changes(&mut self) -> Vec<(Url, String, Option<Vec<TextEdit>>)> {
         mem::take(&mut self.changes)
     }
 
@@ -73,11 +95,83 @@ impl GlobalState {
         self.send(not.into())
     }
 
+    /// Sends a request to the client. Its response is ignored.
+    pub(crate) fn send_request<R>(&mut self, params: R::Params)
+    where
+        R: lsp_types::request::Request,
+    {
+        self.next_request_id += 1;
+        let req =
+            lsp_server::Request::new(self.next_request_id.into(), R::METHOD.to_string(), params);
+        self.send(req.into())
+    }
+
+    /// Reports the progress of a long-running operation, if the client can show it.
+    pub(crate) fn report_progress(
+        &mut self,
+        title: &str,
+        state: Progress,
+        message: Option<String>,
+        fraction: Option<f64>,
+    ) {
+        if !self.config.work_done_progress {
+            return;
+        }
+        let percentage = fraction.map(|fraction| (fraction * 100.0) as u32);
+        let token = lsp_types::ProgressToken::String(format!("star-ls/{}", title));
+        let value = match state {
+            Progress::Begin => {
+                self.send_request::<lsp_types::request::WorkDoneProgressCreate>(
+                    lsp_types::WorkDoneProgressCreateParams {
+                        token: token.clone(),
+                    },
+                );
+                lsp_types::WorkDoneProgress::Begin(lsp_types::WorkDoneProgressBegin {
+                    title: title.to_string(),
+                    cancellable: None,
+                    message,
+                    percentage,
+                })
+            }
+            Progress::Report => {
+                lsp_types::WorkDoneProgress::Report(lsp_types::WorkDoneProgressReport {
+                    cancellable: None,
+                    message,
+                    percentage,
+                })
+            }
+            Progress::End => {
+                lsp_types::WorkDoneProgress::End(lsp_types::WorkDoneProgressEnd { message })
+            }
+        };
+        self.send_notification::<lsp_types::notification::Progress>(lsp_types::ProgressParams {
+            token,
+            value: lsp_types::ProgressParamsValue::WorkDone(value),
+        });
+    }
+
+    pub(crate) fn respond(&self, response: lsp_server::Response) {
+        self.send(response.into())
+    }
+
     pub(crate) fn send(&self, msg: Message) {
         self.connection.sender.send(msg).unwrap();
     }
 }
 
+pub(crate) enum Progress {
+    Begin,
+    Report,
+    End,
+}
+
+/// A read-only view of the server state, handed to request handlers running on the
+/// task pool.
+pub(crate) struct GlobalStateSnapshot {
+    pub(crate) config: Config,
+    pub(crate) db: SourceDatabaseSnapshot<RootDatabase>,
+}
+
 pub struct TaskPool {
     pool: rayon::ThreadPool,
     sender: Sender<Task>,
@@ -0,0 +1,432 @@
+use crate::{
+    global_state::GlobalStateSnapshot,
+    lsp_utils::{offset, position, LspError},
+    Result,
+};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionParams, CompletionResponse, DocumentHighlight,
+    DocumentHighlightKind, DocumentHighlightParams, DocumentSymbol, DocumentSymbolParams,
+    DocumentSymbolResponse, Documentation, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+    HoverContents, HoverParams, Location, MarkupContent, MarkupKind, ParameterInformation,
+    ParameterLabel, PrepareRenameResponse, Range, ReferenceParams, RenameParams, SignatureHelp,
+    SignatureHelpParams, SignatureInformation, SymbolInformation, SymbolKind,
+    TextDocumentPositionParams, TextEdit, Url, WorkspaceEdit, WorkspaceSymbolParams,
+    WorkspaceSymbolResponse,
+};
+use star_db::{lines, SourceDatabaseSnapshot};
+use star_hir::RootDatabase;
+use star_ide::{CompletionKind, DocumentSymbolKind, FilePosition, ReferenceKind, RenameError};
+use star_syntax::{
+    lines::{Encoding, Lines},
+    TextRange, TextSize,
+};
+use std::collections::HashMap;
+
+pub(crate) fn goto_definition(
+    snap: GlobalStateSnapshot,
+    params: GotoDefinitionParams,
+) -> Result<Option<GotoDefinitionResponse>> {
+    let encoding = snap.config.position_encoding;
+    let snap = snap.db;
+    let file_position = match file_position(&snap, params.text_document_position_params, encoding)?
+    {
+        Some(file_position) => file_position,
+        None => return Ok(None),
+    };
+
+    let locations = star_ide::goto_definition(&*snap.db, file_position)
+        .into_iter()
+        .filter_map(|target| {
+            let lines = lines(&*snap.db, target.file);
+            let range = Range {
+                start: position(&lines, target.range.start().into(), encoding),
+                end: position(&lines, target.range.end().into(), encoding),
+            };
+            let url = Url::from_file_path(target.file.path(&*snap.db)).ok()?;
+            Some(Location::new(url, range))
+        })
+        .collect();
+    Ok(Some(GotoDefinitionResponse::Array(locations)))
+}
+
+pub(crate) fn completion(
+    snap: GlobalStateSnapshot,
+    params: CompletionParams,
+) -> Result<Option<CompletionResponse>> {
+    let encoding = snap.config.position_encoding;
+    let snap = snap.db;
+    let file_position = match file_position(&snap, params.text_document_position, encoding)? {
+        Some(file_position) => file_position,
+        None => return Ok(None),
+    };
+
+    let items = star_ide::completions(&*snap.db, file_position)
+        .into_iter()
+        .map(|item| CompletionItem {
+            label: item.label,
+            kind: Some(match item.kind {
+                CompletionKind::Function => CompletionItemKind::FUNCTION,
+                CompletionKind::Variable | CompletionKind::Parameter => {
+                    CompletionItemKind::VARIABLE
+                }
+                CompletionKind::Constant => CompletionItemKind::CONSTANT,
+                CompletionKind::Keyword => CompletionItemKind::KEYWORD,
+                CompletionKind::Method => CompletionItemKind::METHOD,
+                CompletionKind::Field => CompletionItemKind::FIELD,
+            }),
+            detail: item.detail,
+            ..Default::default()
+        })
+        .collect();
+    Ok(Some(CompletionResponse::Array(items)))
+}
+
+pub(crate) fn hover(snap: GlobalStateSnapshot, params: HoverParams) -> Result<Option<Hover>> {
+    let encoding = snap.config.position_encoding;
+    let snap = snap.db;
+    let file_position = match file_position(&snap, params.text_document_position_params, encoding)?
+    {
+        Some(file_position) => file_position,
+        None => return Ok(None),
+    };
+
+    let result = match star_ide::hover(&*snap.db, file_position) {
+        Some(result) => result,
+        None => return Ok(None),
+    };
+    let lines = lines(&*snap.db, file_position.file);
+    Ok(Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: result.markup,
+        }),
+        range: Some(Range {
+            start: position(&lines, result.range.start().into(), encoding),
+            end: position(&lines, result.range.end().into(), encoding),
+        }),
+    }))
+}
+
+pub(crate) fn signature_help(
+    snap: GlobalStateSnapshot,
+    params: SignatureHelpParams,
+) -> Result<Option<SignatureHelp>> {
+    let encoding = snap.config.position_encoding;
+    let snap = snap.db;
+    let file_position = match file_position(&snap, params.text_document_position_params, encoding)?
+    {
+        Some(file_position) => file_position,
+        None => return Ok(None),
+    };
+
+    let help = match star_ide::signature_help(&*snap.db, file_position) {
+        Some(help) => help,
+        None => return Ok(None),
+    };
+    // Offsets in the label are in UTF-16 code units.
+    let utf16_offset = |offset: TextSize| help.label[..offset.into()].encode_utf16().count() as u32;
+    let parameters = help
+        .parameters
+        .iter()
+        .map(|range| ParameterInformation {
+            label: ParameterLabel::LabelOffsets([
+                utf16_offset(range.start()),
+                utf16_offset(range.end()),
+            ]),
+            documentation: None,
+        })
+        .collect();
+    let active_parameter = help.active_parameter.map(|index| index as u32);
+    let signature = SignatureInformation {
+        label: help.label.clone(),
+        documentation: help.doc.map(|doc| {
+            Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: doc,
+            })
+        }),
+        parameters: Some(parameters),
+        active_parameter,
+    };
+    Ok(Some(SignatureHelp {
+        signatures: vec![signature],
+        active_signature: Some(0),
+        active_parameter,
+    }))
+}
+
+pub(crate) fn references(
+    snap: GlobalStateSnapshot,
+    params: ReferenceParams,
+) -> Result<Option<Vec<Location>>> {
+    let encoding = snap.config.position_encoding;
+    let snap = snap.db;
+    let file_position = match file_position(&snap, params.text_document_position, encoding)? {
+        Some(file_position) => file_position,
+        None => return Ok(None),
+    };
+
+    let include_declaration = params.context.include_declaration;
+    let locations = star_ide::find_references(&*snap.db, file_position, include_declaration)
+        .into_iter()
+        .filter_map(|reference| {
+            let lines = lines(&*snap.db, reference.file);
+            let range = Range {
+                start: position(&lines, reference.range.start().into(), encoding),
+                end: position(&lines, reference.range.end().into(), encoding),
+            };
+            let url = Url::from_file_path(reference.file.path(&*snap.db)).ok()?;
+            Some(Location::new(url, range))
+        })
+        .collect();
+    Ok(Some(locations))
+}
+
+pub(crate) fn document_highlight(
+    snap: GlobalStateSnapshot,
+    params: DocumentHighlightParams,
+) -> Result<Option<Vec<DocumentHighlight>>> {
+    let encoding = snap.config.position_encoding;
+    let snap = snap.db;
+    let file_position = match file_position(&snap, params.text_document_position_params, encoding)?
+    {
+        Some(file_position) => file_position,
+        None => return Ok(None),
+    };
+
+    let lines = lines(&*snap.db, file_position.file);
+    let highlights = star_ide::highlight_references(&*snap.db, file_position)
+        .into_iter()
+        .map(|reference| DocumentHighlight {
+            range: Range {
+                start: position(&lines, reference.range.start().into(), encoding),
+                end: position(&lines, reference.range.end().into(), encoding),
+            },
+            kind: Some(match reference.kind {
+                ReferenceKind::Write => DocumentHighlightKind::WRITE,
+                ReferenceKind::Read => DocumentHighlightKind::READ,
+            }),
+        })
+        .collect();
+    Ok(Some(highlights))
+}
+
+pub(crate) fn prepare_rename(
+    snap: GlobalStateSnapshot,
+    params: TextDocumentPositionParams,
+) -> Result<Option<PrepareRenameResponse>> {
+    let encoding = snap.config.position_encoding;
+    let snap = snap.db;
+    let file_position = match file_position(&snap, params, encoding)? {
+        Some(file_position) => file_position,
+        None => return Ok(None),
+    };
+
+    let range = match star_ide::prepare_rename(&*snap.db, file_position) {
+        Ok(range) => range,
+        Err(RenameError::NoName) => return Ok(None),
+        Err(err) => return Err(LspError::request_failed(err.to_string()).into()),
+    };
+    let lines = lines(&*snap.db, file_position.file);
+    Ok(Some(PrepareRenameResponse::Range(Range {
+        start: position(&lines, range.start().into(), encoding),
+        end: position(&lines, range.end().into(), encoding),
+    })))
+}
+
+pub(crate) fn rename(
+    snap: GlobalStateSnapshot,
+    params: RenameParams,
+) -> Result<Option<WorkspaceEdit>> {
+    let encoding = snap.config.position_encoding;
+    let snap = snap.db;
+    let file_position = match file_position(&snap, params.text_document_position, encoding)? {
+        Some(file_position) => file_position,
+        None => return Ok(None),
+    };
+
+    let edits = match star_ide::rename(&*snap.db, file_position, &params.new_name) {
+        Ok(edits) => edits,
+        Err(RenameError::NoName) => return Ok(None),
+        Err(err) => return Err(LspError::request_failed(err.to_string()).into()),
+    };
+    let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+    for edit in edits {
+        let url = match Url::from_file_path(edit.file.path(&*snap.db)) {
+            Ok(url) => url,
+            Err(()) => continue,
+        };
+        let lines = lines(&*snap.db, edit.file);
+        let range = Range {
+            start: position(&lines, edit.range.start().into(), encoding),
+            end: position(&lines, edit.range.end().into(), encoding),
+        };
+        changes
+            .entry(url)
+            .or_default()
+            .push(TextEdit::new(range, edit.new_text));
+    }
+    Ok(Some(WorkspaceEdit::new(changes)))
+}
+
+pub(crate) fn document_symbol(
+    snap: GlobalStateSnapshot,
+    params: DocumentSymbolParams,
+) -> Result<Option<DocumentSymbolResponse>> {
+    let encoding = snap.config.position_encoding;
+    let snap = snap.db;
+    let file = match params
+        .text_document
+        .uri
+        .to_file_path()
+        .ok()
+        .and_then(|path| snap.file(&path))
+    {
+        Some(file) => file,
+        None => return Ok(None),
+    };
+
+    let lines = lines(&*snap.db, file);
+    let symbols = star_ide::document_symbols(&*snap.db, file)
+        .into_iter()
+        .map(|symbol| to_document_symbol(&lines, symbol, encoding))
+        .collect();
+    Ok(Some(DocumentSymbolResponse::Nested(symbols)))
+}
+
+fn to_document_symbol(
+    lines: &Lines,
+    symbol: star_ide::DocumentSymbol,
+    encoding: Encoding,
+) -> DocumentSymbol {
+    let range = |range: TextRange| Range {
+        start: position(lines, range.start().into(), encoding),
+        end: position(lines, range.end().into(), encoding),
+    };
+    #[allow(deprecated)]
+    DocumentSymbol {
+        name: symbol.name,
+        detail: symbol.detail,
+        kind: symbol_kind(symbol.kind),
+        tags: None,
+        deprecated: None,
+        range: range(symbol.range),
+        selection_range: range(symbol.selection_range),
+        children: Some(
+            symbol
+                .children
+                .into_iter()
+                .map(|child| to_document_symbol(lines, child, encoding))
+                .collect(),
+        ),
+    }
+}
+
+pub(crate) fn workspace_symbol(
+    snap: GlobalStateSnapshot,
+    params: WorkspaceSymbolParams,
+) -> Result<Option<WorkspaceSymbolResponse>> {
+    let encoding = snap.config.position_encoding;
+    let snap = snap.db;
+    let symbols = star_ide::workspace_symbols(&*snap.db, &params.query)
+        .into_iter()
+        .filter_map(|symbol| {
+            let lines = lines(&*snap.db, symbol.file);
+            let range = Range {
+                start: position(&lines, symbol.range.start().into(), encoding),
+                end: position(&lines, symbol.range.end().into(), encoding),
+            };
+            let url = Url::from_file_path(symbol.file.path(&*snap.db)).ok()?;
+            #[allow(deprecated)]
+            Some(SymbolInformation {
+                name: symbol.name,
+                kind: symbol_kind(symbol.kind),
+                tags: None,
+                deprecated: None,
+                location: Location::new(url, range),
+                container_name: symbol.container,
+            })
+        })
+        .collect();
+    Ok(Some(WorkspaceSymbolResponse::Flat(symbols)))
+}
+
+fn symbol_kind(kind: DocumentSymbolKind) -> SymbolKind {
+    match kind {
+        DocumentSymbolKind::Function => SymbolKind::FUNCTION,
+        DocumentSymbolKind::Parameter | DocumentSymbolKind::Variable => SymbolKind::VARIABLE,
+        DocumentSymbolKind::Module => SymbolKind::MODULE,
+        DocumentSymbolKind::Target => SymbolKind::OBJECT,
+    }
+}
+
+/// Converts a position in a document to an offset in the file, if the file is known.
+fn file_position(
+    snap: &SourceDatabaseSnapshot<RootDatabase>,
+    params: TextDocumentPositionParams,
+    encoding: Encoding,
+) -> Result<Option<FilePosition>> {
+    let file = match params
+        .text_document
+        .uri
+        .to_file_path()
+        .ok()
+        .and_then(|path| snap.file(&path))
+    {
+        Some(file) => file,
+        None => return Ok(None),
+    };
+    let offset = offset(&lines(&*snap.db, file), params.position, encoding);
+    Ok(Some(FilePosition {
+        file,
+        offset: TextSize::try_from(offset)?,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use lsp_server::ErrorCode;
+    use lsp_types::{Position, TextDocumentIdentifier};
+    use star_db::SourceDatabase;
+    use std::path::Path;
+
+    /// A snapshot of a database holding `text` at `/main.bzl`.
+    fn snapshot(text: &str) -> GlobalStateSnapshot {
+        let mut db: SourceDatabase<RootDatabase> = SourceDatabase::default();
+        db.set_overlay(Path::new("/main.bzl"), text.to_string());
+        GlobalStateSnapshot {
+            config: Config::new(&serde_json::json!({})),
+            db: db.snapshot(),
+        }
+    }
+
+    fn text_document_position(line: u32, character: u32) -> TextDocumentPositionParams {
+        TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier::new(Url::from_file_path("/main.bzl").unwrap()),
+            position: Position::new(line, character),
+        }
+    }
+
+    #[test]
+    fn rename_refusals() {
+        let text = "x = len([])\n";
+        let params = RenameParams {
+            text_document_position: text_document_position(0, 5),
+            new_name: "size".to_string(),
+            work_done_progress_params: Default::default(),
+        };
+        let err = rename(snapshot(text), params).unwrap_err();
+        let err = err.downcast::<LspError>().unwrap();
+        assert_eq!(err.code, ErrorCode::RequestFailed as i32);
+        assert_eq!(err.message, "cannot rename builtin `len`");
+
+        let err = prepare_rename(snapshot(text), text_document_position(0, 5)).unwrap_err();
+        assert!(err.downcast::<LspError>().is_ok());
+
+        // There is nothing to rename next to `=`.
+        let response = prepare_rename(snapshot(text), text_document_position(0, 2)).unwrap();
+        assert!(response.is_none());
+    }
+}
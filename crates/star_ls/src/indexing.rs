@@ -0,0 +1,43 @@
+use crate::{
+    main_loop::Task,
+    workspace_loader::{crawl, Ignore},
+};
+use crossbeam_channel::Sender;
+use rayon::prelude::*;
+use std::{collections::BTreeSet, fs, path::PathBuf};
+
+/// How many files are read before they are handed to the main loop.
+const BATCH_SIZE: usize = 256;
+
+#[derive(Debug)]
+pub(crate) enum IndexingProgress {
+    /// The workspace has been crawled, finding `total` Starlark files.
+    Crawled {
+        packages: BTreeSet<PathBuf>,
+        total: usize,
+    },
+    /// A batch of files has been read.
+    Read(Vec<(PathBuf, String)>),
+    Finished,
+}
+
+/// Crawls the repositories at `roots` and reads all Starlark files in them, reporting
+/// its progress to the main loop, which adds the files to the database. This runs on the
+/// task pool, and reads each batch of files in parallel.
+pub(crate) fn index_workspace(roots: Vec<PathBuf>, ignore: Ignore, sender: Sender<Task>) {
+    let crawl = crawl(&roots, &ignore);
+    let send = |progress| sender.send(Task::Indexing(progress)).unwrap();
+
+    send(IndexingProgress::Crawled {
+        packages: crawl.packages,
+        total: crawl.files.len(),
+    });
+    for batch in crawl.files.chunks(BATCH_SIZE) {
+        let files = batch
+            .par_iter()
+            .filter_map(|path| Some((path.clone(), fs::read_to_string(path).ok()?)))
+            .collect();
+        send(IndexingProgress::Read(files));
+    }
+    send(IndexingProgress::Finished);
+}
@@ -1,18 +1,56 @@
-use lsp_types::{ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind};
+use lsp_types::{
+    CompletionOptions, HoverProviderCapability, OneOf, RenameOptions, ServerCapabilities,
+    SignatureHelpOptions, TextDocumentSyncCapability, TextDocumentSyncKind,
+};
 
+mod config;
+mod dispatch;
 mod errors;
+mod glob;
 mod global_state;
+mod handlers;
+mod indexing;
+mod lsp_utils;
 mod main_loop;
 mod subscriptions;
+mod workspace_loader;
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
-pub use main_loop::main_loop;
+pub use crate::{config::Config, main_loop::main_loop};
 
 pub fn server_capabilities() -> ServerCapabilities {
     ServerCapabilities {
-        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(
+            TextDocumentSyncKind::INCREMENTAL,
+        )),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![".".to_string()]),
+            ..Default::default()
+        }),
+        definition_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
+        document_highlight_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        workspace_symbol_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        rename_provider: Some(OneOf::Right(RenameOptions {
+            prepare_provider: Some(true),
+            work_done_progress_options: Default::default(),
+        })),
+        signature_help_provider: Some(SignatureHelpOptions {
+            trigger_characters: Some(vec!["(".to_string(), ",".to_string()]),
+            ..Default::default()
+        }),
         ..Default::default()
     }
 }
+
+/// Builds the response to the `initialize` request.
+pub fn initialize_result(config: &Config) -> serde_json::Value {
+    let mut capabilities = serde_json::to_value(server_capabilities()).unwrap();
+    // `positionEncoding` is new in LSP 3.17 and not modelled by our version of lsp-types.
+    capabilities["positionEncoding"] = config.position_encoding_kind().into();
+    serde_json::json!({ "capabilities": capabilities })
+}
@@ -0,0 +1,151 @@
+use lsp_server::ErrorCode;
+use lsp_types::{Position, TextDocumentContentChangeEvent};
+use star_syntax::{
+    lines::{Encoding, Lines},
+    TextEdit, TextRange, TextSize,
+};
+use std::fmt;
+
+/// An error a request handler answers with, when it shouldn't be reported as an
+/// `InternalError`.
+#[derive(Debug)]
+pub(crate) struct LspError {
+    pub(crate) code: i32,
+    pub(crate) message: String,
+}
+
+impl LspError {
+    /// The request was valid but couldn't be carried out, like renaming a builtin.
+    pub(crate) fn request_failed(message: String) -> LspError {
+        LspError {
+            code: ErrorCode::RequestFailed as i32,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for LspError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for LspError {}
+
+pub(crate) fn position(lines: &Lines, pos: usize, encoding: Encoding) -> Position {
+    let (line, character) = lines.line_col(pos, encoding);
+    Position { line, character }
+}
+
+pub(crate) fn offset(lines: &Lines, position: Position, encoding: Encoding) -> usize {
+    lines.offset(position.line, position.character, encoding)
+}
+
+/// Applies a batch of content changes to `text`. Changes are applied in order, with the
+/// range of each change referring to the text produced by the previous ones. Returns
+/// the changes as edits of byte ranges, in the same order.
+///
+/// The lines of the text are only indexed again when a change touches a line after an
+/// earlier change, which editors usually avoid by sending changes from the bottom up.
+pub(crate) fn apply_document_changes(
+    text: &mut String,
+    changes: Vec<TextDocumentContentChangeEvent>,
+    encoding: Encoding,
+) -> Vec<TextEdit> {
+    let mut edits = Vec::with_capacity(changes.len());
+    let mut lines = Lines::new(text);
+    // The lines before this one haven't changed since `lines` was built.
+    let mut valid_lines = u32::MAX;
+    for change in changes {
+        match change.range {
+            Some(range) => {
+                if range.end.line >= valid_lines {
+                    lines = Lines::new(text);
+                    valid_lines = u32::MAX;
+                }
+                let start = char_boundary(text, offset(&lines, range.start, encoding));
+                let end = char_boundary(text, offset(&lines, range.end, encoding)).max(start);
+                text.replace_range(start..end, &change.text);
+                valid_lines = valid_lines.min(range.start.line);
+                edits.push(TextEdit::new(text_range(start, end), change.text));
+            }
+            None => {
+                edits.push(TextEdit::new(
+                    text_range(0, text.len()),
+                    change.text.clone(),
+                ));
+                *text = change.text;
+                lines = Lines::new(text);
+                valid_lines = u32::MAX;
+            }
+        }
+    }
+    edits
+}
+
+fn text_range(start: usize, end: usize) -> TextRange {
+    TextRange::new(
+        TextSize::try_from(start).unwrap(),
+        TextSize::try_from(end).unwrap(),
+    )
+}
+
+/// Moves `offset` back to the start of the character it falls in, if any.
+fn char_boundary(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::Range;
+
+    fn change(start: (u32, u32), end: (u32, u32), text: &str) -> TextDocumentContentChangeEvent {
+        TextDocumentContentChangeEvent {
+            range: Some(Range {
+                start: Position::new(start.0, start.1),
+                end: Position::new(end.0, end.1),
+            }),
+            range_length: None,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn changes_apply_in_order() {
+        let mut text = "x = 1\ny = 2\n".to_string();
+        let changes = vec![
+            change((1, 4), (1, 5), "3"),
+            change((0, 0), (0, 1), "long_name"),
+            change((1, 0), (1, 1), "z"),
+        ];
+        let edits = apply_document_changes(&mut text, changes, Encoding::Utf16);
+        assert_eq!(text, "long_name = 1\nz = 3\n");
+
+        // The edits turn the original text into the same one.
+        let mut edited = "x = 1\ny = 2\n".to_string();
+        for edit in &edits {
+            edit.apply(&mut edited);
+        }
+        assert_eq!(edited, text);
+        assert_eq!(
+            edits[1],
+            TextEdit::new(text_range(0, 1), "long_name".to_string())
+        );
+    }
+
+    #[test]
+    fn columns_inside_characters() {
+        let mut text = "😀x".to_string();
+        apply_document_changes(
+            &mut text,
+            vec![change((0, 1), (0, 3), "y")],
+            Encoding::Utf16,
+        );
+        assert_eq!(text, "y");
+    }
+}
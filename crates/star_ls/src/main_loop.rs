@@ -1,12 +1,31 @@
-use crate::{global_state::GlobalState, Result};
+use crate::{
+    dispatch::{NotificationDispatcher, RequestDispatcher},
+    global_state::{GlobalState, Progress},
+    handlers,
+    indexing::{self, IndexingProgress},
+    lsp_utils::{apply_document_changes, position},
+    workspace_loader::{self, find_buck_root, find_root, is_starlark_file, Ignore},
+    Config, Result,
+};
 use crossbeam_channel::select;
-use lsp_server::{Connection, Message, Notification, Request};
-use lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range, Url};
-use star_db::{lines, parse};
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{self, Notification as _},
+    request, Diagnostic, DiagnosticSeverity, FileChangeType, NumberOrString, Range, Url,
+};
+use star_db::{lines, parse, resolve_loads, LoadError, BUILD_FILES, WORKSPACE_FILES};
+use std::{
+    fs, mem,
+    path::{Path, PathBuf},
+};
 
 #[derive(Debug)]
 pub enum Task {
     Diagnostics(Vec<(Url, Vec<Diagnostic>)>),
+    /// Files loaded by open files that haven't been read yet.
+    ReadFiles(Vec<PathBuf>),
+    Response(Response),
+    Indexing(IndexingProgress),
 }
 
 #[derive(Debug)]
@@ -15,27 +34,206 @@ enum Event {
     Task(Task),
 }
 
-pub fn main_loop(connection: Connection) -> Result<()> {
-    GlobalState::new(connection).run()
+pub fn main_loop(connection: Connection, config: Config) -> Result<()> {
+    GlobalState::new(connection, config).run()
 }
 
 impl GlobalState {
-    fn did_change_text_document(&mut self, mut params: lsp_types::DidChangeTextDocumentParams) {
-        if let Some(change) = params.content_changes.pop() {
-            self.changes.push((params.text_document.uri, change.text));
-        }
+    fn did_change_text_document(&mut self, params: lsp_types::DidChangeTextDocumentParams) {
+        let url = params.text_document.uri;
+
+        // Edits apply on top of changes that haven't been written to the database yet.
+        let text = match self
+            .changes
+            .iter()
+            .rev()
+            .find(|(changed, _, _)| *changed == url)
+        {
+            Some((_, text, _)) => Some(text.clone()),
+            None => url
+                .to_file_path()
+                .ok()
+                .and_then(|path| self.db.file_text(&path)),
+        };
+        let mut text = match text {
+            Some(text) => text,
+            None => return,
+        };
+
+        let edits = apply_document_changes(
+            &mut text,
+            params.content_changes,
+            self.config.position_encoding,
+        );
+        self.changes.push((url, text, Some(edits)));
     }
 
     fn did_open_text_document(&mut self, params: lsp_types::DidOpenTextDocumentParams) {
-        self.changes
-            .push((params.text_document.uri.clone(), params.text_document.text));
+        self.changes.push((
+            params.text_document.uri.clone(),
+            params.text_document.text,
+            None,
+        ));
         self.subscriptions.add(params.text_document.uri);
     }
 
     fn did_close_text_document(&mut self, params: lsp_types::DidCloseTextDocumentParams) {
+        if let Ok(path) = params.text_document.uri.to_file_path() {
+            self.db.remove_overlay(&path);
+            self.vfs_changed = true;
+        }
         self.subscriptions.remove(&params.text_document.uri);
     }
 
+    fn did_change_watched_files(&mut self, params: lsp_types::DidChangeWatchedFilesParams) {
+        let mut layout_changed = false;
+        let mut created = Vec::new();
+        for change in params.changes {
+            let path = match change.uri.to_file_path() {
+                Ok(path) => path,
+                Err(_) => continue,
+            };
+            let name = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default();
+            layout_changed |= WORKSPACE_FILES.contains(&name);
+            if BUILD_FILES.contains(&name) {
+                self.update_package(&path);
+            }
+            let indexed = matches!(&self.ignore, Some(ignore) if !ignore.is_ignored(&path));
+            if change.typ == FileChangeType::CREATED && is_starlark_file(name) && indexed {
+                if let Ok(text) = fs::read_to_string(&path) {
+                    created.push((path.clone(), text));
+                }
+            }
+            self.db.reload_file(&path);
+            self.vfs_changed = true;
+        }
+        if !created.is_empty() {
+            self.db.add_files(created);
+        }
+        if layout_changed {
+            self.load_workspace();
+        }
+    }
+
+    /// Adds or removes the package of a BUILD file that was created or deleted.
+    fn update_package(&mut self, build_file: &Path) {
+        let workspace = match self.db.workspace() {
+            Some(workspace) => workspace,
+            None => return,
+        };
+        let dir = match build_file.parent() {
+            Some(dir) => dir,
+            None => return,
+        };
+        let mut packages = workspace.packages(&self.db.db).clone();
+        let changed = if BUILD_FILES.iter().any(|name| dir.join(name).is_file()) {
+            packages.insert(dir.to_path_buf())
+        } else {
+            packages.remove(dir)
+        };
+        if changed {
+            self.db.set_packages(packages);
+        }
+    }
+
+    /// Asks the client to tell us about Starlark and Bazel files changed outside the
+    /// editor.
+    fn register_file_watchers(&mut self) {
+        let patterns = ["**/*.{bzl,star,bazel}", "**/{BUILD,WORKSPACE}"];
+        let options = lsp_types::DidChangeWatchedFilesRegistrationOptions {
+            watchers: patterns
+                .iter()
+                .map(|pattern| lsp_types::FileSystemWatcher {
+                    glob_pattern: pattern.to_string().into(),
+                    kind: None,
+                })
+                .collect(),
+        };
+        self.send_request::<request::RegisterCapability>(lsp_types::RegistrationParams {
+            registrations: vec![lsp_types::Registration {
+                id: notification::DidChangeWatchedFiles::METHOD.to_string(),
+                method: notification::DidChangeWatchedFiles::METHOD.to_string(),
+                register_options: Some(serde_json::to_value(options).unwrap()),
+            }],
+        });
+    }
+
+    /// Finds the workspace containing the directory the client opened, records its
+    /// repositories in the database and starts indexing it.
+    fn load_workspace(&mut self) {
+        let buck_root = self.config.root_path.as_deref().and_then(find_buck_root);
+        self.db.set_buck_root(buck_root);
+        let root = match self.config.root_path.as_deref().and_then(find_root) {
+            Some(root) => root,
+            None => return,
+        };
+        let layout = workspace_loader::load_workspace(root, &self.config.override_repositories);
+        let roots = layout.roots();
+        let ignore = Ignore::new(&layout.root, &self.config.excludes);
+        self.db.set_workspace(layout.root, layout.repositories);
+        self.ignore = Some(ignore.clone());
+        self.task_pool
+            .spawn_with_sender(move |sender| indexing::index_workspace(roots, ignore, sender));
+    }
+
+    fn on_indexing_progress(&mut self, progress: IndexingProgress) {
+        match progress {
+            IndexingProgress::Crawled { packages, total } => {
+                self.db.set_packages(packages);
+                self.vfs_changed = true;
+                self.indexing = Some((0, total));
+                self.report_progress(
+                    "Indexing",
+                    Progress::Begin,
+                    Some(format!("0/{}", total)),
+                    Some(0.0),
+                );
+            }
+            IndexingProgress::Read(files) => {
+                let (done, total) = match &mut self.indexing {
+                    Some((done, total)) => {
+                        *done += files.len();
+                        (*done, *total)
+                    }
+                    None => return,
+                };
+                self.db.add_files(files);
+                self.report_progress(
+                    "Indexing",
+                    Progress::Report,
+                    Some(format!("{}/{}", done, total)),
+                    Some(done as f64 / total.max(1) as f64),
+                );
+            }
+            IndexingProgress::Finished => {
+                self.indexing = None;
+                self.vfs_changed = true;
+                self.report_progress("Indexing", Progress::End, None, None);
+                self.build_workspace_index();
+            }
+        }
+    }
+
+    /// Builds the symbol tables of the workspace in the background, so that they are
+    /// ready when a request needs them.
+    fn build_workspace_index(&self) {
+        let workspace = match self.db.workspace() {
+            Some(workspace) => workspace,
+            None => return,
+        };
+        let mut snap = self.db.snapshot();
+        self.task_pool.spawn_with_sender(move |_| {
+            let snap = std::panic::AssertUnwindSafe(&mut snap);
+            // If the database changes in the meantime, the next request builds them.
+            let _ = salsa::Cancelled::catch(|| {
+                star_hir::workspace_index(&*snap.db, workspace);
+            });
+        });
+    }
+
     fn recv(&self) -> Option<Event> {
         select! {
             recv(self.connection.receiver) -> msg => {
@@ -51,6 +249,11 @@ impl GlobalState {
         // let params: InitializeParams = serde_json::from_value(raw_params).unwrap();
         eprintln!("starting example main loop");
 
+        self.load_workspace();
+        if self.config.watch_files {
+            self.register_file_watchers();
+        }
+
         while let Some(event) = self.recv() {
             if let Event::Lsp(Message::Request(ref req)) = event {
                 if self.connection.handle_shutdown(req)? {
@@ -65,27 +268,11 @@ impl GlobalState {
     fn handle_event(&mut self, event: Event) -> Result<()> {
         match event {
             Event::Lsp(msg) => match msg {
-                Message::Request(req) => {
-                    eprintln!("got request: {:?}", req);
-                }
+                Message::Request(req) => self.on_request(req),
                 Message::Response(resp) => {
                     eprintln!("got response: {:?}", resp);
                 }
-                Message::Notification(not) => {
-                    if let Some(params) =
-                        cast_notification::<lsp_types::notification::DidChangeTextDocument>(&not)
-                    {
-                        self.did_change_text_document(params);
-                    } else if let Some(params) =
-                        cast_notification::<lsp_types::notification::DidOpenTextDocument>(&not)
-                    {
-                        self.did_open_text_document(params);
-                    } else if let Some(params) =
-                        cast_notification::<lsp_types::notification::DidCloseTextDocument>(&not)
-                    {
-                        self.did_close_text_document(params);
-                    }
-                }
+                Message::Notification(not) => self.on_notification(not),
             },
             Event::Task(task) => {
                 self.handle_task(task);
@@ -94,12 +281,18 @@ impl GlobalState {
 
         // Check changed files.
         let changes = self.take_changes();
-        let content_changed = !changes.is_empty();
+        let vfs_changed = mem::take(&mut self.vfs_changed);
+        let content_changed = !changes.is_empty() || vfs_changed;
 
         if !changes.is_empty() {
             self.db.cancel();
-            for (url, text) in changes {
-                self.db.set_file_text(url.to_string(), text);
+            for (url, text, edits) in changes {
+                if let Ok(path) = url.to_file_path() {
+                    match edits {
+                        Some(edits) => self.db.edit_overlay(&path, text, &edits),
+                        None => self.db.set_overlay(&path, text),
+                    }
+                }
             }
         }
 
@@ -124,6 +317,30 @@ impl GlobalState {
         Ok(())
     }
 
+    fn on_request(&mut self, req: Request) {
+        RequestDispatcher::new(req, self)
+            .on::<request::GotoDefinition>(handlers::goto_definition)
+            .on::<request::Completion>(handlers::completion)
+            .on::<request::HoverRequest>(handlers::hover)
+            .on::<request::SignatureHelpRequest>(handlers::signature_help)
+            .on::<request::References>(handlers::references)
+            .on::<request::DocumentHighlightRequest>(handlers::document_highlight)
+            .on::<request::PrepareRenameRequest>(handlers::prepare_rename)
+            .on::<request::Rename>(handlers::rename)
+            .on::<request::DocumentSymbolRequest>(handlers::document_symbol)
+            .on::<request::WorkspaceSymbolRequest>(handlers::workspace_symbol)
+            .finish();
+    }
+
+    fn on_notification(&mut self, not: Notification) {
+        NotificationDispatcher::new(not, self)
+            .on::<notification::DidOpenTextDocument>(GlobalState::did_open_text_document)
+            .on::<notification::DidChangeTextDocument>(GlobalState::did_change_text_document)
+            .on::<notification::DidCloseTextDocument>(GlobalState::did_close_text_document)
+            .on::<notification::DidChangeWatchedFiles>(GlobalState::did_change_watched_files)
+            .finish();
+    }
+
     fn handle_task(&mut self, task: Task) {
         match task {
             Task::Diagnostics(diagnostics) => {
@@ -131,38 +348,37 @@ impl GlobalState {
                     self.process_incoming_diagnostics(url, file_diagnostics);
                 }
             }
+            Task::ReadFiles(paths) => {
+                self.db.read_files(paths);
+                self.vfs_changed = true;
+            }
+            Task::Response(response) => self.respond(response),
+            Task::Indexing(progress) => self.on_indexing_progress(progress),
         }
     }
 
     fn update_diagnostics(&self) {
         let subscriptions: Vec<Url> = self.subscriptions.iter().cloned().collect();
 
+        let encoding = self.config.position_encoding;
         let mut snap = self.db.snapshot();
         self.task_pool.spawn_with_sender(move |sender| {
             let snap = std::panic::AssertUnwindSafe(&mut snap);
-            let diagnostics = match salsa::Cancelled::catch(|| {
-                subscriptions
+            let result = salsa::Cancelled::catch(|| {
+                let mut unread = Vec::new();
+                let diagnostics = subscriptions
                     .into_iter()
                     .filter_map(|url| {
-                        let file = {
-                            let files = snap.files.lock().unwrap();
-                            match files.get(url.as_str()).cloned() {
-                                Some(file) => file,
-                                None => return None,
-                            }
-                        };
+                        let file = snap.file(&url.to_file_path().ok()?)?;
                         let lines = lines(&*snap.db, file);
                         let parse = parse(&*snap.db, file);
 
-                        let diagnostics = parse
+                        let mut diagnostics = parse
                             .errors()
                             .iter()
                             .cloned()
                             .map(|star_syntax::Diagnostic { message, pos }| {
-                                let pos = {
-                                    let (line, character) = lines.line_num_and_col(pos);
-                                    Position { line, character }
-                                };
+                                let pos = position(&lines, pos, encoding);
                                 Diagnostic {
                                     severity: Some(DiagnosticSeverity::ERROR),
                                     range: Range {
@@ -174,43 +390,61 @@ impl GlobalState {
                                 }
                             })
                             .collect::<Vec<_>>();
+                        diagnostics.extend(resolve_loads(&*snap.db, file).iter().filter_map(
+                            |load| {
+                                let err = load.resolution.as_ref().err()?;
+                                if let LoadError::NotFound(path) = err {
+                                    if snap.file(path).is_none() {
+                                        unread.push(path.clone());
+                                    }
+                                }
+                                Some(Diagnostic {
+                                    severity: Some(DiagnosticSeverity::ERROR),
+                                    range: Range {
+                                        start: position(
+                                            &lines,
+                                            load.range.start().into(),
+                                            encoding,
+                                        ),
+                                        end: position(&lines, load.range.end().into(), encoding),
+                                    },
+                                    message: format!("cannot load `{}`: {}", load.module, err),
+                                    ..Default::default()
+                                })
+                            },
+                        ));
+                        diagnostics.extend(star_ide::diagnostics(&*snap.db, file).into_iter().map(
+                            |diagnostic| Diagnostic {
+                                severity: Some(DiagnosticSeverity::ERROR),
+                                range: Range {
+                                    start: position(
+                                        &lines,
+                                        diagnostic.range.start().into(),
+                                        encoding,
+                                    ),
+                                    end: position(&lines, diagnostic.range.end().into(), encoding),
+                                },
+                                code: Some(NumberOrString::String(diagnostic.code.to_string())),
+                                message: diagnostic.message,
+                                ..Default::default()
+                            },
+                        ));
                         Some((url, diagnostics))
                     })
-                    .collect::<Vec<_>>()
-            }) {
-                Ok(diagnostics) => diagnostics,
+                    .collect::<Vec<_>>();
+                (diagnostics, unread)
+            });
+            let (diagnostics, unread) = match result {
+                Ok(result) => result,
                 Err(_) => {
                     return;
                 }
             };
 
             sender.send(Task::Diagnostics(diagnostics)).unwrap();
+            if !unread.is_empty() {
+                sender.send(Task::ReadFiles(unread)).unwrap();
+            }
         });
     }
 }
-
-fn cast_request<R>(req: &Request) -> Option<R::Params>
-where
-    R: lsp_types::request::Request,
-    R::Params: serde::de::DeserializeOwned,
-{
-    if req.method == R::METHOD {
-        let params = serde_json::from_value(req.params.clone()).unwrap();
-        Some(params)
-    } else {
-        None
-    }
-}
-
-fn cast_notification<R>(not: &Notification) -> Option<R::Params>
-where
-    R: lsp_types::notification::Notification,
-    R::Params: serde::de::DeserializeOwned,
-{
-    if not.method == R::METHOD {
-        let params = serde_json::from_value(not.params.clone()).unwrap();
-        Some(params)
-    } else {
-        None
-    }
-}
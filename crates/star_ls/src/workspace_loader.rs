@@ -0,0 +1,151 @@
+use crate::glob::Glob;
+use star_db::{local_repositories, BUILD_FILES, WORKSPACE_FILES};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The repositories of a workspace, as declared in its WORKSPACE or MODULE.bazel file.
+pub(crate) struct WorkspaceLayout {
+    pub(crate) root: PathBuf,
+    pub(crate) repositories: BTreeMap<String, PathBuf>,
+}
+
+impl WorkspaceLayout {
+    /// The roots of all repositories, starting with the main one.
+    pub(crate) fn roots(&self) -> Vec<PathBuf> {
+        let mut roots = vec![self.root.clone()];
+        roots.extend(self.repositories.values().cloned());
+        roots
+    }
+}
+
+/// Finds the root of the repository containing `path`: the closest directory, starting
+/// with `path` itself, that has a WORKSPACE or MODULE.bazel file.
+pub(crate) fn find_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| WORKSPACE_FILES.iter().any(|name| dir.join(name).is_file()))
+        .map(Path::to_path_buf)
+}
+
+/// Finds the root of the Buck project containing `path`: the closest directory, starting
+/// with `path` itself, that has a `.buckconfig` file.
+pub(crate) fn find_buck_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.join(".buckconfig").is_file())
+        .map(Path::to_path_buf)
+}
+
+/// Reads the repositories declared in the workspace rooted at `root` and applies
+/// `overrides` on top of them.
+pub(crate) fn load_workspace(
+    root: PathBuf,
+    overrides: &BTreeMap<String, PathBuf>,
+) -> WorkspaceLayout {
+    let mut repositories = BTreeMap::new();
+    for name in WORKSPACE_FILES {
+        if let Ok(text) = fs::read_to_string(root.join(name)) {
+            repositories.extend(local_repositories(&text, &root));
+        }
+    }
+    repositories.extend(
+        overrides
+            .iter()
+            .map(|(name, path)| (name.clone(), root.join(path))),
+    );
+    WorkspaceLayout { root, repositories }
+}
+
+/// The paths to skip while crawling a workspace: the directories listed in its
+/// `.bazelignore` file, and those matching any of the configured exclude patterns.
+#[derive(Clone, Debug)]
+pub(crate) struct Ignore {
+    root: PathBuf,
+    dirs: Vec<PathBuf>,
+    excludes: Vec<Glob>,
+}
+
+impl Ignore {
+    pub(crate) fn new(root: &Path, excludes: &[String]) -> Ignore {
+        let bazelignore = fs::read_to_string(root.join(".bazelignore")).unwrap_or_default();
+        let dirs = bazelignore
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| root.join(line.trim_end_matches('/')))
+            .collect();
+        Ignore {
+            root: root.to_path_buf(),
+            dirs,
+            excludes: excludes.iter().map(|pattern| Glob::new(pattern)).collect(),
+        }
+    }
+
+    /// Checks whether `path` should be skipped. Exclude patterns are matched against
+    /// paths relative to the workspace root, or absolute paths outside of it.
+    pub(crate) fn is_ignored(&self, path: &Path) -> bool {
+        if self.dirs.iter().any(|dir| path.starts_with(dir)) {
+            return true;
+        }
+        let path = path.strip_prefix(&self.root).unwrap_or(path);
+        let path = path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        self.excludes.iter().any(|glob| glob.matches(&path))
+    }
+}
+
+/// The packages and Starlark files found by crawling a workspace.
+#[derive(Default)]
+pub(crate) struct Crawl {
+    pub(crate) packages: BTreeSet<PathBuf>,
+    pub(crate) files: Vec<PathBuf>,
+}
+
+/// Walks the given repository roots. Hidden directories and ignored paths are skipped,
+/// and symlinks aren't followed, which also skips Bazel's `bazel-*` convenience links.
+pub(crate) fn crawl(roots: &[PathBuf], ignore: &Ignore) -> Crawl {
+    let mut crawl = Crawl::default();
+    for root in roots {
+        walk(root, ignore, &mut crawl);
+    }
+    crawl
+}
+
+fn walk(dir: &Path, ignore: &Ignore, crawl: &mut Crawl) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(_) => continue,
+        };
+        let path = entry.path();
+        if ignore.is_ignored(&path) {
+            continue;
+        }
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if file_type.is_dir() && !name.starts_with('.') {
+            walk(&path, ignore, crawl);
+        } else if file_type.is_file() && is_starlark_file(&name) {
+            if BUILD_FILES.contains(&&*name) {
+                crawl.packages.insert(dir.to_path_buf());
+            }
+            crawl.files.push(path);
+        }
+    }
+}
+
+/// Checks whether a file called `name` contains Starlark.
+pub(crate) fn is_starlark_file(name: &str) -> bool {
+    name.ends_with(".bzl")
+        || name.ends_with(".star")
+        || BUILD_FILES.contains(&name)
+        || WORKSPACE_FILES.contains(&name)
+}
@@ -205,6 +205,7 @@ pub enum Stmt {
     DefStmt(DefStmt),
     IfStmt(IfStmt),
     ForStmt(ForStmt),
+    WhileStmt(WhileStmt),
     SimpleStmt(SimpleStmt),
 }
 
@@ -214,6 +215,7 @@ impl fmt::Display for Stmt {
             Stmt::DefStmt(stmt) => fmt::Display::fmt(stmt, f),
             Stmt::IfStmt(stmt) => fmt::Display::fmt(stmt, f),
             Stmt::ForStmt(stmt) => fmt::Display::fmt(stmt, f),
+            Stmt::WhileStmt(stmt) => fmt::Display::fmt(stmt, f),
             Stmt::SimpleStmt(stmt) => fmt::Display::fmt(stmt, f),
         }
     }
@@ -224,7 +226,10 @@ impl AstNode for Stmt {
     where
         Self: Sized,
     {
-        matches!(kind, DEF_STMT | IF_STMT | FOR_STMT | SIMPLE_STMT)
+        matches!(
+            kind,
+            DEF_STMT | IF_STMT | FOR_STMT | WHILE_STMT | SIMPLE_STMT
+        )
     }
 
     fn cast(syntax: SyntaxNode) -> Option<Self>
@@ -236,6 +241,7 @@ impl AstNode for Stmt {
                 DEF_STMT => Stmt::DefStmt(DefStmt { syntax }),
                 IF_STMT => Stmt::IfStmt(IfStmt { syntax }),
                 FOR_STMT => Stmt::ForStmt(ForStmt { syntax }),
+                WHILE_STMT => Stmt::WhileStmt(WhileStmt { syntax }),
                 SIMPLE_STMT => Stmt::SimpleStmt(SimpleStmt { syntax }),
                 _ => unreachable!(),
             })
@@ -249,11 +255,17 @@ impl AstNode for Stmt {
             Stmt::DefStmt(stmt) => stmt.syntax(),
             Stmt::IfStmt(stmt) => stmt.syntax(),
             Stmt::ForStmt(stmt) => stmt.syntax(),
+            Stmt::WhileStmt(stmt) => stmt.syntax(),
             Stmt::SimpleStmt(stmt) => stmt.syntax(),
         }
     }
 }
 
+def_ast_node!(File, FILE);
+impl File {
+    access_children!(Stmt, statements);
+}
+
 def_ast_node!(DefStmt, DEF_STMT);
 impl DefStmt {
     pub fn name(&self) -> Option<Ident> {
@@ -262,6 +274,11 @@ impl DefStmt {
 
     access_nth_child!(Parameters, parameters);
     access_nth_child!(Suite, suite);
+
+    /// The declared return type, e.g. `str` in `def f() -> str:`.
+    pub fn return_type(&self) -> Option<Expr> {
+        child::<ReturnType>(self.syntax())?.ty()
+    }
 }
 
 def_ast_node!(IfStmt, IF_STMT);
@@ -272,7 +289,7 @@ impl IfStmt {
     pub fn elif_conditions(&self) -> Vec<Expr> {
         self.syntax()
             .children_with_tokens()
-            .skip_while(|el| el.as_token().map(SyntaxToken::kind) != Some(T![if]))
+            .skip_while(|el| el.as_token().map(SyntaxToken::kind) != Some(T![elif]))
             .take_while(|el| el.as_token().map(SyntaxToken::kind) != Some(T![else]))
             .filter_map(|el| el.into_node())
             .filter_map(Expr::cast)
@@ -282,7 +299,7 @@ impl IfStmt {
     pub fn elif_suites(&self) -> Vec<Suite> {
         self.syntax()
             .children_with_tokens()
-            .skip_while(|el| el.as_token().map(SyntaxToken::kind) != Some(T![if]))
+            .skip_while(|el| el.as_token().map(SyntaxToken::kind) != Some(T![elif]))
             .take_while(|el| el.as_token().map(SyntaxToken::kind) != Some(T![else]))
             .filter_map(|el| el.into_node())
             .filter_map(Suite::cast)
@@ -301,7 +318,7 @@ impl IfStmt {
 def_ast_node!(ForStmt, FOR_STMT);
 impl ForStmt {
     pub fn loop_variables(&self) -> Vec<Expr> {
-        children_until_token(self.syntax(), T![in]).collect()
+        loop_variables(self.syntax())
     }
 
     pub fn expr(&self) -> Option<Expr> {
@@ -311,6 +328,12 @@ impl ForStmt {
     access_nth_child!(Suite, suite);
 }
 
+def_ast_node!(WhileStmt, WHILE_STMT);
+impl WhileStmt {
+    access_nth_child!(Expr, condition);
+    access_nth_child!(Suite, suite);
+}
+
 def_ast_node!(SimpleStmt, SIMPLE_STMT);
 impl SimpleStmt {
     access_children!(SmallStmt, statements);
@@ -362,11 +385,14 @@ impl AstNode for SmallStmt {
                 | LAMBDA_EXPR
                 | DOT_EXPR
                 | CALL_EXPR
+                | INDEX_EXPR
                 | SLICE_EXPR
                 | LIST_EXPR
                 | DICT_EXPR
+                | SET_EXPR
                 | LIST_COMP
                 | DICT_COMP
+                | LITERAL
         )
     }
 
@@ -377,13 +403,14 @@ impl AstNode for SmallStmt {
         if Self::can_cast(syntax.kind()) {
             Some(match syntax.kind() {
                 RETURN_STMT => SmallStmt::ReturnStmt(ReturnStmt { syntax }),
-                BREAK_STMT => SmallStmt::ReturnStmt(ReturnStmt { syntax }),
+                BREAK_STMT => SmallStmt::BreakStmt(BreakStmt { syntax }),
                 CONTINUE_STMT => SmallStmt::ContinueStmt(ContinueStmt { syntax }),
                 PASS_STMT => SmallStmt::PassStmt(PassStmt { syntax }),
                 ASSIGN_STMT => SmallStmt::AssignStmt(AssignStmt { syntax }),
                 LOAD_STMT => SmallStmt::LoadStmt(LoadStmt { syntax }),
                 IF_EXPR | UNARY_EXPR | BINARY_EXPR | TUPLE_EXPR | LAMBDA_EXPR | DOT_EXPR
-                | CALL_EXPR | SLICE_EXPR | LIST_EXPR | DICT_EXPR | LIST_COMP | DICT_COMP => {
+                | CALL_EXPR | INDEX_EXPR | SLICE_EXPR | LIST_EXPR | DICT_EXPR | SET_EXPR
+                | LIST_COMP | DICT_COMP | LITERAL => {
                     SmallStmt::ExprStmt(Expr::cast(syntax).unwrap())
                 }
                 _ => unreachable!(),
@@ -424,10 +451,50 @@ def_ast_node!(AssignStmt, ASSIGN_STMT);
 impl AssignStmt {
     access_nth_child!(Expr, lhs);
     access_nth_child!(Expr, rhs, 1);
+
+    /// The declared type of the assigned name, e.g. `int` in `x: int = 1`.
+    pub fn type_expr(&self) -> Option<Expr> {
+        child::<TypeAnnotation>(self.syntax())?.ty()
+    }
 }
 
 def_ast_node!(LoadStmt, LOAD_STMT);
-impl LoadStmt {}
+impl LoadStmt {
+    /// The module being loaded from, e.g. `"//foo:bar.bzl"`.
+    pub fn module(&self) -> Option<String> {
+        child_token(self.syntax())
+    }
+
+    /// Symbols loaded under their own name, e.g. `"sym"`.
+    pub fn symbols(&self) -> Vec<LoadSymbol> {
+        children(self.syntax()).collect()
+    }
+
+    /// Symbols bound to a different local name, e.g. `alias = "other"`.
+    pub fn aliases(&self) -> Vec<LoadAlias> {
+        children(self.syntax()).collect()
+    }
+}
+
+def_ast_node!(LoadSymbol, LOAD_SYMBOL);
+impl LoadSymbol {
+    pub fn name(&self) -> Option<String> {
+        child_token(self.syntax())
+    }
+}
+
+def_ast_node!(LoadAlias, LOAD_ALIAS);
+impl LoadAlias {
+    /// The name bound in the loading file.
+    pub fn alias(&self) -> Option<Ident> {
+        child_token(self.syntax())
+    }
+
+    /// The name of the symbol in the loaded module.
+    pub fn name(&self) -> Option<String> {
+        child_token(self.syntax())
+    }
+}
 
 pub enum Expr {
     IfExpr(IfExpr),
@@ -437,9 +504,11 @@ pub enum Expr {
     LambdaExpr(LambdaExpr),
     DotExpr(DotExpr),
     CallExpr(CallExpr),
+    IndexExpr(IndexExpr),
     SliceExpr(SliceExpr),
     ListExpr(ListExpr),
     DictExpr(DictExpr),
+    SetExpr(SetExpr),
     ListComp(ListComp),
     DictComp(DictComp),
     Literal(Literal),
@@ -455,9 +524,11 @@ impl fmt::Display for Expr {
             Expr::LambdaExpr(expr) => fmt::Display::fmt(expr, f),
             Expr::DotExpr(expr) => fmt::Display::fmt(expr, f),
             Expr::CallExpr(expr) => fmt::Display::fmt(expr, f),
+            Expr::IndexExpr(expr) => fmt::Display::fmt(expr, f),
             Expr::SliceExpr(expr) => fmt::Display::fmt(expr, f),
             Expr::ListExpr(expr) => fmt::Display::fmt(expr, f),
             Expr::DictExpr(expr) => fmt::Display::fmt(expr, f),
+            Expr::SetExpr(expr) => fmt::Display::fmt(expr, f),
             Expr::ListComp(expr) => fmt::Display::fmt(expr, f),
             Expr::DictComp(expr) => fmt::Display::fmt(expr, f),
             Expr::Literal(expr) => fmt::Display::fmt(expr, f),
@@ -479,9 +550,11 @@ impl AstNode for Expr {
                 | LAMBDA_EXPR
                 | DOT_EXPR
                 | CALL_EXPR
+                | INDEX_EXPR
                 | SLICE_EXPR
                 | LIST_EXPR
                 | DICT_EXPR
+                | SET_EXPR
                 | LIST_COMP
                 | DICT_COMP
                 | LITERAL
@@ -501,12 +574,14 @@ impl AstNode for Expr {
                 LAMBDA_EXPR => Expr::LambdaExpr(LambdaExpr { syntax }),
                 DOT_EXPR => Expr::DotExpr(DotExpr { syntax }),
                 CALL_EXPR => Expr::CallExpr(CallExpr { syntax }),
+                INDEX_EXPR => Expr::IndexExpr(IndexExpr { syntax }),
                 SLICE_EXPR => Expr::SliceExpr(SliceExpr { syntax }),
                 LIST_EXPR => Expr::ListExpr(ListExpr { syntax }),
                 LIST_COMP => Expr::ListComp(ListComp { syntax }),
                 DICT_EXPR => Expr::DictExpr(DictExpr { syntax }),
+                SET_EXPR => Expr::SetExpr(SetExpr { syntax }),
                 DICT_COMP => Expr::DictComp(DictComp { syntax }),
-                // LITERAL => Expr::Literal(Literal { syntax }),
+                LITERAL => Expr::Literal(Literal { syntax }),
                 _ => unreachable!(),
             })
         } else {
@@ -523,9 +598,11 @@ impl AstNode for Expr {
             Expr::LambdaExpr(expr) => expr.syntax(),
             Expr::DotExpr(expr) => expr.syntax(),
             Expr::CallExpr(expr) => expr.syntax(),
+            Expr::IndexExpr(expr) => expr.syntax(),
             Expr::SliceExpr(expr) => expr.syntax(),
             Expr::ListExpr(expr) => expr.syntax(),
             Expr::DictExpr(expr) => expr.syntax(),
+            Expr::SetExpr(expr) => expr.syntax(),
             Expr::ListComp(expr) => expr.syntax(),
             Expr::DictComp(expr) => expr.syntax(),
             Expr::Literal(expr) => expr.syntax(),
@@ -578,7 +655,7 @@ impl BinaryExpr {
             .find_map(|token| {
                 let op = match token.kind() {
                     T![or] => BinaryOp::Or,
-                    T![and] => BinaryOp::Add,
+                    T![and] => BinaryOp::And,
                     T![==] => BinaryOp::Eq,
                     T![!=] => BinaryOp::Ne,
                     T![<] => BinaryOp::Lt,
@@ -588,6 +665,7 @@ impl BinaryExpr {
                     T![in] => BinaryOp::In,
                     T![not] => BinaryOp::NotIn,
                     T![|] => BinaryOp::BitOr,
+                    T![^] => BinaryOp::BitXor,
                     T![&] => BinaryOp::BitAnd,
                     T![<<] => BinaryOp::BitShiftLeft,
                     T![>>] => BinaryOp::BitShiftRight,
@@ -622,7 +700,10 @@ impl TupleExpr {
 def_ast_node!(LambdaExpr, LAMBDA_EXPR);
 impl LambdaExpr {
     access_nth_child!(Parameters, parameters);
-    access_nth_child!(Suite, suite);
+
+    pub fn body(&self) -> Option<Expr> {
+        child_after_token(self.syntax(), T![:])
+    }
 }
 
 def_ast_node!(DotExpr, DOT_EXPR);
@@ -643,12 +724,43 @@ impl CallExpr {
     }
 }
 
+def_ast_node!(IndexExpr, INDEX_EXPR);
+impl IndexExpr {
+    access_nth_child!(Expr, expr);
+    access_nth_child!(Expr, index, 1);
+}
+
 def_ast_node!(SliceExpr, SLICE_EXPR);
 impl SliceExpr {
     access_nth_child!(Expr, expr);
-    access_nth_child!(Expr, start, 1);
-    access_nth_child!(Expr, end, 2);
-    access_nth_child!(Expr, step, 3);
+
+    pub fn start(&self) -> Option<Expr> {
+        self.slice_part(0)
+    }
+
+    pub fn end(&self) -> Option<Expr> {
+        self.slice_part(1)
+    }
+
+    pub fn step(&self) -> Option<Expr> {
+        self.slice_part(2)
+    }
+
+    /// Any part of `[start:end:step]` may be omitted, so parts are located by the
+    /// number of ':' tokens preceding them rather than by their position.
+    fn slice_part(&self, colons: usize) -> Option<Expr> {
+        self.syntax()
+            .children_with_tokens()
+            .skip_while(|el| el.as_token().map(SyntaxToken::kind) != Some(T!['[']))
+            .scan(0, |seen, el| {
+                if el.as_token().map(SyntaxToken::kind) == Some(T![:]) {
+                    *seen += 1;
+                }
+                Some((*seen, el))
+            })
+            .filter(|(seen, _)| *seen == colons)
+            .find_map(|(_, el)| el.into_node().and_then(Expr::cast))
+    }
 }
 
 def_ast_node!(ListExpr, LIST_EXPR);
@@ -667,9 +779,14 @@ impl DictExpr {
     access_nth_child!(Entries, entries);
 }
 
+def_ast_node!(SetExpr, SET_EXPR);
+impl SetExpr {
+    access_children!(Expr, elements);
+}
+
 def_ast_node!(DictComp, DICT_COMP);
 impl DictComp {
-    access_nth_child!(Expr, expr);
+    access_nth_child!(Entry, entry);
     access_children!(CompClause, comp_clauses);
 }
 
@@ -693,6 +810,10 @@ impl Literal {
     pub fn kind(&self) -> LiteralKind {
         let token = self.token();
 
+        if let Some(token) = Ident::cast(token.clone()) {
+            return LiteralKind::Ident(token);
+        }
+
         if let Some(token) = Int::cast(token.clone()) {
             return LiteralKind::Int(token);
         }
@@ -712,7 +833,7 @@ impl Literal {
 def_ast_node!(CompFor, LIST_COMP_FOR);
 impl CompFor {
     pub fn loop_variables(&self) -> Vec<Expr> {
-        children_until_token(self.syntax(), T![in]).collect()
+        loop_variables(self.syntax())
     }
 
     pub fn expr(&self) -> Option<Expr> {
@@ -727,7 +848,7 @@ impl CompIf {
 
 def_ast_node!(Entries, ENTRIES);
 impl Entries {
-    access_children!(Entries, entries);
+    access_children!(Entry, entries);
 }
 
 def_ast_node!(Entry, ENTRY);
@@ -767,6 +888,21 @@ impl Parameter {
     }
 
     access_nth_child!(Expr, default);
+
+    /// The declared type of the parameter, e.g. `int` in `x: int = 1`.
+    pub fn type_expr(&self) -> Option<Expr> {
+        child::<TypeAnnotation>(self.syntax())?.ty()
+    }
+}
+
+def_ast_node!(TypeAnnotation, TYPE_ANNOTATION);
+impl TypeAnnotation {
+    access_nth_child!(Expr, ty);
+}
+
+def_ast_node!(ReturnType, RETURN_TYPE);
+impl ReturnType {
+    access_nth_child!(Expr, ty);
 }
 
 def_ast_node!(Arguments, ARGUMENTS);
@@ -810,6 +946,15 @@ def_ast_token!(Int, INT);
 def_ast_token!(Float, FLOAT);
 def_ast_token!(String, STRING);
 
+impl String {
+    /// Returns the contents of this string literal, with its prefix and quotes removed
+    /// and escape sequences processed. Returns `None` for malformed literals and for
+    /// f-strings.
+    pub fn value(&self) -> Option<std::string::String> {
+        unquote(self.syntax().text())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AstChildren<N: AstNode> {
     inner: SyntaxNodeChildren,
@@ -835,15 +980,12 @@ fn children<N: AstNode>(parent: &SyntaxNode) -> AstChildren<N> {
     }
 }
 
-fn children_until_token<N: AstNode>(
-    parent: &SyntaxNode,
-    kind: SyntaxKind,
-) -> impl Iterator<Item = N> {
+fn loop_variables(parent: &SyntaxNode) -> Vec<Expr> {
     parent
-        .children_with_tokens()
-        .take_while(move |el| el.as_token().map(SyntaxToken::kind) != Some(kind))
-        .filter_map(|el| el.into_node())
-        .filter_map(N::cast)
+        .children()
+        .find(|node| node.kind() == LOOP_VARIABLES)
+        .map(|node| children(&node).collect())
+        .unwrap_or_default()
 }
 
 fn children_after_token<N: AstNode>(
@@ -867,3 +1009,195 @@ fn child_token<T: AstToken>(parent: &SyntaxNode) -> Option<T> {
         .filter_map(|el| el.into_token())
         .find_map(T::cast)
 }
+
+fn unquote(text: &str) -> Option<std::string::String> {
+    let prefix_len = text
+        .find(|ch: char| !matches!(ch, 'r' | 'R' | 'b' | 'B' | 'f' | 'F'))
+        .unwrap_or(text.len());
+    let (prefix, rest) = text.split_at(prefix_len);
+    let raw = prefix.contains(['r', 'R']);
+    // The value of a format string is only known when it's evaluated.
+    if prefix.contains(['f', 'F']) {
+        return None;
+    }
+
+    let quote = match rest.get(..3) {
+        Some(quote @ ("'''" | "\"\"\"")) => quote,
+        _ => match rest.get(..1) {
+            Some(quote @ ("'" | "\"")) => quote,
+            _ => return None,
+        },
+    };
+    let body = rest.strip_prefix(quote)?.strip_suffix(quote)?;
+    if raw {
+        return Some(body.to_string());
+    }
+
+    let mut value = std::string::String::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            value.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('\n') => (),
+            Some('n') => value.push('\n'),
+            Some('t') => value.push('\t'),
+            Some('r') => value.push('\r'),
+            Some('a') => value.push('\x07'),
+            Some('b') => value.push('\x08'),
+            Some('f') => value.push('\x0c'),
+            Some('v') => value.push('\x0b'),
+            Some(ch @ ('\\' | '\'' | '"')) => value.push(ch),
+            Some(ch @ '0'..='7') => {
+                let mut code = ch.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match chars.peek().and_then(|ch| ch.to_digit(8)) {
+                        Some(digit) => {
+                            code = code * 8 + digit;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                value.push(char::from_u32(code)?);
+            }
+            Some(kind @ ('x' | 'u' | 'U')) => {
+                let len = match kind {
+                    'x' => 2,
+                    'u' => 4,
+                    _ => 8,
+                };
+                let mut code = 0;
+                for _ in 0..len {
+                    code = code * 16 + chars.next()?.to_digit(16)?;
+                }
+                value.push(char::from_u32(code)?);
+            }
+            Some(ch) => {
+                value.push('\\');
+                value.push(ch);
+            }
+            None => value.push('\\'),
+        }
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_file, Dialect};
+
+    fn load_stmts(input: &str) -> Vec<LoadStmt> {
+        parse_file(input, Dialect::default())
+            .syntax()
+            .descendants()
+            .filter_map(LoadStmt::cast)
+            .collect()
+    }
+
+    #[test]
+    fn load_stmt_accessors() {
+        let stmts = load_stmts(r#"load("//foo:bar.bzl", "a", b = "c", "d")"#);
+        let stmt = &stmts[0];
+
+        assert_eq!(
+            stmt.module().and_then(|module| module.value()).as_deref(),
+            Some("//foo:bar.bzl")
+        );
+        assert_eq!(
+            stmt.symbols()
+                .iter()
+                .filter_map(|symbol| symbol.name()?.value())
+                .collect::<Vec<_>>(),
+            ["a", "d"]
+        );
+        let aliases = stmt
+            .aliases()
+            .iter()
+            .map(|alias| {
+                (
+                    alias.alias().unwrap().syntax().text().to_string(),
+                    alias.name().and_then(|name| name.value()).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(aliases, [("b".to_string(), "c".to_string())]);
+    }
+
+    #[test]
+    fn slice_expr_parts() {
+        let parts = |input: &str| {
+            let slice = parse_file(input, Dialect::default())
+                .syntax()
+                .descendants()
+                .find_map(SliceExpr::cast)
+                .unwrap();
+            [slice.start(), slice.end(), slice.step()]
+                .map(|part| part.map(|expr| expr.syntax().text().to_string()))
+        };
+
+        assert_eq!(
+            parts("a[1:2:3]"),
+            [Some("1".into()), Some("2".into()), Some("3".into())]
+        );
+        assert_eq!(parts("a[:2]"), [None, Some("2".into()), None]);
+        assert_eq!(parts("a[::x]"), [None, None, Some("x".into())]);
+        assert_eq!(parts("a[1:]"), [Some("1".into()), None, None]);
+    }
+
+    #[test]
+    fn type_annotations() {
+        let syntax = parse_file(
+            "def f(x: int, y = 1) -> str:\n    z: bool = x\n",
+            Dialect::default(),
+        )
+        .syntax();
+        let text = |expr: Option<Expr>| expr.map(|expr| expr.syntax().text().to_string());
+
+        let def = syntax.descendants().find_map(DefStmt::cast).unwrap();
+        assert_eq!(text(def.return_type()), Some("str".into()));
+        let parameters = def.parameters().unwrap().parameters();
+        assert_eq!(text(parameters[0].type_expr()), Some("int".into()));
+        assert_eq!(text(parameters[1].type_expr()), None);
+        assert_eq!(text(parameters[1].default()), Some("1".into()));
+
+        let assign = syntax.descendants().find_map(AssignStmt::cast).unwrap();
+        assert_eq!(text(assign.type_expr()), Some("bool".into()));
+        assert_eq!(text(assign.lhs()), Some("z".into()));
+        assert_eq!(text(assign.rhs()), Some("x".into()));
+    }
+
+    #[test]
+    fn set_expr_elements() {
+        let file = File::cast(parse_file("{1, x}\n", Dialect::default()).syntax()).unwrap();
+        let stmt = match file.statements().pop() {
+            Some(Stmt::SimpleStmt(stmt)) => stmt.statements().pop(),
+            _ => None,
+        };
+        let elements = match stmt {
+            Some(SmallStmt::ExprStmt(Expr::SetExpr(set))) => set.elements(),
+            _ => panic!("expected a set expression"),
+        };
+        let elements: Vec<_> = elements
+            .iter()
+            .map(|elem| elem.syntax().text().to_string())
+            .collect();
+        assert_eq!(elements, ["1", "x"]);
+    }
+
+    #[test]
+    fn string_value() {
+        assert_eq!(unquote(r#""foo""#).as_deref(), Some("foo"));
+        assert_eq!(unquote(r#"'''a"b'''"#).as_deref(), Some("a\"b"));
+        assert_eq!(
+            unquote(r#""a\tb\x41\101\u00e9""#).as_deref(),
+            Some("a\tbAAé")
+        );
+        assert_eq!(unquote(r#"r"a\tb""#).as_deref(), Some("a\\tb"));
+        assert_eq!(unquote(r#"b'\n'"#).as_deref(), Some("\n"));
+        assert_eq!(unquote(r#""unterminated"#), None);
+    }
+}
@@ -0,0 +1,73 @@
+/// The language features a Starlark implementation accepts. Every construct is parsed in
+/// every dialect, but using one the dialect doesn't allow is reported as an error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Dialect {
+    /// Whether `if` and `for` statements may appear outside of functions.
+    pub top_level_control: bool,
+    /// Whether functions may be defined with `def`.
+    pub def_stmts: bool,
+    /// Whether `def` statements may appear inside other functions.
+    pub nested_def: bool,
+    /// Whether `while` loops are allowed.
+    pub while_stmts: bool,
+    /// Whether a function may call itself.
+    pub recursion: bool,
+    /// Whether sets may be written as literals, as in `{1, 2}`.
+    pub set_literals: bool,
+    /// Whether strings may be formatted with `f"..."` literals.
+    pub f_strings: bool,
+    /// Whether parameters, return values and assignments may be annotated with types, as
+    /// in `def f(x: int) -> str` and `x: int = 1`.
+    pub type_annotations: bool,
+}
+
+impl Dialect {
+    /// Starlark as described by the language specification.
+    pub const STANDARD: Dialect = Dialect {
+        top_level_control: false,
+        def_stmts: true,
+        nested_def: true,
+        while_stmts: false,
+        recursion: false,
+        set_literals: false,
+        f_strings: false,
+        type_annotations: false,
+    };
+
+    /// The dialect of `.bzl` files.
+    pub const BAZEL: Dialect = Dialect::STANDARD;
+
+    /// The dialect of BUILD, WORKSPACE and MODULE.bazel files, which can only call
+    /// functions defined elsewhere.
+    pub const BAZEL_BUILD: Dialect = Dialect {
+        def_stmts: false,
+        ..Dialect::BAZEL
+    };
+
+    /// The dialect of Buck2 and other users of starlark-rust, which allow statements at
+    /// the top level, f-strings and type annotations.
+    pub const BUCK2: Dialect = Dialect {
+        top_level_control: true,
+        f_strings: true,
+        type_annotations: true,
+        ..Dialect::STANDARD
+    };
+
+    /// A dialect that allows everything the parser understands.
+    pub const PERMISSIVE: Dialect = Dialect {
+        top_level_control: true,
+        def_stmts: true,
+        nested_def: true,
+        while_stmts: true,
+        recursion: true,
+        set_literals: true,
+        f_strings: true,
+        type_annotations: true,
+    };
+}
+
+impl Default for Dialect {
+    fn default() -> Dialect {
+        Dialect::STANDARD
+    }
+}
@@ -39,6 +39,36 @@ impl<'src> Lexer<'src> {
             done: false,
         }
     }
+
+    /// Creates a lexer that starts at `pos`, which must be the start of a line outside
+    /// of brackets, with the indentation levels of the blocks open there.
+    pub(crate) fn resume(src: &'src str, pos: usize, indent_levels: Vec<usize>) -> Self {
+        Self {
+            chars: src[pos..].chars(),
+            pos,
+            indent_levels,
+            ..Self::new(src)
+        }
+    }
+
+    /// The byte offset up to which the input has been lexed.
+    pub(crate) fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// The indentation levels of the open blocks if the lexer is at the start of a line
+    /// outside of brackets, where lexing could resume, or `None` otherwise.
+    pub(crate) fn indent_levels(&self) -> Option<&[usize]> {
+        (self.line_start && !self.done).then_some(&self.indent_levels)
+    }
+}
+
+/// The indentation level of a line indented by `indent`, counting a tab as eight spaces.
+pub(crate) fn indent_level(indent: &str) -> usize {
+    indent
+        .chars()
+        .map(|ch| if ch == '\t' { 8 } else { 1 })
+        .sum()
 }
 
 impl<'src> Lexer<'src> {
@@ -111,8 +141,41 @@ impl<'src> Lexer<'src> {
         }
     }
 
+    /// Parses the rest of a decimal int or float literal, after its first character.
+    fn parse_number(&mut self, mut seen_dot: bool) -> SyntaxKind {
+        let mut is_float = seen_dot;
+        loop {
+            match self.peek() {
+                Some('0'..='9') => {}
+                Some('.') if !seen_dot => {
+                    seen_dot = true;
+                    is_float = true;
+                }
+                Some('e' | 'E') => {
+                    is_float = true;
+                    self.bump();
+                    if matches!(self.peek(), Some('+' | '-')) {
+                        self.bump();
+                    }
+                    while matches!(self.peek(), Some('0'..='9')) {
+                        self.bump();
+                    }
+                    break;
+                }
+                _ => break,
+            }
+            self.bump();
+        }
+        if is_float {
+            FLOAT
+        } else {
+            INT
+        }
+    }
+
     fn parse_identifier_or_keyword(&mut self, start: usize) -> SyntaxKind {
-        while matches!(self.peek(), Some(ch) if ch.is_alphabetic() || ch.is_ascii_digit()) {
+        while matches!(self.peek(), Some(ch) if ch.is_alphabetic() || ch.is_ascii_digit() || ch == '_')
+        {
             self.bump();
         }
         match &self.input[start..self.pos] {
@@ -171,6 +234,7 @@ impl<'src> Iterator for Lexer<'src> {
         // Check if we're not currently in a line continuation from '\'. If we aren't,
         // then calculate indentation.
         let mut indent_cols = 0;
+        let indent_start = self.pos;
         let was_line_start = self.line_start;
 
         if self.line_start {
@@ -235,7 +299,7 @@ impl<'src> Iterator for Lexer<'src> {
             return Some(LexerReturn(
                 Token {
                     kind: WHITESPACE,
-                    len: indent_cols,
+                    len: self.pos - indent_start,
                 },
                 None,
             ));
@@ -271,21 +335,22 @@ impl<'src> Iterator for Lexer<'src> {
 
         let kind = match self.bump() {
             Some(ch) => match ch {
-                ch if ch.is_alphabetic() => {
-                    // Check for bytes, raw string, or raw bytes literals.
-                    let peek_first = self.peek();
+                ch if ch.is_alphabetic() || ch == '_' => {
+                    // Check for bytes, raw string, raw bytes or format string literals.
+                    // Prefixes are case-insensitive, as in `F"..."`.
+                    let peek_first = self.peek().map(|ch| ch.to_ascii_lowercase());
                     let peek_second = self.peek2();
                     let mut kind = STRING;
-                    match (ch, peek_first, peek_second) {
-                        ('r' | 'b', Some(ch @ ('\'' | '"')), _) => {
+                    match (ch.to_ascii_lowercase(), peek_first, peek_second) {
+                        ('r' | 'b' | 'f', Some(ch @ ('\'' | '"')), _) => {
                             self.bump();
                             diagnostic = self.parse_string_or_bytes(ch, ch == 'r');
                         }
-                        ('r', Some('b'), Some(ch @ ('\'' | '"')))
-                        | ('b', Some('r'), Some(ch @ ('\'' | '"'))) => {
+                        ('r', Some('b' | 'f'), Some(ch @ ('\'' | '"')))
+                        | ('b' | 'f', Some('r'), Some(ch @ ('\'' | '"'))) => {
                             self.bump();
                             self.bump();
-                            // raw bytes (br)
+                            // raw bytes (br) or raw format strings (rf)
                             diagnostic = self.parse_string_or_bytes(ch, true);
                         }
                         _ => {
@@ -294,12 +359,14 @@ impl<'src> Iterator for Lexer<'src> {
                     }
                     kind
                 }
-                '0'..='9' => {
-                    while matches!(self.peek(), Some('0'..='9')) {
+                '0' if matches!(self.peek(), Some('x' | 'X' | 'o' | 'O' | 'b' | 'B')) => {
+                    self.bump();
+                    while matches!(self.peek(), Some(ch) if ch.is_ascii_alphanumeric()) {
                         self.bump();
                     }
                     INT
                 }
+                '0'..='9' => self.parse_number(false),
                 '\'' | '"' => {
                     diagnostic = self.parse_string_or_bytes(ch, false);
                     STRING
@@ -311,13 +378,23 @@ impl<'src> Iterator for Lexer<'src> {
                     WHITESPACE
                 }
                 '#' => {
-                    while self.peek() != Some('\n') {
+                    while !matches!(self.peek(), Some('\n') | None) {
                         self.bump();
                     }
                     COMMENT
                 }
                 '+' => peek_or!('=', PLUS_EQ, PLUS),
-                '-' => peek_or!('=', MINUS_EQ, MINUS),
+                '-' => match self.peek() {
+                    Some('=') => {
+                        self.bump();
+                        MINUS_EQ
+                    }
+                    Some('>') => {
+                        self.bump();
+                        ARROW
+                    }
+                    _ => MINUS,
+                },
                 '*' => match self.peek() {
                     Some('*') => {
                         self.bump();
@@ -347,9 +424,19 @@ impl<'src> Iterator for Lexer<'src> {
                 },
                 '%' => peek_or!('=', MOD_EQ, MOD),
                 '~' => TILDE,
-                '&' => peek_or!('&', AND_EQ, AND),
-                '|' => peek_or!('|', OR_EQ, OR),
-                '^' => peek_or!('^', XOR_EQ, XOR),
+                '&' => peek_or!('=', AND_EQ, AND),
+                '|' => peek_or!('=', OR_EQ, OR),
+                '^' => peek_or!('=', XOR_EQ, XOR),
+                '!' => match self.peek() {
+                    Some('=') => {
+                        self.bump();
+                        BANG_EQ
+                    }
+                    _ => {
+                        diagnostic = Some("Unexpected character".to_string());
+                        ERROR
+                    }
+                },
                 '<' => match self.peek() {
                     Some('<') => {
                         self.bump();
@@ -382,6 +469,7 @@ impl<'src> Iterator for Lexer<'src> {
                     }
                     _ => GT,
                 },
+                '.' if matches!(self.peek(), Some('0'..='9')) => self.parse_number(true),
                 '.' => DOT,
                 ',' => COMMA,
                 '=' => peek_or!('=', EQ_EQ, EQ),
@@ -511,6 +599,132 @@ for foo in bar
         );
     }
 
+    #[test]
+    fn test_ident_underscore() {
+        check_lexing(
+            "_foo bar_baz __init__2",
+            expect![[r#"
+            IDENT@0..4 "_foo" None
+            WHITESPACE@4..5 " " None
+            IDENT@5..12 "bar_baz" None
+            WHITESPACE@12..13 " " None
+            IDENT@13..22 "__init__2" None
+            NEWLINE@22..22 "" None
+        "#]],
+        );
+    }
+
+    #[test]
+    fn test_numbers() {
+        check_lexing(
+            "0 123 0x1F 0o17 0b10 1.5 1. .5 1e10 1.5E-3 a.b",
+            expect![[r#"
+            INT@0..1 "0" None
+            WHITESPACE@1..2 " " None
+            INT@2..5 "123" None
+            WHITESPACE@5..6 " " None
+            INT@6..10 "0x1F" None
+            WHITESPACE@10..11 " " None
+            INT@11..15 "0o17" None
+            WHITESPACE@15..16 " " None
+            INT@16..20 "0b10" None
+            WHITESPACE@20..21 " " None
+            FLOAT@21..24 "1.5" None
+            WHITESPACE@24..25 " " None
+            FLOAT@25..27 "1." None
+            WHITESPACE@27..28 " " None
+            FLOAT@28..30 ".5" None
+            WHITESPACE@30..31 " " None
+            FLOAT@31..35 "1e10" None
+            WHITESPACE@35..36 " " None
+            FLOAT@36..42 "1.5E-3" None
+            WHITESPACE@42..43 " " None
+            IDENT@43..44 "a" None
+            DOT@44..45 "." None
+            IDENT@45..46 "b" None
+            NEWLINE@46..46 "" None
+        "#]],
+        );
+    }
+
+    #[test]
+    fn test_tab_indentation() {
+        check_lexing(
+            "if x:\n\tpass\n# done",
+            expect![[r##"
+            IF_KW@0..2 "if" None
+            WHITESPACE@2..3 " " None
+            IDENT@3..4 "x" None
+            COLON@4..5 ":" None
+            NEWLINE@5..6 "\n" None
+            WHITESPACE@6..7 "\t" None
+            INDENT@7..7 "" None
+            PASS_KW@7..11 "pass" None
+            NEWLINE@11..12 "\n" None
+            COMMENT@12..18 "# done" None
+            NEWLINE@18..18 "" None
+            OUTDENT@18..18 "" None
+        "##]],
+        );
+    }
+
+    #[test]
+    fn test_operators() {
+        check_lexing(
+            "a != b &= c |= d ^= e & f | g ^ h",
+            expect![[r#"
+            IDENT@0..1 "a" None
+            WHITESPACE@1..2 " " None
+            BANG_EQ@2..4 "!=" None
+            WHITESPACE@4..5 " " None
+            IDENT@5..6 "b" None
+            WHITESPACE@6..7 " " None
+            AND_EQ@7..9 "&=" None
+            WHITESPACE@9..10 " " None
+            IDENT@10..11 "c" None
+            WHITESPACE@11..12 " " None
+            OR_EQ@12..14 "|=" None
+            WHITESPACE@14..15 " " None
+            IDENT@15..16 "d" None
+            WHITESPACE@16..17 " " None
+            XOR_EQ@17..19 "^=" None
+            WHITESPACE@19..20 " " None
+            IDENT@20..21 "e" None
+            WHITESPACE@21..22 " " None
+            AND@22..23 "&" None
+            WHITESPACE@23..24 " " None
+            IDENT@24..25 "f" None
+            WHITESPACE@25..26 " " None
+            OR@26..27 "|" None
+            WHITESPACE@27..28 " " None
+            IDENT@28..29 "g" None
+            WHITESPACE@29..30 " " None
+            XOR@30..31 "^" None
+            WHITESPACE@31..32 " " None
+            IDENT@32..33 "h" None
+            NEWLINE@33..33 "" None
+        "#]],
+        );
+    }
+
+    #[test]
+    fn test_arrow() {
+        check_lexing(
+            "a->b -= -c",
+            expect![[r#"
+            IDENT@0..1 "a" None
+            ARROW@1..3 "->" None
+            IDENT@3..4 "b" None
+            WHITESPACE@4..5 " " None
+            MINUS_EQ@5..7 "-=" None
+            WHITESPACE@7..8 " " None
+            MINUS@8..9 "-" None
+            IDENT@9..10 "c" None
+            NEWLINE@10..10 "" None
+        "#]],
+        );
+    }
+
     #[test]
     fn test_strings() {
         check_lexing(
@@ -526,7 +740,10 @@ b'hello'
 b'''hello"'''
 b"""hello'"""
 rb'hello'
-br"hello""#,
+br"hello"
+F"hello"
+Rb'hello'
+fr"hello""#,
             expect![[r#"
                 NEWLINE@0..1 "\n" None
                 STRING@1..6 "\"foo\"" None
@@ -552,7 +769,13 @@ br"hello""#,
                 STRING@99..108 "rb'hello'" None
                 NEWLINE@108..109 "\n" None
                 STRING@109..118 "br\"hello\"" None
-                NEWLINE@118..118 "" None
+                NEWLINE@118..119 "\n" None
+                STRING@119..127 "F\"hello\"" None
+                NEWLINE@127..128 "\n" None
+                STRING@128..137 "Rb'hello'" None
+                NEWLINE@137..138 "\n" None
+                STRING@138..147 "fr\"hello\"" None
+                NEWLINE@147..147 "" None
             "#]],
         )
     }
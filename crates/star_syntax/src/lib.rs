@@ -1,16 +1,22 @@
 pub mod ast;
+mod dialect;
 pub mod lexer;
 pub mod lines;
 pub mod parser;
 pub mod render;
 pub mod syntax_kind;
+mod text_edit;
+mod validation;
 
 pub(crate) use crate::syntax_kind::*;
 pub use crate::{
+    dialect::Dialect,
     parser::{parse_file, Parse},
     render::*,
     syntax_kind::SyntaxKind,
+    text_edit::TextEdit,
 };
+pub use rowan::{TextRange, TextSize};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum StarlarkLanguage {}
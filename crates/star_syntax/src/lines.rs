@@ -1,31 +1,193 @@
+use std::collections::HashMap;
+
+/// The unit in which columns are counted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// Columns count bytes, which is also how offsets into the text are measured.
+    Utf8,
+    /// Columns count UTF-16 code units. This is the default for LSP.
+    Utf16,
+    /// Columns count Unicode code points.
+    Utf32,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Lines {
     positions: Vec<usize>,
+    /// Characters that take up more than one byte, keyed by line.
+    wide_chars: HashMap<u32, Vec<WideChar>>,
+    len: usize,
+}
+
+/// A multi-byte character, with its byte range relative to the start of its line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct WideChar {
+    start: usize,
+    end: usize,
+}
+
+impl WideChar {
+    fn len(&self, encoding: Encoding) -> usize {
+        match encoding {
+            Encoding::Utf8 => self.end - self.start,
+            Encoding::Utf16 if self.end - self.start == 4 => 2,
+            Encoding::Utf16 | Encoding::Utf32 => 1,
+        }
+    }
 }
 
 impl Lines {
     pub fn new(s: &str) -> Lines {
         let mut positions = Vec::new();
+        let mut wide_chars = HashMap::new();
+        let mut line_wide_chars = Vec::new();
+        let mut line_start = 0;
         let mut cursor = 0;
 
         for ch in s.chars() {
+            let len = ch.len_utf8();
             if ch == '\n' {
+                if !line_wide_chars.is_empty() {
+                    wide_chars.insert(positions.len() as u32, std::mem::take(&mut line_wide_chars));
+                }
                 positions.push(cursor);
+                line_start = cursor + 1;
+            } else if len > 1 {
+                line_wide_chars.push(WideChar {
+                    start: cursor - line_start,
+                    end: cursor - line_start + len,
+                });
             }
-            cursor += ch.len_utf8();
+            cursor += len;
+        }
+
+        if !line_wide_chars.is_empty() {
+            wide_chars.insert(positions.len() as u32, line_wide_chars);
         }
 
-        Lines { positions }
+        Lines {
+            positions,
+            wide_chars,
+            len: cursor,
+        }
     }
 
-    pub fn line_num_and_col(&self, pos: usize) -> (u32, u32) {
-        let index = self.positions.partition_point(|line_pos| *line_pos < pos);
+    /// Converts a byte offset to a zero-based line and a column counted in `encoding`.
+    pub fn line_col(&self, pos: usize, encoding: Encoding) -> (u32, u32) {
+        let pos = pos.min(self.len);
+        let line = self.positions.partition_point(|line_pos| *line_pos < pos);
+        let mut col = pos - self.line_start(line);
+
+        if let Some(wide_chars) = self.wide_chars.get(&(line as u32)) {
+            let before = wide_chars.iter().take_while(|ch| ch.end <= col);
+            col -= before
+                .map(|ch| ch.len(Encoding::Utf8) - ch.len(encoding))
+                .sum::<usize>();
+        }
+
+        (line as u32, col as u32)
+    }
 
-        // First line. Simply return position.
-        if index == 0 {
-            (0, pos as u32)
-        } else {
-            (index as u32, (pos - self.positions[index - 1] - 1) as u32)
+    /// Converts a zero-based line and a column counted in `encoding` to a byte offset.
+    /// Positions past the end of a line or of the text are clamped, and columns in the
+    /// middle of a character are moved to its start.
+    pub fn offset(&self, line: u32, col: u32, encoding: Encoding) -> usize {
+        let line_index = line as usize;
+        if line_index > self.positions.len() {
+            return self.len;
+        }
+        let line_start = self.line_start(line_index);
+        let line_end = self.positions.get(line_index).cloned().unwrap_or(self.len);
+
+        let mut col = col as usize;
+        if let Some(wide_chars) = self.wide_chars.get(&line) {
+            for ch in wide_chars {
+                if ch.start >= col {
+                    break;
+                }
+                if col < ch.start + ch.len(encoding) {
+                    return (line_start + ch.start).min(line_end);
+                }
+                // This character comes before the one we are looking for, so account for
+                // the difference between its length in bytes and in `encoding`.
+                col += ch.len(Encoding::Utf8) - ch.len(encoding);
+            }
+        }
+
+        (line_start + col).min(line_end)
+    }
+
+    fn line_start(&self, line: usize) -> usize {
+        match line.checked_sub(1) {
+            Some(index) => self.positions[index] + 1,
+            None => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 'ü' is two bytes and one UTF-16 code unit, '𝄞' is four bytes and two code units.
+    const TEXT: &str = "x = 1\ns = \"ü𝄞\" + y\n\nz";
+
+    #[test]
+    fn offset() {
+        let lines = Lines::new(TEXT);
+
+        assert_eq!(lines.offset(0, 0, Encoding::Utf16), 0);
+        assert_eq!(lines.offset(0, 4, Encoding::Utf16), 4);
+        assert_eq!(lines.offset(0, 100, Encoding::Utf16), 5);
+
+        let line_start = 6;
+        assert_eq!(lines.offset(1, 5, Encoding::Utf16), line_start + 5);
+        assert_eq!(lines.offset(1, 6, Encoding::Utf16), line_start + 7);
+        assert_eq!(lines.offset(1, 8, Encoding::Utf16), line_start + 11);
+        assert_eq!(lines.offset(1, 7, Encoding::Utf32), line_start + 11);
+        assert_eq!(lines.offset(1, 11, Encoding::Utf8), line_start + 11);
+        assert_eq!(&TEXT[lines.offset(1, 10, Encoding::Utf16)..], "+ y\n\nz");
+
+        assert_eq!(lines.offset(2, 0, Encoding::Utf16), TEXT.len() - 2);
+        assert_eq!(lines.offset(3, 1, Encoding::Utf16), TEXT.len());
+        assert_eq!(lines.offset(10, 0, Encoding::Utf16), TEXT.len());
+    }
+
+    #[test]
+    fn offset_inside_char() {
+        let lines = Lines::new("😀x\nü");
+
+        assert_eq!(lines.offset(0, 1, Encoding::Utf16), 0);
+        assert_eq!(lines.offset(0, 2, Encoding::Utf16), 4);
+        assert_eq!(lines.offset(0, 3, Encoding::Utf8), 0);
+        assert_eq!(lines.offset(0, 4, Encoding::Utf8), 4);
+        assert_eq!(lines.offset(1, 1, Encoding::Utf8), 6);
+        assert_eq!(lines.offset(1, 2, Encoding::Utf8), 8);
+    }
+
+    #[test]
+    fn line_col() {
+        let lines = Lines::new(TEXT);
+
+        assert_eq!(lines.line_col(4, Encoding::Utf16), (0, 4));
+        assert_eq!(lines.line_col(5, Encoding::Utf16), (0, 5));
+
+        let pos = TEXT.find('+').unwrap();
+        assert_eq!(lines.line_col(pos, Encoding::Utf8), (1, 13));
+        assert_eq!(lines.line_col(pos, Encoding::Utf16), (1, 10));
+        assert_eq!(lines.line_col(pos, Encoding::Utf32), (1, 9));
+
+        assert_eq!(lines.line_col(TEXT.len(), Encoding::Utf16), (3, 1));
+    }
+
+    #[test]
+    fn roundtrip() {
+        let lines = Lines::new(TEXT);
+        for encoding in [Encoding::Utf8, Encoding::Utf16, Encoding::Utf32] {
+            for (pos, _) in TEXT.char_indices() {
+                let (line, col) = lines.line_col(pos, encoding);
+                assert_eq!(lines.offset(line, col, encoding), pos);
+            }
         }
     }
 }
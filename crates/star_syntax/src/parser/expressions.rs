@@ -1,7 +1,7 @@
 use super::*;
 
 pub(crate) const ATOM_EXPR_START: SyntaxKindSet =
-    SyntaxKindSet::new(&[T![ident], INT, STRING, T!['('], T!['['], T!['{']]);
+    SyntaxKindSet::new(&[T![ident], INT, FLOAT, STRING, T!['('], T!['['], T!['{']]);
 
 pub(crate) const EXPR_START: SyntaxKindSet = ATOM_EXPR_START.union(SyntaxKindSet::new(&[
     T![if],
@@ -75,7 +75,7 @@ pub(crate) fn expression_or_tuple(p: &mut Parser, parens: bool, force_expr_list:
 // 1 if 2 else 3 if 4 else 5
 pub(crate) fn test(p: &mut Parser, allow_if: bool) -> bool {
     match p.current() {
-        T![lambda] => todo!(),
+        T![lambda] => lambda_expr(p),
         _ => {
             let checkpoint = p.checkpoint();
             if !or_expr(p) {
@@ -122,11 +122,11 @@ pub(crate) fn or_expr(p: &mut Parser) -> bool {
 
 pub(crate) fn and_expr(p: &mut Parser) -> bool {
     let checkpoint = p.checkpoint();
-    if eq_expr(p) {
+    if not_expr(p) {
         while p.at(T![and]) {
             p.enter_at(checkpoint, BINARY_EXPR);
             p.bump_any();
-            eq_expr(p);
+            not_expr(p);
             p.exit();
         }
         true
@@ -135,15 +135,46 @@ pub(crate) fn and_expr(p: &mut Parser) -> bool {
     }
 }
 
+// `not` binds more loosely than comparisons, so `not x in y` is `not (x in y)`.
+// test not_expr
+// not x
+// not not x
+// not x in y
+// not x and y
+pub(crate) fn not_expr(p: &mut Parser) -> bool {
+    if !p.at(T![not]) {
+        return eq_expr(p);
+    }
+    p.enter(UNARY_EXPR);
+    p.bump(T![not]);
+    let res = not_expr(p);
+    p.exit();
+    res
+}
+
+// test eq_expr
+// a == b
+// a != b
+// a < b <= c
+// a > b >= c
+// a in b
+// a not in b
 pub(crate) fn eq_expr(p: &mut Parser) -> bool {
     let checkpoint = p.checkpoint();
     if bitwise_or_expr(p) {
-        while matches!(
-            p.current(),
-            T![==] | T![!=] | T![<] | T![>] | T![<=] | T![>=] | T![in]
-        ) {
-            p.enter_at(checkpoint, BINARY_EXPR);
-            p.bump_any();
+        loop {
+            match p.current() {
+                T![==] | T![!=] | T![<] | T![>] | T![<=] | T![>=] | T![in] => {
+                    p.enter_at(checkpoint, BINARY_EXPR);
+                    p.bump_any();
+                }
+                T![not] if p.nth_at(1, T![in]) => {
+                    p.enter_at(checkpoint, BINARY_EXPR);
+                    p.bump(T![not]);
+                    p.bump(T![in]);
+                }
+                _ => break,
+            }
             bitwise_or_expr(p);
             p.exit();
         }
@@ -230,11 +261,11 @@ pub(crate) fn add_expr(p: &mut Parser) -> bool {
 
 pub(crate) fn mul_expr(p: &mut Parser) -> bool {
     let checkpoint = p.checkpoint();
-    if primary_expr(p) {
+    if unary_expr(p) {
         while matches!(p.current(), T![*] | T![%] | T![/] | T!["//"]) {
             p.enter_at(checkpoint, BINARY_EXPR);
             p.bump_any();
-            primary_expr(p);
+            unary_expr(p);
             p.exit();
         }
         true
@@ -243,6 +274,24 @@ pub(crate) fn mul_expr(p: &mut Parser) -> bool {
     }
 }
 
+// test unary_expr
+// -1
+// +x
+// ~x
+// --x
+// -x * y
+// -x.y
+pub(crate) fn unary_expr(p: &mut Parser) -> bool {
+    if !matches!(p.current(), T![+] | T![-] | T![~]) {
+        return primary_expr(p);
+    }
+    p.enter(UNARY_EXPR);
+    p.bump_any();
+    let res = unary_expr(p);
+    p.exit();
+    res
+}
+
 pub(crate) fn primary_expr(p: &mut Parser) -> bool {
     let checkpoint = p.checkpoint();
     if atom_expr(p) {
@@ -281,50 +330,54 @@ pub(crate) fn primary_expr(p: &mut Parser) -> bool {
                         p.error_and_recover(RECOVERY_SET);
                     }
                 }
+                // test index_expr
+                // a[0]
+                // a[b][c]
+                // a[1, 2]
                 T!['['] => {
-                    p.enter_at(checkpoint, SLICE_EXPR);
                     p.bump(T!['[']);
-                    match p.current() {
-                        T![:] => (),
-                        kind if EXPR_START.contains(kind) => {
+                    let has_start = EXPR_START.contains(p.current());
+                    if has_start {
+                        expression_or_tuple(
+                            p, /* parens */ false, /* force_expr_list */ false,
+                        );
+                    }
+                    let kind = if p.at(T![:]) {
+                        // test slice_expr
+                        // a[1:2]
+                        // a[1:2:3]
+                        // a[:]
+                        // a[::]
+                        // a[1:]
+                        // a[:2]
+                        // a[::2]
+                        // a[1::2]
+                        p.bump(T![:]);
+                        if EXPR_START.contains(p.current()) {
+                            test(p, true);
+                        }
+                        if p.eat(T![:]) && EXPR_START.contains(p.current()) {
                             test(p, true);
-                            match p.current() {
-                                T![']'] => {
-                                    p.eat(T![']']);
-                                    p.exit();
-                                    break true;
-                                }
-                                T![:] => (),
-                                _ => {
-                                    p.error("expected ':' or ']'");
-                                    // TODO: Recover to closing brace?
-                                    p.exit();
-                                    break true;
-                                }
-                            }
                         }
-                        _ => {
-                            p.error("expected ':' or expression");
-                            // TODO: Recover to closing brace?
-                            p.exit();
-                            break true;
+                        SLICE_EXPR
+                    } else {
+                        // test_err index_expr_empty
+                        // a[]
+                        if !has_start {
+                            p.error("expected expression");
                         }
-                    }
-
-                    // Parse slice.
-                    p.bump(T![:]);
-
-                    // if EXPR_START.contains(p.current()) {
-                    //     test(p, true);
-                    // }
-
-                    // if p.eat(T![:]) && EXPR_START.contains(p.current()) {
-                    //     test(p, true);
-                    // }
+                        INDEX_EXPR
+                    };
+                    p.enter_at(checkpoint, kind);
 
-                    // if !p.expect(T![']']) {
-                    //     p.exit();
-                    // }
+                    // test_err slice_expr_unclosed
+                    // a[1:2
+                    // b[1 2]
+                    let res = p.expect(T![']']);
+                    p.exit();
+                    if !res {
+                        p.error_and_recover(RECOVERY_SET);
+                    }
                 }
                 _ => break true,
             }
@@ -347,6 +400,7 @@ pub(crate) fn atom_expr(p: &mut Parser) -> bool {
             expression_or_tuple(p, /* parens */ true, /* force_expr_list */ false);
         }
         T!['['] => list_expr_or_comp(p),
+        T!['{'] => dict_expr_or_comp(p),
         _ => {
             p.error("expected expression");
             return false;
@@ -355,6 +409,14 @@ pub(crate) fn atom_expr(p: &mut Parser) -> bool {
     true
 }
 
+// test literal_expr
+// x
+// 1
+// 0x1F
+// 1.5
+// 1e10
+// "a"
+// b'b'
 pub(crate) fn literal(p: &mut Parser) {
     p.enter(LITERAL);
     p.bump_any();
@@ -380,29 +442,7 @@ pub(crate) fn list_expr_or_comp(p: &mut Parser) {
                 // [x for x in y if x]
                 // [(x, y) for x in a for y in b if x == y]
                 p.enter_at(checkpoint, LIST_COMP);
-                loop {
-                    match p.current() {
-                        T![for] => {
-                            p.enter(LIST_COMP_FOR);
-                            p.bump(T![for]);
-                            loop_variables(p);
-                            if !p.expect(T![in]) {
-                                p.exit();
-                                break;
-                            }
-
-                            test(p, false);
-                            p.exit();
-                        }
-                        T![if] => {
-                            p.enter(LIST_COMP_IF);
-                            p.bump(T![if]);
-                            test(p, false);
-                            p.exit();
-                        }
-                        _ => break,
-                    }
-                }
+                comp_clauses(p);
             } else {
                 // test list_expr
                 // []
@@ -422,6 +462,128 @@ pub(crate) fn list_expr_or_comp(p: &mut Parser) {
     }
 }
 
+// test dict_expr
+// {}
+// {1: 2}
+// {"a": 1, "b": 2}
+// {"a": 1, "b": 2,}
+// {k: v for k, v in x}
+// {k: v for k in x if k for v in y}
+// test set_expr
+// {1}
+// {1, 2,}
+// {"a", x + 1}
+pub(crate) fn dict_expr_or_comp(p: &mut Parser) {
+    let checkpoint = p.checkpoint();
+    p.bump(T!['{']);
+    if p.at(T!['}']) {
+        p.enter_at(checkpoint, DICT_EXPR);
+        p.bump(T!['}']);
+        p.exit();
+        return;
+    }
+
+    // The first element tells a dict from a set: only dict entries have a ':'.
+    let entries_checkpoint = p.checkpoint();
+    test(p, true);
+    if p.at(T![:]) {
+        p.enter_at(entries_checkpoint, ENTRY);
+        p.bump(T![:]);
+        test(p, true);
+        p.exit();
+        if p.at(T![for]) {
+            p.enter_at(checkpoint, DICT_COMP);
+            comp_clauses(p);
+        } else {
+            p.enter_at(entries_checkpoint, ENTRIES);
+            while p.at(T![,]) && EXPR_START.contains(p.nth(1)) {
+                p.bump(T![,]);
+                entry(p);
+            }
+            p.exit();
+            p.enter_at(checkpoint, DICT_EXPR);
+            p.eat(T![,]);
+        }
+    } else {
+        p.enter_at(checkpoint, SET_EXPR);
+        while p.at(T![,]) && EXPR_START.contains(p.nth(1)) {
+            p.bump(T![,]);
+            test(p, true);
+        }
+        p.eat(T![,]);
+    }
+
+    // test_err dict_expr_recover
+    // {1: 2
+    // {1 2}
+    // {1: 2 for}
+    let is_closed = p.expect(T!['}']);
+    p.exit();
+    if !is_closed {
+        p.error_and_recover(RECOVERY_SET);
+    }
+}
+
+// `Entry = Test ':' Test .`
+pub(crate) fn entry(p: &mut Parser) {
+    p.enter(ENTRY);
+    test(p, true);
+    if p.expect(T![:]) {
+        test(p, true);
+    }
+    p.exit();
+}
+
+// `CompClause = 'for' LoopVariables 'in' Test | 'if' Test .`
+pub(crate) fn comp_clauses(p: &mut Parser) {
+    loop {
+        match p.current() {
+            T![for] => {
+                p.enter(LIST_COMP_FOR);
+                p.bump(T![for]);
+                loop_variables(p);
+                if !p.expect(T![in]) {
+                    p.exit();
+                    break;
+                }
+
+                test(p, false);
+                p.exit();
+            }
+            T![if] => {
+                p.enter(LIST_COMP_IF);
+                p.bump(T![if]);
+                test(p, false);
+                p.exit();
+            }
+            _ => break,
+        }
+    }
+}
+
+// `LambdaExpr = 'lambda' [Parameters] ':' Test .`
+// test lambda_expr
+// lambda: 1
+// lambda x: x
+// lambda x, *a, **kw: x
+// lambda x=1, y=2: x + y
+// f(lambda x: x * 2, key=lambda: None)
+pub(crate) fn lambda_expr(p: &mut Parser) {
+    p.enter(LAMBDA_EXPR);
+    p.bump(T![lambda]);
+    if PARAMETER_START.contains(p.current()) {
+        parameters(p, /* annotations */ false);
+    }
+
+    // test_err lambda_expr_missing_colon
+    // lambda x x
+    // lambda x:
+    if p.expect(T![:]) {
+        test(p, true);
+    }
+    p.exit();
+}
+
 pub(crate) fn loop_variables(p: &mut Parser) {
     p.enter(LOOP_VARIABLES);
     primary_expr(p);
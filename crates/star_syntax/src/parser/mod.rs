@@ -1,15 +1,18 @@
 use crate::{
+    dialect::Dialect,
     lexer::{Lexer, LexerReturn},
+    validation::validate,
     Diagnostic,
     SyntaxKind::{self, *},
     SyntaxKindSet, SyntaxNode, T,
 };
 use rowan::{Checkpoint, GreenNode, GreenNodeBuilder};
-use std::mem;
+use std::{mem, sync::Arc};
 
 mod arguments;
 mod expressions;
 mod parameters;
+mod reparsing;
 mod statements;
 mod suite;
 
@@ -28,6 +31,9 @@ pub(crate) const RECOVERY_SET: SyntaxKindSet = SyntaxKindSet::new(&[T!['\n']]);
 pub struct Parse {
     errors: Vec<Diagnostic>,
     green: GreenNode,
+    /// The lexed tokens of the whole file, kept around for incremental reparsing.
+    tokens: Arc<[(SyntaxKind, usize)]>,
+    dialect: Dialect,
 }
 
 enum State {
@@ -56,6 +62,10 @@ impl Parse {
     pub fn errors(&self) -> &[Diagnostic] {
         &self.errors
     }
+
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
 }
 
 impl<'a> Parser<'a> {
@@ -63,7 +73,7 @@ impl<'a> Parser<'a> {
         let tokens_without_whitespace = tokens
             .iter()
             .filter_map(|(kind, _)| {
-                if *kind != WHITESPACE {
+                if !kind.is_whitespace() {
                     Some(*kind)
                 } else {
                     None
@@ -236,19 +246,26 @@ pub(crate) fn file(p: &mut Parser) {
     p.exit();
 }
 
-pub fn parse_file(input: &str) -> Parse {
+fn lex(input: &str) -> Vec<(SyntaxKind, usize)> {
     let mut errors: Vec<String> = Vec::new();
 
-    let tokens = Lexer::new(input)
+    Lexer::new(input)
         .map(|LexerReturn(token, error)| {
             if let Some(error) = error {
                 errors.push(error);
             }
             (token.kind, token.len)
         })
-        .collect::<Vec<_>>();
+        .collect()
+}
 
-    let mut p = Parser::new(tokens, input);
+pub fn parse_file(input: &str, dialect: Dialect) -> Parse {
+    parse_tokens(lex(input), input, dialect)
+}
+
+/// Parses `input`, which has already been lexed into `tokens`.
+fn parse_tokens(tokens: Vec<(SyntaxKind, usize)>, input: &str, dialect: Dialect) -> Parse {
+    let mut p = Parser::new(tokens.clone(), input);
     file(&mut p);
 
     match mem::replace(&mut p.state, State::Normal) {
@@ -260,8 +277,21 @@ pub fn parse_file(input: &str) -> Parse {
         }
     }
 
+    let green = p.builder.finish();
+    let mut parse_errors = p.errors;
+    validate(
+        &SyntaxNode::new_root(green.clone()),
+        dialect,
+        &mut parse_errors,
+    );
+
+    // Keep errors ordered by position, so that incremental reparsing can splice them.
+    parse_errors.sort_by_key(|error| error.pos);
+
     Parse {
-        errors: p.errors,
-        green: p.builder.finish(),
+        errors: parse_errors,
+        green,
+        tokens: tokens.into(),
+        dialect,
     }
 }
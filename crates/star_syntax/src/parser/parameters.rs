@@ -6,15 +6,19 @@ pub(crate) const PARAMETER_START: SyntaxKindSet = SyntaxKindSet::new(&[IDENT, ST
 // test parameters
 // def foo(x, y=1+2, *z, **w):
 //     pass
-pub(crate) fn parameters(p: &mut Parser) {
+// Type annotations are never parsed in lambdas, where the ':' ends the parameters.
+// test parameters_type_annotations
+// def foo(x: int, y: list[str] = [], *args: int, **kwargs: str) -> str:
+//     pass
+pub(crate) fn parameters(p: &mut Parser, annotations: bool) {
     p.enter(PARAMETERS);
-    parameter(p);
+    parameter(p, annotations);
     while !p.at(EOF) && !p.at(T![')']) && !p.at(T![:]) {
         if !(p.at(T![,]) && PARAMETER_START.contains(p.nth(1))) {
             break;
         }
         p.bump(T![,]);
-        parameter(p);
+        parameter(p, annotations);
     }
     p.exit();
 }
@@ -26,15 +30,30 @@ pub(crate) fn parameters(p: &mut Parser) {
 // test parameters_default_always_ok
 // def foo(x=1, *y=1, **z=1):
 //     pass
-pub(crate) fn parameter(p: &mut Parser) {
+pub(crate) fn parameter(p: &mut Parser, annotations: bool) {
     p.enter(PARAMETER);
     match p.current() {
         T![*] | T![**] => p.bump_any(),
         kind if EXPR_START.contains(kind) => (),
         _ => unreachable!(),
     }
-    if p.expect(T![ident]) && p.eat(T![=]) {
+    if !p.expect(T![ident]) {
+        p.exit();
+        return;
+    }
+    if annotations && p.at(T![:]) {
+        type_annotation(p);
+    }
+    if p.eat(T![=]) {
         test(p, true);
     }
     p.exit();
 }
+
+// `TypeAnnotation = ':' Test .`
+pub(crate) fn type_annotation(p: &mut Parser) {
+    p.enter(TYPE_ANNOTATION);
+    p.bump(T![:]);
+    test(p, true);
+    p.exit();
+}
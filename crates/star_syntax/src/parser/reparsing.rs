@@ -0,0 +1,556 @@
+//! Incremental reparsing.
+//!
+//! Instead of parsing the whole file again after an edit, we look for the smallest
+//! statement, suite or bracketed expression that encloses the edit, reparse just its
+//! text and splice the resulting green node into the old tree. This is only valid if the
+//! tokens outside of that node are unaffected by the edit, which rules out edits that
+//! change indentation or block structure; those fall back to a full parse.
+//!
+//! Either way, only the tokens around the edit are lexed again: lexing starts at the
+//! line the edit begins on, and stops at the first line after it where the lexer is in
+//! the same state as it was before the edit.
+
+use super::*;
+use crate::{
+    lexer::{indent_level, Lexer, LexerReturn},
+    SyntaxToken, TextEdit, TextRange, TextSize,
+};
+use rowan::NodeOrToken;
+
+/// How many non-whitespace tokens following a reparsed node are handed to the parser.
+/// The parser peeks at most two tokens ahead, so this lets it see exactly what it would
+/// during a full parse when deciding where the node ends.
+const LOOKAHEAD: usize = 2;
+
+impl Parse {
+    /// Parses `text`, the text of this parse after `edit`, reusing as much of the
+    /// existing tree and tokens as possible.
+    pub fn reparse(&self, edit: &TextEdit, text: &str) -> Parse {
+        if edit.delete.end() > self.syntax().text_range().end() {
+            return parse_file(text, self.dialect);
+        }
+        let relexed = relex(self, edit, text);
+        match incremental_reparse(self, edit, &relexed, text) {
+            Some(parse) => parse,
+            None => parse_tokens(relexed.tokens, text, self.dialect),
+        }
+    }
+}
+
+/// The tokens of the text after an edit.
+struct Relexed {
+    tokens: Vec<(SyntaxKind, usize)>,
+    /// The number of leading tokens that didn't change.
+    prefix: usize,
+    /// The number of trailing tokens that didn't change.
+    suffix: usize,
+}
+
+/// Walks the tokens of a parse, keeping track of the indentation levels of the blocks
+/// open at each token, as the lexer did when producing them.
+#[derive(Clone)]
+struct TokenWalk<'a> {
+    root: &'a SyntaxNode,
+    tokens: &'a [(SyntaxKind, usize)],
+    index: usize,
+    offset: usize,
+    indent_levels: Vec<usize>,
+}
+
+impl<'a> TokenWalk<'a> {
+    fn new(root: &'a SyntaxNode, tokens: &'a [(SyntaxKind, usize)]) -> Self {
+        TokenWalk {
+            root,
+            tokens,
+            index: 0,
+            offset: 0,
+            indent_levels: Vec::new(),
+        }
+    }
+
+    fn done(&self) -> bool {
+        self.index == self.tokens.len()
+    }
+
+    /// Whether the next token starts a line outside of brackets. The lexer only emits
+    /// newline tokens outside of brackets.
+    fn at_line_start(&self) -> bool {
+        self.index == 0 || self.tokens[self.index - 1] == (NEWLINE, 1)
+    }
+
+    fn step(&mut self) {
+        let (kind, len) = self.tokens[self.index];
+        match kind {
+            INDENT => {
+                // The INDENT token follows the whitespace indenting its line.
+                let indent = match self.index.checked_sub(1).map(|i| self.tokens[i]) {
+                    Some((WHITESPACE, len)) => {
+                        let range = TextRange::at(
+                            TextSize::try_from(self.offset - len).unwrap(),
+                            TextSize::try_from(len).unwrap(),
+                        );
+                        match self.root.covering_element(range) {
+                            NodeOrToken::Token(token) => indent_level(token.text()),
+                            NodeOrToken::Node(node) => indent_level(&node.text().to_string()),
+                        }
+                    }
+                    _ => 0,
+                };
+                self.indent_levels.push(indent);
+            }
+            OUTDENT => {
+                self.indent_levels.pop();
+            }
+            _ => {}
+        }
+        self.index += 1;
+        self.offset += len;
+    }
+}
+
+/// Lexes `text`, the text of `parse` after `edit`, reusing the tokens of `parse` before
+/// the line the edit begins on and after the first line past the edit that the lexer
+/// starts in the same state as before.
+fn relex(parse: &Parse, edit: &TextEdit, text: &str) -> Relexed {
+    let root = parse.syntax();
+    let old_tokens = &parse.tokens[..];
+    let edit_start = usize::from(edit.delete.start());
+    let edit_end = usize::from(edit.delete.end());
+    let insert_end = edit_start + edit.insert.len();
+
+    let mut old = TokenWalk::new(&root, old_tokens);
+    let mut restart = old.clone();
+    while !old.done() && old.offset <= edit_start {
+        if old.at_line_start() {
+            restart = old.clone();
+        }
+        old.step();
+    }
+
+    let mut old = restart;
+    let start = old.index;
+    let mut tokens = old_tokens[..start].to_vec();
+    let mut lexer = Lexer::resume(text, old.offset, old.indent_levels.clone());
+    let mut kept = 0;
+    while let Some(LexerReturn(token, _)) = lexer.next() {
+        tokens.push((token.kind, token.len));
+        let levels = match lexer.indent_levels() {
+            Some(levels) if lexer.pos() >= insert_end => levels,
+            _ => continue,
+        };
+        // The text from here on is the same as from `old_pos` in the old text.
+        let old_pos = lexer.pos() - insert_end + edit_end;
+        while !old.done() && old.offset < old_pos {
+            old.step();
+        }
+        if old.offset == old_pos && old.at_line_start() && old.indent_levels == levels {
+            kept = old_tokens.len() - old.index;
+            tokens.extend_from_slice(&old_tokens[old.index..]);
+            break;
+        }
+    }
+
+    // Tokens next to the edit may not have changed either.
+    let prefix = start
+        + tokens[start..]
+            .iter()
+            .zip(&old_tokens[start..])
+            .take_while(|(new, old)| new == old)
+            .count();
+    let suffix = kept
+        + tokens[..tokens.len() - kept]
+            .iter()
+            .rev()
+            .zip(old_tokens[..old_tokens.len() - kept].iter().rev())
+            .take_while(|(new, old)| new == old)
+            .count();
+    Relexed {
+        tokens,
+        prefix,
+        suffix,
+    }
+}
+
+/// Tries to reparse only part of the file, returning `None` if a full parse is needed.
+fn incremental_reparse(
+    parse: &Parse,
+    edit: &TextEdit,
+    relexed: &Relexed,
+    text: &str,
+) -> Option<Parse> {
+    let root = parse.syntax();
+    let old_tokens = &parse.tokens;
+    let new_tokens = &relexed.tokens;
+
+    // Tokens before `prefix` and after the last `suffix` tokens didn't change. A node can
+    // only be reparsed on its own if all changed tokens lie within it.
+    let (prefix, suffix) = (relexed.prefix, relexed.suffix);
+
+    let mut offsets = Vec::with_capacity(old_tokens.len());
+    let mut offset = 0;
+    for (_, len) in old_tokens.iter() {
+        offsets.push(offset);
+        offset += len;
+    }
+
+    let start = match root.covering_element(edit.delete) {
+        NodeOrToken::Node(node) => node,
+        NodeOrToken::Token(token) => token.parent()?,
+    };
+
+    for node in start.ancestors() {
+        let reparser = match reparser(node.kind()) {
+            Some(reparser) => reparser,
+            None => continue,
+        };
+
+        let range = node.text_range();
+        if range.start() > edit.delete.start() || range.end() < edit.delete.end() {
+            continue;
+        }
+        // Whether a call is recursive depends on the names bound anywhere in the
+        // functions around it, so those are validated as a whole.
+        if !parse.dialect.recursion && node.ancestors().skip(1).any(|it| it.kind() == DEF_STMT) {
+            continue;
+        }
+
+        let first = token_index(&offsets, &node.first_token()?);
+        let last = token_index(&offsets, &node.last_token()?) + 1;
+        if first > prefix || old_tokens.len() - last > suffix {
+            continue;
+        }
+        let new_last = match (last + new_tokens.len()).checked_sub(old_tokens.len()) {
+            Some(new_last) if new_last > first => new_last,
+            _ => continue,
+        };
+
+        if let Some(parse) = reparse_node(
+            parse,
+            &node,
+            reparser,
+            new_tokens,
+            first..new_last,
+            edit,
+            text,
+        ) {
+            return Some(parse);
+        }
+    }
+
+    None
+}
+
+/// Returns the function that parses nodes of the given kind. Every node of that kind
+/// must be produced by a call to that function starting at the node's first token.
+fn reparser(kind: SyntaxKind) -> Option<fn(&mut Parser)> {
+    let reparser = match kind {
+        SIMPLE_STMT | DEF_STMT | IF_STMT | FOR_STMT | WHILE_STMT => statement,
+        SUITE => suite,
+        LIST_EXPR | LIST_COMP => list_expr_or_comp,
+        DICT_EXPR | DICT_COMP | SET_EXPR => dict_expr_or_comp,
+        _ => return None,
+    };
+    Some(reparser)
+}
+
+/// Finds the index of `token` in the token stream, given the start offsets of all tokens.
+fn token_index(offsets: &[usize], token: &SyntaxToken) -> usize {
+    let start = usize::from(token.text_range().start());
+
+    // Zero-width tokens such as INDENT and OUTDENT share their offset with the token that
+    // follows them, so skip over the ones preceding `token`.
+    let mut index = offsets.partition_point(|&offset| offset < start);
+    let mut prev = token.prev_token();
+    while let Some(token) = prev {
+        if usize::from(token.text_range().start()) != start {
+            break;
+        }
+        index += 1;
+        prev = token.prev_token();
+    }
+    index
+}
+
+fn reparse_node(
+    parse: &Parse,
+    node: &SyntaxNode,
+    reparser: fn(&mut Parser),
+    tokens: &[(SyntaxKind, usize)],
+    range: std::ops::Range<usize>,
+    edit: &TextEdit,
+    text: &str,
+) -> Option<Parse> {
+    let old_range = node.text_range();
+    let start = usize::from(old_range.start());
+    let len: usize = tokens[range.clone()].iter().map(|(_, len)| len).sum();
+    let new_range = TextRange::at(old_range.start(), TextSize::try_from(len).ok()?);
+
+    // The reparsers assume they are called at a token that can start the node.
+    let first = tokens[range.start].0;
+    let can_start = match node.kind() {
+        SUITE => first == T!['\n'] || SMALL_STMT_START.contains(first),
+        LIST_EXPR | LIST_COMP => first == T!['['],
+        DICT_EXPR | DICT_COMP | SET_EXPR => first == T!['{'],
+        _ => true,
+    };
+    if !can_start {
+        return None;
+    }
+
+    // Errors reported right at the boundaries could belong to either the node or one of
+    // its ancestors, so we can't tell whether they need to be replaced.
+    if parse
+        .errors
+        .iter()
+        .any(|error| error.pos == start || error.pos == usize::from(old_range.end()))
+    {
+        return None;
+    }
+
+    let mut end = range.end;
+    let mut lookahead = 0;
+    while end < tokens.len() && lookahead < LOOKAHEAD {
+        if !tokens[end].0.is_whitespace() {
+            lookahead += 1;
+        }
+        end += 1;
+    }
+
+    let mut p = Parser::new(tokens[range.start..end].to_vec(), &text[start..]);
+    p.enter(ERROR);
+    reparser(&mut p);
+    if p.source_pos != range.len() {
+        return None;
+    }
+    p.exit();
+    p.builder.finish_node();
+
+    let wrapper = SyntaxNode::new_root(p.builder.finish());
+    let mut children = wrapper.children_with_tokens();
+    let green = match (children.next(), children.next()) {
+        (Some(NodeOrToken::Node(new_node)), None) if new_node.kind() == node.kind() => {
+            new_node.green().into_owned()
+        }
+        _ => return None,
+    };
+
+    let root = SyntaxNode::new_root(node.replace_with(green));
+    let new_node = match root.covering_element(new_range) {
+        NodeOrToken::Node(node) => node,
+        NodeOrToken::Token(token) => token.parent()?,
+    }
+    .ancestors()
+    .find(|it| it.text_range() == new_range && it.kind() == node.kind())?;
+
+    let delta = edit.insert.len() as isize - usize::from(edit.delete.len()) as isize;
+    let mut errors: Vec<_> = parse
+        .errors
+        .iter()
+        .filter(|error| error.pos < start)
+        .cloned()
+        .collect();
+    errors.extend(p.errors.into_iter().map(|mut error| {
+        error.pos += start;
+        error
+    }));
+    validate(&new_node, parse.dialect, &mut errors);
+    errors.extend(
+        parse
+            .errors
+            .iter()
+            .filter(|error| error.pos > usize::from(old_range.end()))
+            .map(|error| {
+                Diagnostic::new(error.message.clone(), (error.pos as isize + delta) as usize)
+            }),
+    );
+    errors.sort_by_key(|error| error.pos);
+
+    Some(Parse {
+        errors,
+        green: root.green().into_owned(),
+        tokens: tokens.into(),
+        dialect: parse.dialect,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_reparse(text: &str, edit: TextEdit, incremental: bool) {
+        let old = parse_file(text, Dialect::default());
+        let mut new_text = text.to_string();
+        edit.apply(&mut new_text);
+
+        assert_eq!(
+            old.reparse(&edit, &new_text),
+            parse_file(&new_text, Dialect::default())
+        );
+        let relexed = relex(&old, &edit, &new_text);
+        let reparsed = incremental_reparse(&old, &edit, &relexed, &new_text);
+        assert_eq!(reparsed.is_some(), incremental, "{:?}", new_text);
+    }
+
+    fn replace(text: &str, needle: &str, insert: &str) -> TextEdit {
+        let start = text.find(needle).unwrap();
+        let range = TextRange::at(
+            TextSize::try_from(start).unwrap(),
+            TextSize::try_from(needle.len()).unwrap(),
+        );
+        TextEdit::new(range, insert.to_string())
+    }
+
+    const TEXT: &str = r#"load("//foo:bar.bzl", "baz")
+
+def f(x, y = 1):
+    if x:
+        return [x, y]
+    for i in range(10):
+        y += {"a": i}
+    return y
+
+z = f(1, [
+    2,
+    3,
+])
+"#;
+
+    #[test]
+    fn reparse_statement() {
+        check_reparse(TEXT, replace(TEXT, "y += ", "y -= "), true);
+        check_reparse(TEXT, replace(TEXT, "return y", "return"), true);
+        check_reparse(TEXT, replace(TEXT, "\"baz\"", "\"baz\", \"qux\""), true);
+    }
+
+    #[test]
+    fn reparse_bracketed() {
+        check_reparse(TEXT, replace(TEXT, "    3,\n", "    3,\n    4,\n"), true);
+        check_reparse(TEXT, replace(TEXT, "[x, y]", "[x for x in y if x]"), true);
+    }
+
+    #[test]
+    fn reparse_keeps_errors() {
+        let text = "x = 1 +\ny = [1, 2]\nz = (3,)\n";
+        check_reparse(text, replace(text, "[1, 2]", "[1, 2, 3]"), true);
+        check_reparse(text, replace(text, "(3,)", "(3 4)"), true);
+        check_reparse(text, replace(text, "[1, 2]", "[1, 2"), false);
+    }
+
+    #[test]
+    fn reparse_revalidates_recursive_calls() {
+        let text = "def f():\n    x = 1\n    return f()\n";
+        let old = parse_file(text, Dialect::STANDARD);
+        let edit = replace(text, "x = 1", "f = g");
+        let mut new_text = text.to_string();
+        edit.apply(&mut new_text);
+        assert_eq!(old.errors.len(), 1);
+        assert_eq!(
+            incremental_reparse(&old, &edit, &relex(&old, &edit, &new_text), &new_text),
+            Some(parse_file(&new_text, Dialect::STANDARD))
+        );
+    }
+
+    #[test]
+    fn relex_stops_after_edit() {
+        let text = "def f():\n    x = 1\n    y = 2\nz = 3\n";
+        let old = parse_file(text, Dialect::default());
+        let edit = replace(text, "1", "[10,\n 11]");
+        let mut new_text = text.to_string();
+        edit.apply(&mut new_text);
+        // Tokens past the first line after the edit are taken from the old parse, so
+        // changing the text there goes unnoticed.
+        let changed_tail = new_text.replace("z = 3", "z = 'a'");
+        assert_eq!(relex(&old, &edit, &changed_tail).tokens, lex(&new_text));
+    }
+
+    #[test]
+    fn full_reparse_on_block_changes() {
+        check_reparse(TEXT, replace(TEXT, "    return y\n", "return y\n"), false);
+        check_reparse(TEXT, replace(TEXT, "[x, y]", "[x, y"), false);
+        check_reparse(TEXT, replace(TEXT, "bar.bzl", "bar.bzl\"\"\""), false);
+    }
+
+    /// Applies a long series of pseudo-random edits, checking that each incremental
+    /// reparse matches a full parse of the same text.
+    #[test]
+    fn reparse_matches_full_parse() {
+        const FRAGMENTS: &[&str] = &[
+            "x",
+            "1",
+            " ",
+            "\n",
+            "    ",
+            "(",
+            ")",
+            "[",
+            "]",
+            "{",
+            "}",
+            ":",
+            ",",
+            "=",
+            "+",
+            "\"",
+            "'",
+            "#",
+            "\\",
+            "if ",
+            "else",
+            "elif y:",
+            "for a in b",
+            "def g():\n",
+            "load(\"a\", \"b\")",
+            "lambda: 1",
+            "[i for i in x]",
+            "while ",
+            "{1, 2}",
+            "f'{x}'",
+            ": int",
+            " -> y",
+            "\n    pass\n",
+            "\t",
+        ];
+
+        let mut rng = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move |bound: usize| {
+            rng ^= rng << 13;
+            rng ^= rng >> 7;
+            rng ^= rng << 17;
+            (rng % bound as u64) as usize
+        };
+
+        let mut text = TEXT.to_string();
+        let mut parse = parse_file(&text, Dialect::default());
+        for i in 0..5000 {
+            if i % 100 == 0 {
+                text = TEXT.to_string();
+                parse = parse_file(&text, Dialect::default());
+            }
+
+            let start = next(text.len() + 1);
+            let end = (start + next(8)).min(text.len());
+            let insert = if next(3) == 0 {
+                String::new()
+            } else {
+                FRAGMENTS[next(FRAGMENTS.len())].to_string()
+            };
+            let edit = TextEdit::new(
+                TextRange::new(
+                    TextSize::try_from(start).unwrap(),
+                    TextSize::try_from(end).unwrap(),
+                ),
+                insert,
+            );
+
+            edit.apply(&mut text);
+            let reparsed = parse.reparse(&edit, &text);
+            assert_eq!(
+                reparsed,
+                parse_file(&text, Dialect::default()),
+                "{:?}",
+                text
+            );
+            parse = reparsed;
+        }
+    }
+}
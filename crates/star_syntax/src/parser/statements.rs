@@ -1,14 +1,54 @@
 use super::*;
 
-pub(crate) const SMALL_STMT_START: SyntaxKindSet =
-    SyntaxKindSet::new(&[T![return], T![break], T![continue], T![pass], T![load]])
-        .union(ATOM_EXPR_START);
+pub(crate) const SMALL_STMT_START: SyntaxKindSet = SyntaxKindSet::new(&[
+    T![return],
+    T![break],
+    T![continue],
+    T![pass],
+    T![load],
+    T![+],
+    T![-],
+    T![~],
+    T![not],
+    T![lambda],
+])
+.union(ATOM_EXPR_START);
 
 pub(crate) fn statement(p: &mut Parser) {
     match p.current() {
         T![def] => def_stmt(p),
+        T![if] => if_stmt(p),
+        T![for] => for_stmt(p),
+        T![while] => while_stmt(p),
         kind if SMALL_STMT_START.contains(kind) => simple_stmt(p),
         T!['\n'] => p.bump(T!['\n']),
+
+        // test_err unexpected_indent
+        // x = 1
+        //     y = 2
+        // z = 3
+        INDENT => {
+            p.error("Unexpected indentation");
+            p.enter(ERROR);
+            p.bump(INDENT);
+            while !p.at(EOF) && !p.at(OUTDENT) {
+                statement(p);
+            }
+            p.eat(OUTDENT);
+            p.exit();
+        }
+
+        // test_err else_without_if
+        // else:
+        //     pass
+        T![elif] => {
+            p.error("'elif' without matching 'if'");
+            p.error_and_recover(RECOVERY_SET);
+        }
+        T![else] => {
+            p.error("'else' without matching 'if'");
+            p.error_and_recover(RECOVERY_SET);
+        }
         _ => {
             p.error("expected statement");
             p.error_and_recover(RECOVERY_SET);
@@ -38,6 +78,7 @@ pub(crate) fn small_stmt(p: &mut Parser) {
         T![break] => break_stmt(p),
         T![continue] => continue_stmt(p),
         T![pass] => pass_stmt(p),
+        T![load] => load_stmt(p),
         kind if EXPR_START.contains(kind) => expr_or_assign_stmt(p),
         _ => {
             p.error_unexpected(p.current());
@@ -54,7 +95,17 @@ pub(crate) fn small_stmt(p: &mut Parser) {
 pub(crate) fn expr_or_assign_stmt(p: &mut Parser) {
     let checkpoint = p.checkpoint();
     expression_or_tuple(p, /* parens */ false, /* force_expr_list */ false);
-    if matches!(
+    // test assign_stmt_type_annotation
+    // x: int = 1
+    // y: dict[str, int | None] = {}
+    if p.at(T![:]) {
+        p.enter_at(checkpoint, ASSIGN_STMT);
+        type_annotation(p);
+        if p.expect(T![=]) {
+            expression_or_tuple(p, /* parens */ false, /* force_expr_list */ false);
+        }
+        p.exit();
+    } else if matches!(
         p.current(),
         T![=]
             | T![+=]
@@ -117,6 +168,75 @@ pub(crate) fn pass_stmt(p: &mut Parser) {
     p.exit();
 }
 
+// LoadStmt = 'load' '(' string {',' [identifier '='] string} [','] ')' .
+// test load_stmt
+// load("//foo:bar.bzl", "sym")
+// load("//foo:bar.bzl", "a", "b", c = "d",)
+// load(":defs.bzl", my_alias = "other", "sym")
+pub(crate) fn load_stmt(p: &mut Parser) {
+    p.enter(LOAD_STMT);
+    p.bump(T![load]);
+
+    // test_err load_stmt_missing_paren
+    // load "foo.bzl"
+    if !p.expect(T!['(']) {
+        p.exit();
+        return;
+    }
+
+    // test_err load_stmt_missing_module
+    // load()
+    // load(foo, "bar")
+    let has_module = p.eat(STRING);
+    if !has_module {
+        p.error("Expected module string in load statement");
+    }
+
+    // test_err load_stmt_no_symbols
+    // load("//foo:bar.bzl")
+    // load("//foo:bar.bzl",)
+    let mut has_symbols = false;
+    while p.at(T![,]) {
+        p.bump(T![,]);
+        match p.current() {
+            STRING => {
+                p.enter(LOAD_SYMBOL);
+                p.bump(STRING);
+                p.exit();
+            }
+            T![ident] => {
+                // test_err load_stmt_bad_alias
+                // load("//foo:bar.bzl", a = b)
+                // load("//foo:bar.bzl", a "b")
+                p.enter(LOAD_ALIAS);
+                p.bump(T![ident]);
+                if p.expect(T![=]) && !p.eat(STRING) {
+                    p.error("Expected symbol string after '='");
+                }
+                p.exit();
+            }
+            _ => break,
+        }
+        has_symbols = true;
+    }
+    if has_module && !has_symbols {
+        p.error("load statement must import at least one symbol");
+    }
+
+    let res = p.expect(T![')']);
+    p.exit();
+    if !res {
+        p.error_and_recover(RECOVERY_SET);
+    }
+
+    // Loads outside of the top level are reported during validation.
+    // test_err load_stmt_not_top_level
+    // def foo():
+    //     load("//foo:bar.bzl", "sym")
+    // if x:
+    //     load("//foo:bar.bzl", "sym")
+}
+
 // test def_stmt
 // def hello():
 //     pass
@@ -140,8 +260,11 @@ pub(crate) fn def_stmt(p: &mut Parser) {
         return;
     }
 
-    if p.at(T![ident]) {
-        parameters(p);
+    // test def_stmt_varargs_first
+    // def foo(*args, **kwargs):
+    //     pass
+    if PARAMETER_START.contains(p.current()) {
+        parameters(p, /* annotations */ true);
         p.eat(T![,]);
     }
 
@@ -156,53 +279,101 @@ pub(crate) fn def_stmt(p: &mut Parser) {
         p.exit();
     }
 
-    let mut checked = false;
+    if p.at(T![->]) {
+        p.enter(RETURN_TYPE);
+        p.bump(T![->]);
+        test(p, true);
+        p.exit();
+    }
 
-    // Check if we are at the ending ':'
-    if !p.at(T![:]) {
-        p.error("Expected ':'");
-        checked = true;
+    block(p);
+    p.exit();
+}
 
-        // If we don't have it, recover to the next ':' or '\n'
-        p.enter(ERROR);
-        while !p.at(EOF) && !p.at(T![:]) && !p.at(T!['\n']) {
-            p.bump_any();
-        }
-        p.exit();
+// test if_stmt
+// if x:
+//     pass
+// if x: pass
+// if x:
+//     pass
+// elif y:
+//     pass
+// elif z: pass
+// else:
+//     pass
+// if x:
+//     if y:
+//         pass
+//     else:
+//         pass
+pub(crate) fn if_stmt(p: &mut Parser) {
+    p.enter(IF_STMT);
+    p.bump(T![if]);
+
+    // test_err if_stmt_missing_condition
+    // if :
+    //     pass
+    // if x
+    //     pass
+    test(p, true);
+    block(p);
+
+    while p.at(T![elif]) {
+        p.bump(T![elif]);
+        test(p, true);
+        block(p);
     }
 
-    match p.current() {
-        T![:] => {
-            p.bump(T![:]);
-            match p.current() {
-                T!['\n'] => suite(p),
-                kind if SMALL_STMT_START.contains(kind) => suite(p),
-                _ => {
-                    p.enter(ERROR);
-                    while !p.at(EOF) && !p.at(T!['\n']) {
-                        p.bump_any();
-                    }
-                    p.exit();
-                    p.eat(T!['\n']);
-                    return;
-                }
-            }
-        }
-        T!['\n'] => {
-            if !checked {
-                p.error("expected ':'");
-            }
+    if p.eat(T![else]) {
+        block(p);
+    }
 
-            // If next token is INDENT, can parse suite. Otherwise, consume '\n' and finish.
-            if !p.nth_at(1, INDENT) {
-                p.bump(T!['\n']);
-                p.exit();
-                return;
-            }
-            suite(p);
+    p.exit();
+}
+
+// test for_stmt
+// for x in y:
+//     pass
+// for x, y in z: pass
+// for (x, y) in a, b:
+//     for z in x:
+//         continue
+//     break
+pub(crate) fn for_stmt(p: &mut Parser) {
+    p.enter(FOR_STMT);
+    p.bump(T![for]);
+    loop_variables(p);
+
+    // test_err for_stmt_missing_in
+    // for x y:
+    //     pass
+    // for x in :
+    //     pass
+    if p.expect(T![in]) {
+        if EXPR_START.contains(p.current()) {
+            expression_or_tuple(p, /* parens */ false, /* force_expr_list */ false);
+        } else {
+            p.error("expected expression");
         }
-        _ => {}
     }
+    block(p);
+
+    p.exit();
+}
+
+// test while_stmt
+// while x:
+//     x -= 1
+// while True: break
+pub(crate) fn while_stmt(p: &mut Parser) {
+    p.enter(WHILE_STMT);
+    p.bump(T![while]);
+
+    // test_err while_stmt_missing_condition
+    // while :
+    //     pass
+    test(p, true);
+    block(p);
 
     p.exit();
 }
@@ -21,3 +21,49 @@ pub(crate) fn suite(p: &mut Parser) {
     }
     p.exit();
 }
+
+/// Parses the `':' Suite` tail shared by all compound statements. This doesn't exit
+/// the enclosing statement node, which is left to the caller.
+pub(crate) fn block(p: &mut Parser) {
+    // Check if we are at the ending ':'
+    if !p.at(T![:]) {
+        p.error("Expected ':'");
+
+        // If we don't have it, recover to the next ':' or '\n'
+        if !p.at(EOF) && !p.at(T!['\n']) {
+            p.enter(ERROR);
+            while !p.at(EOF) && !p.at(T![:]) && !p.at(T!['\n']) {
+                p.bump_any();
+            }
+            p.exit();
+        }
+    }
+
+    match p.current() {
+        T![:] => {
+            p.bump(T![:]);
+            match p.current() {
+                T!['\n'] => suite(p),
+                kind if SMALL_STMT_START.contains(kind) => suite(p),
+                _ => {
+                    p.error_unexpected(p.current());
+                    p.enter(ERROR);
+                    while !p.at(EOF) && !p.at(T!['\n']) {
+                        p.bump_any();
+                    }
+                    p.exit();
+                    p.eat(T!['\n']);
+                }
+            }
+        }
+        T!['\n'] => {
+            // If next token is INDENT, can parse suite. Otherwise, consume '\n' and finish.
+            if !p.nth_at(1, INDENT) {
+                p.bump(T!['\n']);
+                return;
+            }
+            suite(p);
+        }
+        _ => {}
+    }
+}
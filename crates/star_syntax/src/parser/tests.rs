@@ -3,10 +3,10 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use expect_test::expect_file;
+use expect_test::{expect, expect_file, Expect};
 use runfiles::find_runfiles_dir;
 
-use crate::{parse_file, render};
+use crate::{parse_file, render, Dialect};
 
 fn project_root() -> PathBuf {
     find_runfiles_dir()
@@ -82,8 +82,101 @@ fn dir_tests() {
 }
 
 fn check(input: &str, expect_path: PathBuf) {
-    let parse = parse_file(input);
+    let parse = parse_file(input, Dialect::PERMISSIVE);
     let rendered = render(parse.syntax(), parse.errors);
     let expect = expect_file![expect_path];
     expect.assert_eq(&rendered);
 }
+
+fn check_errors(input: &str, dialect: Dialect, expect: Expect) {
+    let parse = parse_file(input, dialect);
+    let errors: String = parse
+        .errors()
+        .iter()
+        .map(|error| format!("{}:{}\n", error.pos, error.message))
+        .collect();
+    expect.assert_eq(&errors);
+}
+
+#[test]
+fn dialects() {
+    const TEXT: &str = r#"
+if x:
+    pass
+for x in y:
+    pass
+while x:
+    pass
+def f(x: int) -> int:
+    f(x)
+    def g():
+        return f(1)
+    for z in {1, 2}:
+        print(f"{z}")
+"#;
+    check_errors(
+        TEXT,
+        Dialect::STANDARD,
+        expect![[r#"
+        1:`if` statements at the top level are not allowed in this dialect
+        16:`for` statements at the top level are not allowed in this dialect
+        37:`while` loops are not allowed in this dialect
+        62:type annotations are not allowed in this dialect
+        69:type annotations are not allowed in this dialect
+        81:recursive calls are not allowed in this dialect
+        114:recursive calls are not allowed in this dialect
+        132:set literals are not allowed in this dialect
+        154:f-strings are not allowed in this dialect
+    "#]],
+    );
+    check_errors(
+        TEXT,
+        Dialect::BUCK2,
+        expect![[r#"
+        37:`while` loops are not allowed in this dialect
+        81:recursive calls are not allowed in this dialect
+        114:recursive calls are not allowed in this dialect
+        132:set literals are not allowed in this dialect
+    "#]],
+    );
+    check_errors(TEXT, Dialect::PERMISSIVE, expect![[r#""#]]);
+    check_errors(
+        r#"
+def f(x):
+    F"{x}"
+    return rf"{x}" + r"{x}"
+def g(g):
+    g(1)
+def h():
+    h = len
+    return h([])
+def k():
+    return lambda k: k() + [k() for k in []]
+def m():
+    return lambda: m()
+"#,
+        Dialect::STANDARD,
+        expect![[r#"
+            15:f-strings are not allowed in this dialect
+            33:f-strings are not allowed in this dialect
+            189:recursive calls are not allowed in this dialect
+        "#]],
+    );
+    check_errors(
+        "def f():\n    pass\n",
+        Dialect::BAZEL_BUILD,
+        expect![[r#"
+            0:`def` statements are not allowed in this dialect
+        "#]],
+    );
+    check_errors(
+        "def f():\n    def g():\n        pass\n",
+        Dialect {
+            nested_def: false,
+            ..Dialect::STANDARD
+        },
+        expect![[r#"
+            13:nested `def` statements are not allowed in this dialect
+        "#]],
+    );
+}
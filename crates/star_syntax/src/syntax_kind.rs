@@ -44,6 +44,7 @@ pub enum SyntaxKind {
     XOR_EQ,
     LT_LT_EQ,
     GT_GT_EQ,
+    ARROW,
     INT,
     FLOAT,
     STRING,
@@ -89,6 +90,7 @@ pub enum SyntaxKind {
     DEF_STMT,
     IF_STMT,
     FOR_STMT,
+    WHILE_STMT,
     SIMPLE_STMT,
     RETURN_STMT,
     BREAK_STMT,
@@ -97,6 +99,8 @@ pub enum SyntaxKind {
     ASSIGN_STMT,
     EXPR_STMT,
     LOAD_STMT,
+    LOAD_SYMBOL,
+    LOAD_ALIAS,
     EXPR,
     IF_EXPR,
     PRIMARY_EXPR,
@@ -106,9 +110,11 @@ pub enum SyntaxKind {
     LAMBDA_EXPR,
     DOT_EXPR,
     CALL_EXPR,
+    INDEX_EXPR,
     SLICE_EXPR,
     LIST_EXPR,
     DICT_EXPR,
+    SET_EXPR,
     LIST_COMP,
     LIST_COMP_FOR,
     LIST_COMP_IF,
@@ -117,6 +123,8 @@ pub enum SyntaxKind {
     ARGUMENT,
     PARAMETERS,
     PARAMETER,
+    TYPE_ANNOTATION,
+    RETURN_TYPE,
     ENTRIES,
     ENTRY,
     COMP_CLAUSE,
@@ -127,27 +135,29 @@ pub enum SyntaxKind {
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub(crate) struct SyntaxKindSet(u128);
+pub(crate) struct SyntaxKindSet([u128; 2]);
 
 impl SyntaxKindSet {
-    pub(crate) const EMPTY: SyntaxKindSet = SyntaxKindSet(0);
+    pub(crate) const EMPTY: SyntaxKindSet = SyntaxKindSet([0; 2]);
 
     pub(crate) const fn new(kinds: &[SyntaxKind]) -> SyntaxKindSet {
-        let mut inner = 0;
+        let mut inner = [0; 2];
         let mut i = 0;
         while i < kinds.len() {
-            inner |= 1 << kinds[i] as u16;
+            let kind = kinds[i] as usize;
+            inner[kind / 128] |= 1 << (kind % 128);
             i += 1;
         }
         SyntaxKindSet(inner)
     }
 
     pub(crate) const fn contains(&self, kind: SyntaxKind) -> bool {
-        self.0 & 1 << kind as usize > 0
+        let kind = kind as usize;
+        self.0[kind / 128] & 1 << (kind % 128) > 0
     }
 
     pub(crate) const fn union(&self, other: SyntaxKindSet) -> SyntaxKindSet {
-        SyntaxKindSet(self.0 | other.0)
+        SyntaxKindSet([self.0[0] | other.0[0], self.0[1] | other.0[1]])
     }
 }
 
@@ -165,6 +175,6 @@ impl From<SyntaxKind> for rowan::SyntaxKind {
 
 #[macro_export]
 macro_rules! T {
-    [;] => { $ crate :: SyntaxKind :: SEMICOLON } ; [:] => { $ crate :: SyntaxKind :: COLON } ; [-] => { $ crate :: SyntaxKind :: MINUS } ; [*] => { $ crate :: SyntaxKind :: STAR } ; [**] => { $ crate :: SyntaxKind :: STAR_STAR } ; [/] => { $ crate :: SyntaxKind :: SLASH } ; [/] => { $ crate :: SyntaxKind :: SLASH } ; [=] => { $ crate :: SyntaxKind :: EQ } ; [+=] => { $ crate :: SyntaxKind :: PLUS_EQ } ; [-=] => { $ crate :: SyntaxKind :: MINUS_EQ } ; [*=] => { $ crate :: SyntaxKind :: STAR_EQ } ; [/=] => { $ crate :: SyntaxKind :: SLASH_EQ } ; ["//="] => { $ crate :: SyntaxKind :: SLASH_SLASH_EQ } ; [%=] => { $ crate :: SyntaxKind :: MOD_EQ } ; [&=] => { $ crate :: SyntaxKind :: AND_EQ } ; [|=] => { $ crate :: SyntaxKind :: OR_EQ } ; [^=] => { $ crate :: SyntaxKind :: XOR_EQ } ; [<<=] => { $ crate :: SyntaxKind :: LT_LT_EQ } ; [>>=] => { $ crate :: SyntaxKind :: GT_GT_EQ } ; [whitespace] => { $ crate :: SyntaxKind :: WHITESPACE } ; [ident] => { $ crate :: SyntaxKind :: IDENT } ; [pass] => { $ crate :: SyntaxKind :: PASS_KW } ; [break] => { $ crate :: SyntaxKind :: BREAK_KW } ; [continue] => { $ crate :: SyntaxKind :: CONTINUE_KW } ; ['('] => { $ crate :: SyntaxKind :: L_PAREN } ; ['['] => { $ crate :: SyntaxKind :: L_BRACK } ; ['{'] => { $ crate :: SyntaxKind :: L_BRACE } ; [')'] => { $ crate :: SyntaxKind :: R_PAREN } ; [']'] => { $ crate :: SyntaxKind :: R_BRACK } ; [:] => { $ crate :: SyntaxKind :: COLON } ; [def] => { $ crate :: SyntaxKind :: DEF_KW } ; [,] => { $ crate :: SyntaxKind :: COMMA } ; ['\n'] => { $ crate :: SyntaxKind :: NEWLINE } ; [return] => { $ crate :: SyntaxKind :: RETURN_KW } ; [load] => { $ crate :: SyntaxKind :: LOAD_KW } ; [if] => { $ crate :: SyntaxKind :: IF_KW } ; [else] => { $ crate :: SyntaxKind :: ELSE_KW } ; [for] => { $ crate :: SyntaxKind :: FOR_KW } ; [lambda] => { $ crate :: SyntaxKind :: LAMBDA_KW } ; [not] => { $ crate :: SyntaxKind :: NOT_KW } ; [~] => { $ crate :: SyntaxKind :: TILDE } ; [+] => { $ crate :: SyntaxKind :: PLUS } ; [&] => { $ crate :: SyntaxKind :: AND } ; [|] => { $ crate :: SyntaxKind :: OR } ; [^] => { $ crate :: SyntaxKind :: XOR } ; [and] => { $ crate :: SyntaxKind :: AND_KW } ; [or] => { $ crate :: SyntaxKind :: OR_KW } ; [<<] => { $ crate :: SyntaxKind :: LT_LT } ; [>>] => { $ crate :: SyntaxKind :: GT_GT } ; [.] => { $ crate :: SyntaxKind :: DOT } ; ["//"] => { $ crate :: SyntaxKind :: SLASH_SLASH } ; [%] => { $ crate :: SyntaxKind :: MOD } ; [==] => { $ crate :: SyntaxKind :: EQ_EQ } ; [!=] => { $crate :: SyntaxKind :: BANG_EQ } ; [<] => { $crate :: SyntaxKind :: LT } ; [>] => { $crate :: SyntaxKind :: GT } ; [<=] => { $crate :: SyntaxKind :: LT_EQ } ; [>=] => { $crate :: SyntaxKind :: GT_EQ } ; [in] => { $crate :: SyntaxKind :: IN_KW } ;
+    [;] => { $ crate :: SyntaxKind :: SEMICOLON } ; [:] => { $ crate :: SyntaxKind :: COLON } ; [-] => { $ crate :: SyntaxKind :: MINUS } ; [*] => { $ crate :: SyntaxKind :: STAR } ; [**] => { $ crate :: SyntaxKind :: STAR_STAR } ; [/] => { $ crate :: SyntaxKind :: SLASH } ; [/] => { $ crate :: SyntaxKind :: SLASH } ; [=] => { $ crate :: SyntaxKind :: EQ } ; [+=] => { $ crate :: SyntaxKind :: PLUS_EQ } ; [-=] => { $ crate :: SyntaxKind :: MINUS_EQ } ; [*=] => { $ crate :: SyntaxKind :: STAR_EQ } ; [/=] => { $ crate :: SyntaxKind :: SLASH_EQ } ; ["//="] => { $ crate :: SyntaxKind :: SLASH_SLASH_EQ } ; [%=] => { $ crate :: SyntaxKind :: MOD_EQ } ; [&=] => { $ crate :: SyntaxKind :: AND_EQ } ; [|=] => { $ crate :: SyntaxKind :: OR_EQ } ; [^=] => { $ crate :: SyntaxKind :: XOR_EQ } ; [<<=] => { $ crate :: SyntaxKind :: LT_LT_EQ } ; [>>=] => { $ crate :: SyntaxKind :: GT_GT_EQ } ; [->] => { $ crate :: SyntaxKind :: ARROW } ; [whitespace] => { $ crate :: SyntaxKind :: WHITESPACE } ; [ident] => { $ crate :: SyntaxKind :: IDENT } ; [pass] => { $ crate :: SyntaxKind :: PASS_KW } ; [break] => { $ crate :: SyntaxKind :: BREAK_KW } ; [continue] => { $ crate :: SyntaxKind :: CONTINUE_KW } ; ['('] => { $ crate :: SyntaxKind :: L_PAREN } ; ['['] => { $ crate :: SyntaxKind :: L_BRACK } ; ['{'] => { $ crate :: SyntaxKind :: L_BRACE } ; [')'] => { $ crate :: SyntaxKind :: R_PAREN } ; [']'] => { $ crate :: SyntaxKind :: R_BRACK } ; ['}'] => { $ crate :: SyntaxKind :: R_BRACE } ; [:] => { $ crate :: SyntaxKind :: COLON } ; [def] => { $ crate :: SyntaxKind :: DEF_KW } ; [,] => { $ crate :: SyntaxKind :: COMMA } ; ['\n'] => { $ crate :: SyntaxKind :: NEWLINE } ; [return] => { $ crate :: SyntaxKind :: RETURN_KW } ; [load] => { $ crate :: SyntaxKind :: LOAD_KW } ; [if] => { $ crate :: SyntaxKind :: IF_KW } ; [elif] => { $ crate :: SyntaxKind :: ELIF_KW } ; [else] => { $ crate :: SyntaxKind :: ELSE_KW } ; [for] => { $ crate :: SyntaxKind :: FOR_KW } ; [while] => { $ crate :: SyntaxKind :: WHILE_KW } ; [lambda] => { $ crate :: SyntaxKind :: LAMBDA_KW } ; [not] => { $ crate :: SyntaxKind :: NOT_KW } ; [~] => { $ crate :: SyntaxKind :: TILDE } ; [+] => { $ crate :: SyntaxKind :: PLUS } ; [&] => { $ crate :: SyntaxKind :: AND } ; [|] => { $ crate :: SyntaxKind :: OR } ; [^] => { $ crate :: SyntaxKind :: XOR } ; [and] => { $ crate :: SyntaxKind :: AND_KW } ; [or] => { $ crate :: SyntaxKind :: OR_KW } ; [<<] => { $ crate :: SyntaxKind :: LT_LT } ; [>>] => { $ crate :: SyntaxKind :: GT_GT } ; [.] => { $ crate :: SyntaxKind :: DOT } ; ["//"] => { $ crate :: SyntaxKind :: SLASH_SLASH } ; [%] => { $ crate :: SyntaxKind :: MOD } ; [==] => { $ crate :: SyntaxKind :: EQ_EQ } ; [!=] => { $crate :: SyntaxKind :: BANG_EQ } ; [<] => { $crate :: SyntaxKind :: LT } ; [>] => { $crate :: SyntaxKind :: GT } ; [<=] => { $crate :: SyntaxKind :: LT_EQ } ; [>=] => { $crate :: SyntaxKind :: GT_EQ } ; [in] => { $crate :: SyntaxKind :: IN_KW } ;
 }
 pub use T;
@@ -0,0 +1,26 @@
+use rowan::{TextRange, TextSize};
+
+/// A single change to a document: the text in `delete` is replaced by `insert`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextEdit {
+    pub delete: TextRange,
+    pub insert: String,
+}
+
+impl TextEdit {
+    pub fn new(delete: TextRange, insert: String) -> TextEdit {
+        TextEdit { delete, insert }
+    }
+
+    pub fn insert(offset: TextSize, text: String) -> TextEdit {
+        TextEdit::new(TextRange::empty(offset), text)
+    }
+
+    pub fn delete(range: TextRange) -> TextEdit {
+        TextEdit::new(range, String::new())
+    }
+
+    pub fn apply(&self, text: &mut String) {
+        text.replace_range(std::ops::Range::<usize>::from(self.delete), &self.insert);
+    }
+}
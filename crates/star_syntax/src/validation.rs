@@ -0,0 +1,176 @@
+use crate::{
+    ast::{self, AstNode, AstToken},
+    Diagnostic, Dialect,
+    SyntaxKind::{self, *},
+    SyntaxNode,
+};
+use rowan::WalkEvent;
+
+/// Checks rules that the grammar alone doesn't capture, such as where certain
+/// statements are allowed to appear and which constructs `dialect` allows, for `node`
+/// and all of its descendants. Diagnostics are appended to `errors`.
+pub(crate) fn validate(node: &SyntaxNode, dialect: Dialect, errors: &mut Vec<Diagnostic>) {
+    for node in node.descendants() {
+        match node.kind() {
+            LOAD_STMT => validate_load_stmt(&node, errors),
+            _ => validate_dialect(&node, dialect, errors),
+        }
+    }
+}
+
+fn validate_load_stmt(node: &SyntaxNode, errors: &mut Vec<Diagnostic>) {
+    let pos = node.text_range().start().into();
+    if node.ancestors().any(|node| node.kind() == DEF_STMT) {
+        errors.push(Diagnostic::new(
+            "load statement is not allowed inside a function".to_string(),
+            pos,
+        ));
+    } else if node.ancestors().any(|node| node.kind() == SUITE) {
+        errors.push(Diagnostic::new(
+            "load statement must be at the top level of the file".to_string(),
+            pos,
+        ));
+    }
+}
+
+fn validate_dialect(node: &SyntaxNode, dialect: Dialect, errors: &mut Vec<Diagnostic>) {
+    let in_function = || {
+        node.ancestors()
+            .skip(1)
+            .any(|ancestor| ancestor.kind() == DEF_STMT)
+    };
+    let construct = match node.kind() {
+        IF_STMT if !dialect.top_level_control && !in_function() => {
+            "`if` statements at the top level"
+        }
+        FOR_STMT if !dialect.top_level_control && !in_function() => {
+            "`for` statements at the top level"
+        }
+        WHILE_STMT if !dialect.while_stmts => "`while` loops",
+        DEF_STMT if !dialect.def_stmts => "`def` statements",
+        DEF_STMT if !dialect.nested_def && in_function() => "nested `def` statements",
+        CALL_EXPR if !dialect.recursion && is_recursive_call(node) => "recursive calls",
+        SET_EXPR if !dialect.set_literals => "set literals",
+        LITERAL if !dialect.f_strings && is_f_string(node) => "f-strings",
+        TYPE_ANNOTATION | RETURN_TYPE if !dialect.type_annotations => "type annotations",
+        _ => return,
+    };
+    errors.push(Diagnostic::new(
+        format!("{} are not allowed in this dialect", construct),
+        node.text_range().start().into(),
+    ));
+}
+
+/// Checks whether `node` calls a function it is defined in, by a name that resolves to
+/// that function rather than to a local variable or parameter shadowing it.
+fn is_recursive_call(node: &SyntaxNode) -> bool {
+    let callee = match ast::CallExpr::cast(node.clone()).and_then(|call| call.expr()) {
+        Some(ast::Expr::Literal(literal)) => match literal.kind() {
+            ast::LiteralKind::Ident(ident) => ident.syntax().text().to_string(),
+            _ => return false,
+        },
+        _ => return false,
+    };
+    for scope in node
+        .ancestors()
+        .filter(|ancestor| is_scope(ancestor.kind()))
+    {
+        if binds(&scope, &callee) {
+            return false;
+        }
+        // The name of a function is bound in the scope enclosing it.
+        let def_name = ast::DefStmt::cast(scope).and_then(|def| def.name());
+        if matches!(def_name, Some(name) if name.syntax().text() == callee) {
+            return true;
+        }
+    }
+    false
+}
+
+fn is_scope(kind: SyntaxKind) -> bool {
+    matches!(kind, DEF_STMT | LAMBDA_EXPR | LIST_COMP | DICT_COMP)
+}
+
+/// Checks whether the function, lambda or comprehension `scope` binds `name` in its own
+/// scope, through a parameter, an assignment, a loop or a nested `def`.
+fn binds(scope: &SyntaxNode, name: &str) -> bool {
+    let parameters = match scope.kind() {
+        DEF_STMT => ast::DefStmt::cast(scope.clone()).and_then(|def| def.parameters()),
+        LAMBDA_EXPR => ast::LambdaExpr::cast(scope.clone()).and_then(|lambda| lambda.parameters()),
+        _ => None,
+    };
+    let is_parameter = parameters
+        .iter()
+        .flat_map(ast::Parameters::parameters)
+        .filter_map(|parameter| parameter.name())
+        .any(|ident| ident.syntax().text() == name);
+    if is_parameter {
+        return true;
+    }
+
+    let mut targets = Vec::new();
+    let mut preorder = scope.preorder();
+    while let Some(event) = preorder.next() {
+        let node = match event {
+            WalkEvent::Enter(node) => node,
+            WalkEvent::Leave(_) => continue,
+        };
+        if node != *scope && is_scope(node.kind()) {
+            if let Some(ident) = ast::DefStmt::cast(node).and_then(|def| def.name()) {
+                if ident.syntax().text() == name {
+                    return true;
+                }
+            }
+            preorder.skip_subtree();
+            continue;
+        }
+        match node.kind() {
+            ASSIGN_STMT => targets.extend(ast::AssignStmt::cast(node).and_then(|stmt| stmt.lhs())),
+            FOR_STMT => targets.extend(
+                ast::ForStmt::cast(node)
+                    .into_iter()
+                    .flat_map(|stmt| stmt.loop_variables()),
+            ),
+            LIST_COMP_FOR => targets.extend(
+                ast::CompFor::cast(node)
+                    .into_iter()
+                    .flat_map(|clause| clause.loop_variables()),
+            ),
+            _ => {}
+        }
+    }
+    targets.into_iter().any(|target| target_binds(target, name))
+}
+
+/// Checks whether the target of an assignment or a loop binds `name`.
+fn target_binds(target: ast::Expr, name: &str) -> bool {
+    match target {
+        ast::Expr::Literal(literal) => match literal.kind() {
+            ast::LiteralKind::Ident(ident) => ident.syntax().text() == name,
+            _ => false,
+        },
+        ast::Expr::TupleExpr(tuple) => tuple
+            .exprs()
+            .into_iter()
+            .any(|expr| target_binds(expr, name)),
+        ast::Expr::ListExpr(list) => list
+            .elements()
+            .into_iter()
+            .any(|expr| target_binds(expr, name)),
+        _ => false,
+    }
+}
+
+/// Checks whether a string literal has an `f` prefix, in either case and possibly
+/// combined with other prefixes, as in `F"..."` or `rf"..."`.
+fn is_f_string(node: &SyntaxNode) -> bool {
+    match ast::Literal::cast(node.clone()).map(|literal| literal.kind()) {
+        Some(ast::LiteralKind::String(string)) => string
+            .syntax()
+            .text()
+            .chars()
+            .take_while(|c| !matches!(c, '"' | '\''))
+            .any(|c| c.eq_ignore_ascii_case(&'f')),
+        _ => false,
+    }
+}